@@ -5,14 +5,21 @@ pub mod network;
 
 use network::{PacketData, SendResult, NetworkInterface, BatchTaskStatus, BatchTaskHandle, TaskMap, SnifferState, MonitorState, InterfaceManagerState, SequencePacket, PacketSequence, SequenceTaskStatus, SequenceTaskHandle, SequenceTaskMap};
 use network::interface::InterfaceInfo;
-use network::{SnifferManager, CapturedPacket, PacketStatistics, CaptureFilters, MonitorManager, TestConfig, TestResult, MonitoringStatistics};
-use network::InterfaceManager;
+use network::{CaptureSessions, CaptureSessionInfo, CapturedPacket, PacketStatistics, CaptureFilters, MonitorManager, TestConfig, TestResult, MonitoringStatistics, TracerouteHop};
+use network::{InterfaceManager, InterfaceSnapshot, IsolationMode, SnapshotDiff, StaleIsolation, StaleRestoreResult, VethPair};
+use network::capture_sessions::MONITOR_SESSION;
+use network::CorrelationReport;
+use network::{SignatureReport, SignatureStream, SignatureTracker};
+use network::interface_correlation::DEFAULT_MAX_LATENCY_US;
+use network::{StreamConversation, FollowedStream, EndpointStats, ConversationStats, RateHistory, RateSeriesSnapshot, RingBufferConfig, RingBufferStatus, FieldError, ProtocolSchema};
+use network::{ArpEntry, ArpHost, ArpScanConfig, GratuitousKind};
+use network::{ScanConfig, ScanResult, ScanTaskStatus, ScanTaskHandle, ScanTaskMap};
+use network::{BenchmarkConfig, BenchmarkStatus, BenchmarkTaskHandle, BenchmarkTaskMap, FrameSizeResult};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{Manager, State};
 use uuid::Uuid;
-use crate::network::interface::NetworkSender;
 // use crossbeam_channel::{bounded, select}; // 保留备用
 use std::sync::atomic::{AtomicU64, AtomicBool, Ordering};
 use std::sync::Mutex;
@@ -26,33 +33,7 @@ fn greet(name: &str) -> String {
 // Build packet bytes using the same builder as send; return uppercase hex string
 #[tauri::command]
 async fn build_packet_preview(packet_data: serde_json::Value) -> Result<String, String> {
-    let protocol = packet_data
-        .get("protocol")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| "缺少协议类型".to_string())?
-        .to_string();
-
-    let fields_obj = packet_data
-        .get("fields")
-        .and_then(|v| v.as_object())
-        .ok_or_else(|| "缺少字段数据".to_string())?;
-
-    let mut fields = HashMap::new();
-    for (k, v) in fields_obj.iter() {
-        match v {
-            Value::String(s) => { fields.insert(k.clone(), s.clone()); }
-            Value::Number(n) => { fields.insert(k.clone(), n.to_string()); }
-            Value::Bool(b) => { fields.insert(k.clone(), b.to_string()); }
-            _ => {}
-        }
-    }
-
-    let payload = packet_data
-        .get("payload")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let data = PacketData { protocol, fields, payload };
+    let data = packet_data_from_json(&packet_data)?;
     match network::PacketBuilder::new(data).build() {
         Ok(bytes) => {
             let mut hex = String::with_capacity(bytes.len() * 2);
@@ -63,40 +44,30 @@ async fn build_packet_preview(packet_data: serde_json::Value) -> Result<String,
     }
 }
 
+// 编辑器输入时调用，返回全部无效字段；空列表表示可以发送
 #[tauri::command]
-async fn send_packet(packet_data: Value, interface_name: Option<String>) -> Result<SendResult, String> {
-    // 将 JSON 数据转换为 PacketData
-    let protocol = packet_data["protocol"]
-        .as_str()
-        .ok_or("缺少协议类型")?
-        .to_string();
-    
-    let fields_value = packet_data["fields"]
-        .as_object()
-        .ok_or("缺少字段数据")?;
-    
-    let mut fields = HashMap::new();
-    for (key, value) in fields_value {
-        match value {
-            Value::String(s) => { fields.insert(key.clone(), s.clone()); }
-            Value::Number(n) => { fields.insert(key.clone(), n.to_string()); }
-            Value::Bool(b) => { fields.insert(key.clone(), b.to_string()); }
-            _ => {}
-        }
+fn validate_packet(packet_data: Value) -> Result<Vec<FieldError>, String> {
+    Ok(network::PacketBuilder::new(packet_data_from_json(&packet_data)?).validate())
+}
+
+// 字段定义的唯一来源，前端据此生成表单和校验；不传协议时返回全部协议
+#[tauri::command]
+fn get_packet_schema(protocol: Option<String>) -> Result<Vec<ProtocolSchema>, String> {
+    match protocol {
+        Some(p) => network::PacketBuilder::schema(&p)
+            .map(|s| vec![s])
+            .ok_or_else(|| format!("获取字段定义失败: 不支持的协议类型 {}", p)),
+        None => Ok(network::PacketBuilder::schemas()),
     }
-    
-    let payload = packet_data["payload"]
-        .as_str()
-        .map(|s| s.to_string());
-    
-    let packet_data = PacketData {
-        protocol,
-        fields,
-        payload,
-    };
-    
+}
+
+#[tauri::command]
+async fn send_packet(packet_data: Value, interface_name: Option<String>, interface_manager: State<'_, InterfaceManagerState>) -> Result<SendResult, String> {
+    let packet_data = packet_data_from_json(&packet_data)?;
+
     // 发送报文
-    match network::send_packet(packet_data, interface_name).await {
+    let namespace = interface_namespace(&interface_manager, interface_name.as_deref());
+    match network::send_packet(packet_data, interface_name, namespace).await {
         Ok(result) => Ok(result),
         Err(e) => Err(format!("发送失败: {}", e)),
     }
@@ -119,6 +90,123 @@ fn check_admin_privileges(interface_manager: State<'_, InterfaceManagerState>) -
     }
 }
 
+// 获取网卡隔离前的配置快照
+#[tauri::command]
+fn get_interface_snapshot(interface_name: String, interface_manager: State<'_, InterfaceManagerState>) -> Result<Option<InterfaceSnapshot>, String> {
+    let manager = interface_manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+    Ok(manager.snapshot(&interface_name).cloned())
+}
+
+// 比较网卡当前配置与快照，返回不一致的项
+#[tauri::command]
+async fn verify_interface_snapshot(interface_name: String, interface_manager: State<'_, InterfaceManagerState>) -> Result<Vec<SnapshotDiff>, String> {
+    let manager = interface_manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let manager = manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+        manager.verify_interface(&interface_name).map_err(|e| format!("校验网卡配置失败: {}", e))
+    })
+    .await
+    .map_err(|e| format!("校验网卡配置失败: {}", e))?
+}
+
+// 手动恢复仍处于隔离状态的网卡，返回恢复后与快照不一致的项
+#[tauri::command]
+async fn restore_interface_snapshot(interface_name: String, interface_manager: State<'_, InterfaceManagerState>) -> Result<Vec<SnapshotDiff>, String> {
+    let manager = interface_manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let mut manager = manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+        manager.restore_interface_verified(&interface_name).map_err(|e| format!("恢复网卡配置失败: {}", e))
+    })
+    .await
+    .map_err(|e| format!("恢复网卡配置失败: {}", e))?
+}
+
+// 列出上次运行遗留的隔离网卡以及恢复时将改变的配置
+#[tauri::command]
+async fn get_stale_isolations(interface_manager: State<'_, InterfaceManagerState>) -> Result<Vec<StaleIsolation>, String> {
+    let manager = interface_manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let manager = manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+        Ok(manager.stale_isolations())
+    })
+    .await
+    .map_err(|e| format!("检查遗留隔离网卡失败: {}", e))?
+}
+
+#[tauri::command]
+async fn restore_stale_interfaces(interface_manager: State<'_, InterfaceManagerState>) -> Result<Vec<StaleRestoreResult>, String> {
+    let manager = interface_manager.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let mut manager = manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+        Ok(manager.restore_stale_interfaces())
+    })
+    .await
+    .map_err(|e| format!("恢复遗留隔离网卡失败: {}", e))?
+}
+
+// 创建用于环回测试的 veth 对，未指定名称时随机生成
+#[tauri::command]
+async fn create_veth_pair(name: Option<String>) -> Result<VethPair, String> {
+    let name = name.unwrap_or_else(|| format!("bs{}", &Uuid::new_v4().simple().to_string()[..6]));
+    tokio::task::spawn_blocking(move || network::netns::create_veth_pair(&name))
+        .await
+        .map_err(|e| format!("创建 veth 对失败: {}", e))?
+        .map_err(|e| format!("创建 veth 对失败: {}", e))
+}
+
+#[tauri::command]
+async fn delete_veth_pair(name: String) -> Result<String, String> {
+    let deleted = name.clone();
+    tokio::task::spawn_blocking(move || network::netns::delete_veth_pair(&name))
+        .await
+        .map_err(|e| format!("删除 veth 对失败: {}", e))?
+        .map_err(|e| format!("删除 veth 对失败: {}", e))?;
+    Ok(format!("已删除 veth 对 {}", deleted))
+}
+
+// 命名空间隔离中的网卡所在的命名空间，发送和捕获句柄需在其中打开
+fn interface_namespace(interface_manager: &InterfaceManagerState, interface_name: Option<&str>) -> Option<String> {
+    interface_manager.lock().ok()?.namespace_of(interface_name?)
+}
+
+fn capture_namespaces(interface_manager: &InterfaceManagerState) -> HashMap<String, String> {
+    interface_manager.lock().map(|manager| manager.namespaces()).unwrap_or_default()
+}
+
+// 依赖宿主机地址和路由的功能拒绝已移入命名空间的网卡
+fn require_host_namespace(interface_manager: &InterfaceManagerState, interface_name: &str) -> Result<(), String> {
+    let manager = interface_manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+    manager.require_host_namespace(interface_name).map_err(|e| e.to_string())
+}
+
+// 前端传入的报文，字段值统一转为字符串；预览、校验和发送共用，校验的即是实际发送的内容
+fn packet_data_from_json(packet_data: &Value) -> Result<PacketData, String> {
+    let protocol = packet_data["protocol"]
+        .as_str()
+        .ok_or("缺少协议类型")?
+        .to_string();
+
+    let fields_value = packet_data["fields"]
+        .as_object()
+        .ok_or("缺少字段数据")?;
+
+    let mut fields = HashMap::new();
+    for (key, value) in fields_value {
+        match value {
+            Value::String(s) => { fields.insert(key.clone(), s.clone()); }
+            Value::Number(n) => { fields.insert(key.clone(), n.to_string()); }
+            Value::Bool(b) => { fields.insert(key.clone(), b.to_string()); }
+            _ => {}
+        }
+    }
+
+    let payload = packet_data["payload"]
+        .as_str()
+        .map(|s| s.to_string());
+
+    Ok(PacketData { protocol, fields, payload })
+}
+
 #[tauri::command]
 async fn start_batch_send(
     packet_data: serde_json::Value,
@@ -127,13 +215,18 @@ async fn start_batch_send(
     stop_condition: Option<String>,
     stop_value: Option<u32>,
     isolate_interface: Option<bool>,
+    isolation_mode: Option<IsolationMode>,
+    capture_session: Option<String>,
     state: State<'_, TaskMap>,
     interface_manager: State<'_, InterfaceManagerState>,
+    sniffer_state: State<'_, SnifferState>,
 ) -> Result<String, String> {
     use tokio::sync::oneshot;
 
     let task_id = Uuid::new_v4().to_string();
     let sent_count = Arc::new(AtomicU64::new(0));
+    let sent_bytes = Arc::new(AtomicU64::new(0));
+    let rates = Arc::new(Mutex::new(RateHistory::new()));
     let running = Arc::new(AtomicBool::new(true));
     let status = Arc::new(Mutex::new(BatchTaskStatus {
         task_id: task_id.clone(),
@@ -155,13 +248,39 @@ async fn start_batch_send(
     // 为主工作任务克隆所需变量
     let status_clone = status.clone();
     let sent_count_clone = sent_count.clone();
+    let sent_bytes_clone = sent_bytes.clone();
+    let rates_clone = rates.clone();
     let running_clone = running.clone();
-    let packet_data_clone = packet_data.clone();
+    let template = packet_data_from_json(&packet_data)?;
     let interface_name_clone = interface_name.clone();
     let stop_condition = stop_condition.unwrap_or_else(|| "manual".to_string());
     let stop_value = stop_value.unwrap_or(0);
     let isolate_interface = isolate_interface.unwrap_or(false);
 
+    // 在启动发送线程前校验模板，避免线程静默退出而命令已返回成功
+    let template_frames = network::PacketBuilder::new(template.clone())
+        .build_frames()
+        .map_err(|e| format!("构建数据包失败: {}", e))?;
+    // 签名追加在帧尾，分片报文无法携带
+    if capture_session.is_some() && template_frames.len() > 1 {
+        return Err("启用签名匹配时报文不能分片".to_string());
+    }
+
+    // 指定接收侧捕获会话时，发出的报文携带序号和时间戳签名，并在该会话的捕获流中匹配
+    let signature = match capture_session {
+        Some(session) => {
+            let sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+            let sniffer = sessions.get(Some(&session))
+                .filter(|sniffer| sniffer.is_running())
+                .ok_or_else(|| format!("捕获会话 {} 未在运行", session))?;
+            let stream = Arc::new(SignatureStream::new(Uuid::new_v4().as_u128() as u32));
+            let (receiver, capture_dropped) = sniffer.subscribe_counted();
+            let tracker = Arc::new(Mutex::new(SignatureTracker::new(stream, capture_dropped)));
+            Some((tracker, receiver))
+        }
+        None => None,
+    };
+
     // 如果需要隔离网卡，先执行隔离操作
     if isolate_interface {
         if let Some(ref iface_name) = interface_name_clone {
@@ -173,7 +292,7 @@ async fn start_batch_send(
             }
             
             // 执行隔离
-            if let Err(e) = manager.isolate_interface(iface_name) {
+            if let Err(e) = manager.isolate_interface_with_mode(iface_name, isolation_mode.unwrap_or_default()) {
                 return Err(format!("隔离网卡失败: {}", e));
             }
         } else {
@@ -181,6 +300,18 @@ async fn start_batch_send(
         }
     }
 
+    // 在开始发送前启动匹配线程，发送结束后它会等待在途报文再退出
+    let signature = signature.map(|(tracker, receiver)| {
+        let tracker_for_matcher = tracker.clone();
+        let running_for_matcher = running.clone();
+        std::thread::spawn(move || network::signature::run_signature_matcher(receiver, tracker_for_matcher, running_for_matcher));
+        tracker
+    });
+    let signature_stream = signature.as_ref().and_then(|tracker| tracker.lock().ok().map(|t| t.stream()));
+
+    // 命名空间隔离时发送句柄需在网卡所在的命名空间中打开
+    let namespace = interface_namespace(&interface_manager, interface_name_clone.as_deref());
+
     // 克隆接口管理器的引用以便在spawn_blocking中使用
     let interface_manager_clone = interface_manager.inner().clone();
 
@@ -194,15 +325,34 @@ async fn start_batch_send(
             _ => 8,            // 超高频：八线程
         };
 
-        // 状态统计线程
+        // 状态统计线程，在所有工作线程结束后再记录一次，补上最后一个间隔的增量
+        let workers_running = Arc::new(AtomicBool::new(true));
         let status_for_stats = status_clone.clone();
         let sent_for_stats = sent_count_clone.clone();
-        let running_for_stats = running_clone.clone();
-        std::thread::spawn(move || {
-            while running_for_stats.load(Ordering::Relaxed) {
+        let running_for_stats = workers_running.clone();
+        let bytes_for_stats = sent_bytes_clone.clone();
+        let rates_for_stats = rates_clone.clone();
+        let protocol_for_stats = template.protocol.clone();
+        let stats_handle = std::thread::spawn(move || {
+            let mut last_count = 0u64;
+            let mut last_bytes = 0u64;
+            loop {
+                let active = running_for_stats.load(Ordering::Relaxed);
+                let count = sent_for_stats.load(Ordering::Relaxed);
+                let bytes = bytes_for_stats.load(Ordering::Relaxed);
                 {
                     let mut s = status_for_stats.lock().unwrap();
-                    s.sent_count = sent_for_stats.load(Ordering::Relaxed);
+                    s.sent_count = count;
+                }
+                // 按增量记录到速率序列
+                if let Ok(mut r) = rates_for_stats.lock() {
+                    let now_ms = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+                    r.record(now_ms, &protocol_for_stats, count - last_count, bytes - last_bytes);
+                }
+                last_count = count;
+                last_bytes = bytes;
+                if !active {
+                    break;
                 }
                 std::thread::sleep(std::time::Duration::from_millis(100));
             }
@@ -215,14 +365,17 @@ async fn start_batch_send(
         let mut handles = vec![];
         for thread_id in 0..thread_count {
             let sent_for_thread = sent_count_clone.clone();
+            let bytes_for_thread = sent_bytes_clone.clone();
             let running_for_thread = running_clone.clone();
             let interface_for_thread = interface_name_clone.clone();
-            let packet_for_thread = packet_data_clone.clone();
+            let packet_for_thread = template.clone();
             let stop_condition_clone = stop_condition.clone();
+            let signature_for_thread = signature_stream.clone();
+            let namespace_for_thread = namespace.clone();
 
             handles.push(std::thread::spawn(move || {
                 // 初始化网络发送器
-                let mut sender = match NetworkSender::open(interface_for_thread.as_deref().unwrap_or_default()) {
+                let mut sender = match network::netns::open_sender(interface_for_thread.as_deref().unwrap_or_default(), namespace_for_thread.as_deref()) {
                     Ok(s) => s,
                     Err(_) => {
                         running_for_thread.store(false, Ordering::Relaxed);
@@ -230,31 +383,20 @@ async fn start_batch_send(
                     }
                 };
 
-                // 构建数据包
-                let fields_map = packet_for_thread["fields"].as_object().map(|o| {
-                    let mut m = HashMap::new();
-                    for (k, v) in o.iter() {
-                        match v {
-                            Value::String(s) => { m.insert(k.clone(), s.clone()); }
-                            Value::Number(n) => { m.insert(k.clone(), n.to_string()); }
-                            Value::Bool(b) => { m.insert(k.clone(), b.to_string()); }
-                            _ => {}
-                        }
-                    }
-                    m
-                }).unwrap_or_default();
-
-                let packet_bytes = match network::PacketBuilder::new(PacketData {
-                    protocol: packet_for_thread["protocol"].as_str().unwrap_or_default().to_string(),
-                    fields: fields_map,
-                    payload: packet_for_thread["payload"].as_str().map(String::from),
-                }).build() {
-                    Ok(bytes) => bytes,
+                // 启用分片时一个逻辑报文对应多个帧；模板已在启动前校验
+                let builder = network::PacketBuilder::new(packet_for_thread);
+                let seed_base = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(1) ^ thread_id as u64;
+                let mut datagrams = 0u64;
+                let built = builder.build().and_then(|packet| Ok((packet.clone(), builder.fragment_frames(packet, seed_base)?)));
+                let (packet, mut frames) = match built {
+                    Ok(built) => built,
                     Err(_) => {
                         running_for_thread.store(false, Ordering::Relaxed);
                         return;
                     }
                 };
+                // 当前分片组中下一个待发送的分片，发送失败后从这里继续
+                let mut next_fragment = 0usize;
 
                 // 计算每个线程的发送间隔（微秒）
                 let interval_micros = (1_000_000 * thread_count as u64) / frequency.max(1) as u64;
@@ -297,8 +439,39 @@ async fn start_batch_send(
                     }
                     
                     if now >= next_send_time {
-                        // 发送报文，支持错误重试
-                        match sender.send(&packet_bytes) {
+                        // 发送报文，支持错误重试；签名模式下每个报文重新追加签名并修正长度和校验和
+                        let result = match signature_for_thread.as_ref() {
+                            Some(stream) => {
+                                let mut frame = stream.stamp(&frames[0][..network::packet_builder::ipv4_frame_end(&frames[0])]);
+                                let result = builder.fixup_ipv4_frame(&mut frame).and_then(|_| {
+                                    sender.send(&frame)?;
+                                    bytes_for_thread.fetch_add(frame.len() as u64, Ordering::Relaxed);
+                                    Ok(())
+                                });
+                                // 已分配的序号未能发出，单独计数以免算作被测设备丢包
+                                if result.is_err() {
+                                    stream.send_failed();
+                                }
+                                result
+                            }
+                            // 一组分片中途发送失败时，下次从失败的分片继续，不重发已发出的分片
+                            None => frames[next_fragment..].iter().try_for_each(|frame| {
+                                sender.send(frame)?;
+                                bytes_for_thread.fetch_add(frame.len() as u64, Ordering::Relaxed);
+                                next_fragment += 1;
+                                Ok::<(), anyhow::Error>(())
+                            }),
+                        };
+                        // 整组发完后为下一个报文重新分片，乱序和重复分片每个报文各不相同
+                        if result.is_ok() && frames.len() > 1 {
+                            next_fragment = 0;
+                            datagrams += 1;
+                            let seed = seed_base.wrapping_add(datagrams.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                            if let Ok(next) = builder.fragment_frames(packet.clone(), seed) {
+                                frames = next;
+                            }
+                        }
+                        match result {
                             Ok(_) => {
                                 sent_for_thread.fetch_add(1, Ordering::Relaxed);
                             }
@@ -334,6 +507,8 @@ async fn start_batch_send(
         for handle in handles {
             let _ = handle.join();
         }
+        workers_running.store(false, Ordering::Relaxed);
+        let _ = stats_handle.join();
         
         // 任务结束，更新最终状态
         let mut final_status = status_clone.lock().unwrap();
@@ -359,6 +534,8 @@ async fn start_batch_send(
         stop_tx: Some(stop_tx),
         interface_name: interface_name.clone(),
         isolate_interface,
+        rates,
+        signature,
     });
 
     Ok(task_id)
@@ -370,6 +547,31 @@ fn get_batch_send_status(task_id: String, state: State<'_, TaskMap>) -> Option<B
     map.get(&task_id).map(|handle| handle.status.lock().unwrap().clone())
 }
 
+// interval_ms 为 100 或 1000，默认 1000；since_ms 用于增量拉取
+#[tauri::command]
+fn get_batch_send_rate_series(
+    task_id: String,
+    interval_ms: Option<u64>,
+    since_ms: Option<u64>,
+    state: State<'_, TaskMap>,
+) -> Result<RateSeriesSnapshot, String> {
+    let interval_ms = interval_ms.unwrap_or(1000);
+    let map = state.lock().map_err(|e| format!("获取任务列表失败: {}", e))?;
+    let handle = map.get(&task_id).ok_or_else(|| format!("未找到发送任务: {}", task_id))?;
+    let rates = handle.rates.lock().map_err(|e| format!("获取速率序列失败: {}", e))?;
+    rates.snapshot(interval_ms, since_ms).ok_or_else(|| format!("不支持的统计间隔: {}ms", interval_ms))
+}
+
+// 签名匹配结果：发出的报文中哪些被接收侧捕获到，以及单向时延、乱序和重复
+#[tauri::command]
+fn get_signature_report(task_id: String, state: State<'_, TaskMap>) -> Result<SignatureReport, String> {
+    let map = state.lock().map_err(|e| format!("获取任务列表失败: {}", e))?;
+    let handle = map.get(&task_id).ok_or_else(|| format!("未找到发送任务: {}", task_id))?;
+    let tracker = handle.signature.as_ref().ok_or_else(|| "该发送任务未启用签名匹配".to_string())?;
+    let tracker = tracker.lock().map_err(|e| format!("获取签名匹配结果失败: {}", e))?;
+    Ok(tracker.report())
+}
+
 #[tauri::command]
 fn stop_batch_send(
     task_id: String, 
@@ -407,6 +609,7 @@ async fn start_sequence_send(
     sequence: PacketSequence,
     interface_name: Option<String>,
     isolate_interface: Option<bool>,
+    isolation_mode: Option<IsolationMode>,
     sequence_state: State<'_, SequenceTaskMap>,
     interface_manager: State<'_, InterfaceManagerState>,
 ) -> Result<String, String> {
@@ -447,13 +650,14 @@ async fn start_sequence_send(
                 return Err("网卡隔离功能需要管理员权限".to_string());
             }
             
-            if let Err(e) = manager.isolate_interface(iface_name) {
+            if let Err(e) = manager.isolate_interface_with_mode(iface_name, isolation_mode.unwrap_or_default()) {
                 return Err(format!("隔离网卡失败: {}", e));
             }
         } else {
             return Err("使用网卡隔离功能时必须指定网卡名称".to_string());
         }
     }
+    let namespace = interface_namespace(&interface_manager, interface_name.as_deref());
 
     // 克隆变量以供异步任务使用
     let status_clone = status.clone();
@@ -467,10 +671,9 @@ async fn start_sequence_send(
     tokio::task::spawn_blocking(move || {
         use std::time::Duration;
         use crate::network::PacketBuilder;
-        use crate::network::interface::NetworkSender;
 
         // 初始化网络发送器
-        let mut sender = match NetworkSender::open(interface_name_clone.as_deref().unwrap_or_default()) {
+        let mut sender = match network::netns::open_sender(interface_name_clone.as_deref().unwrap_or_default(), namespace.as_deref()) {
             Ok(s) => s,
             Err(e) => {
                 eprintln!("初始化网络发送器失败: {}", e);
@@ -512,16 +715,16 @@ async fn start_sequence_send(
                     payload: packet.payload.clone(),
                 };
 
-                let packet_bytes = match PacketBuilder::new(packet_data).build() {
-                    Ok(bytes) => bytes,
+                let frames = match PacketBuilder::new(packet_data).build_frames() {
+                    Ok(frames) => frames,
                     Err(e) => {
                         eprintln!("构建数据包失败: {}", e);
                         continue;
                     }
                 };
 
-                // 发送数据包
-                match sender.send(&packet_bytes) {
+                // 发送数据包，分片后的各帧依次发出，计为一个数据包
+                match frames.iter().try_for_each(|frame| sender.send(frame)) {
                     Ok(_) => {
                         let mut status = status_clone.lock().unwrap();
                         status.total_packets_sent += 1;
@@ -613,18 +816,25 @@ fn stop_sequence_send(
     false
 }
 
-// 数据包嗅探相关命令
+// 数据包嗅探相关命令，session 为捕获会话名，未指定时使用默认会话
 #[tauri::command]
 async fn start_packet_capture(
     interface_name: String,
     filters: CaptureFilters,
-    sniffer_state: State<'_, SnifferState>
+    ring_buffer: Option<RingBufferConfig>,
+    session: Option<String>,
+    sniffer_state: State<'_, SnifferState>,
+    interface_manager: State<'_, InterfaceManagerState>
 ) -> Result<String, String> {
-    let mut sniffer = sniffer_state.lock().map_err(|e| {
+    if CaptureSessions::is_reserved(session.as_deref()) {
+        return Err(format!("捕获会话名 {} 保留给响应监控使用", MONITOR_SESSION));
+    }
+    let namespaces = capture_namespaces(&interface_manager);
+    let mut sessions = sniffer_state.lock().map_err(|e| {
         format!("获取嗅探器状态失败: {}", e)
     })?;
     
-    match sniffer.start_capture(interface_name.clone(), filters) {
+    match sessions.start_with(session.as_deref(), |sniffer| sniffer.start_capture(interface_name.clone(), &namespaces, filters, ring_buffer)) {
         Ok(()) => {
             Ok(format!("开始在接口 {} 上进行数据包捕获", interface_name))
         },
@@ -634,23 +844,64 @@ async fn start_packet_capture(
     }
 }
 
+// 在多个接口上同时捕获，报文按时间戳合并到同一会话
 #[tauri::command]
-async fn stop_packet_capture(sniffer_state: State<'_, SnifferState>) -> Result<String, String> {
-    let mut sniffer = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
-    sniffer.stop_capture();
+async fn start_multi_interface_capture(
+    interface_names: Vec<String>,
+    filters: CaptureFilters,
+    session: Option<String>,
+    sniffer_state: State<'_, SnifferState>,
+    interface_manager: State<'_, InterfaceManagerState>
+) -> Result<String, String> {
+    if CaptureSessions::is_reserved(session.as_deref()) {
+        return Err(format!("捕获会话名 {} 保留给响应监控使用", MONITOR_SESSION));
+    }
+    let namespaces = capture_namespaces(&interface_manager);
+    let mut sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+    let names = sessions
+        .start_with(session.as_deref(), |sniffer| {
+            sniffer.start_multi_capture(interface_names, &namespaces, filters, None)?;
+            Ok(sniffer.interface_names().join(", "))
+        })
+        .map_err(|e| format!("启动数据包捕获失败: {}", e))?;
+    Ok(format!("开始在接口 {} 上进行数据包捕获", names))
+}
+
+#[tauri::command]
+async fn stop_packet_capture(session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Result<String, String> {
+    let mut sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+    if let Some(sniffer) = sessions.get_mut(session.as_deref()) {
+        sniffer.stop_capture();
+    }
     Ok("数据包捕获已停止".to_string())
 }
 
 #[tauri::command]
-fn get_capture_status(sniffer_state: State<'_, SnifferState>) -> Result<bool, String> {
-    let sniffer = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
-    Ok(sniffer.is_running())
+fn get_capture_status(session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Result<bool, String> {
+    let sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+    Ok(sessions.get(session.as_deref()).is_some_and(|sniffer| sniffer.is_running()))
 }
 
 #[tauri::command]
-fn get_packet_statistics(sniffer_state: State<'_, SnifferState>) -> Option<PacketStatistics> {
-    if let Ok(sniffer) = sniffer_state.lock() {
-        sniffer.get_statistics()
+fn list_capture_sessions(sniffer_state: State<'_, SnifferState>) -> Vec<CaptureSessionInfo> {
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.list()
+    } else {
+        Vec::new()
+    }
+}
+
+// 停止并删除捕获会话，释放其缓存和统计
+#[tauri::command]
+fn close_capture_session(session: String, sniffer_state: State<'_, SnifferState>) -> Result<bool, String> {
+    let mut sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+    Ok(sessions.remove(Some(&session)))
+}
+
+#[tauri::command]
+fn get_packet_statistics(session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Option<PacketStatistics> {
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())?.get_statistics()
     } else {
         None
     }
@@ -659,26 +910,15 @@ fn get_packet_statistics(sniffer_state: State<'_, SnifferState>) -> Option<Packe
 #[tauri::command]
 fn get_captured_packets(
     max_count: Option<usize>, 
-    sniffer_state: State<'_, SnifferState>,
-    monitor_state: State<'_, MonitorState>
+    session: Option<String>,
+    sniffer_state: State<'_, SnifferState>
 ) -> Vec<CapturedPacket> {
     let max_count = max_count.unwrap_or(100);
     
-    if let Ok(sniffer) = sniffer_state.lock() {
-        let packets = sniffer.get_packets(max_count);
-        
-        // 只有在响应监控运行时才转发数据包，避免重复统计
-        // 数据包在捕获时已经被处理过一次，这里不应该重复处理
-        if let Ok(monitor) = monitor_state.lock() {
-            if monitor.is_running() {
-                // 只转发给响应监控器处理，不重复统计
-                for packet in &packets {
-                    monitor.process_received_packet(packet);
-                }
-            }
-        }
-        
-        packets
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.get_packets(max_count))
+            .unwrap_or_default()
     } else {
         Vec::new()
     }
@@ -688,76 +928,365 @@ fn get_captured_packets(
 fn get_filtered_packets(
     max_count: Option<usize>,
     protocol_filter: Option<String>,
-    sniffer_state: State<'_, SnifferState>,
-    monitor_state: State<'_, MonitorState>
+    session: Option<String>,
+    sniffer_state: State<'_, SnifferState>
 ) -> Vec<CapturedPacket> {
     let max_count = max_count.unwrap_or(100);
     
-    if let Ok(sniffer) = sniffer_state.lock() {
-        let packets = sniffer.get_filtered_packets(
-            max_count, 
-            protocol_filter.as_deref()
-        );
-        
-        // 将数据包转发给响应监控器处理
-        if let Ok(monitor) = monitor_state.lock() {
-            for packet in &packets {
-                monitor.process_received_packet(packet);
-            }
-        }
-        
-        packets
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.get_filtered_packets(max_count, protocol_filter.as_deref()))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+// 匹配各接口上捕获到的相同报文，统计转发时延
+#[tauri::command]
+fn get_interface_correlation(session: Option<String>, max_latency_us: Option<u64>, sniffer_state: State<'_, SnifferState>) -> CorrelationReport {
+    let max_latency_us = max_latency_us.unwrap_or(DEFAULT_MAX_LATENCY_US);
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.correlate_interfaces(max_latency_us))
+            .unwrap_or_default()
+    } else {
+        CorrelationReport::default()
+    }
+}
+
+// 流重组相关命令
+#[tauri::command]
+fn get_stream_conversations(session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Vec<StreamConversation> {
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.get_stream_conversations())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+#[tauri::command]
+fn follow_stream(flow_id: u64, session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Result<FollowedStream, String> {
+    let sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+    sessions.get(session.as_deref())
+        .and_then(|sniffer| sniffer.follow_stream(flow_id))
+        .ok_or_else(|| format!("未找到会话: {}", flow_id))
+}
+
+#[tauri::command]
+fn get_ring_buffer_status(session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Option<RingBufferStatus> {
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())?.get_ring_buffer_status()
+    } else {
+        None
+    }
+}
+
+#[tauri::command]
+fn get_capture_rate_series(
+    interval_ms: Option<u64>,
+    since_ms: Option<u64>,
+    session: Option<String>,
+    sniffer_state: State<'_, SnifferState>,
+) -> Result<RateSeriesSnapshot, String> {
+    let interval_ms = interval_ms.unwrap_or(1000);
+    let sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+    let sniffer = sessions.get(session.as_deref()).ok_or_else(|| "捕获会话不存在".to_string())?;
+    sniffer.get_rate_series(interval_ms, since_ms).ok_or_else(|| format!("不支持的统计间隔: {}ms", interval_ms))
+}
+
+// 端点/会话统计命令，kind 为 mac/ip（端点）或 ip/tcp/udp（会话）
+#[tauri::command]
+fn get_endpoint_statistics(kind: Option<String>, session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Vec<EndpointStats> {
+    let kind = kind.unwrap_or_else(|| "ip".to_string());
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.get_endpoint_statistics(&kind))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+#[tauri::command]
+fn get_conversation_statistics(kind: Option<String>, session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Vec<ConversationStats> {
+    let kind = kind.unwrap_or_else(|| "ip".to_string());
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.get_conversation_statistics(&kind))
+            .unwrap_or_default()
     } else {
         Vec::new()
     }
 }
 
+#[tauri::command]
+fn get_arp_table(session: Option<String>, sniffer_state: State<'_, SnifferState>) -> Vec<ArpEntry> {
+    if let Ok(sessions) = sniffer_state.lock() {
+        sessions.get(session.as_deref())
+            .map(|sniffer| sniffer.get_arp_table())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+// ARP 工具命令：扫描网段内的存活主机，发送免费 ARP
+#[tauri::command]
+async fn arp_scan(interface_name: String, config: ArpScanConfig, interface_manager: State<'_, InterfaceManagerState>) -> Result<Vec<ArpHost>, String> {
+    require_host_namespace(&interface_manager, &interface_name).map_err(|e| format!("ARP 扫描失败: {}", e))?;
+    tokio::task::spawn_blocking(move || network::arp::arp_scan(&interface_name, &config))
+        .await
+        .map_err(|e| format!("ARP 扫描失败: {}", e))?
+        .map_err(|e| format!("ARP 扫描失败: {}", e))
+}
+
+// 未指定 IP/MAC 时使用接口自身的地址
+#[tauri::command]
+async fn send_gratuitous_arp(
+    interface_name: String,
+    kind: GratuitousKind,
+    ip: Option<String>,
+    mac: Option<String>,
+    interface_manager: State<'_, InterfaceManagerState>,
+) -> Result<SendResult, String> {
+    let (local_mac, local_ip) = NetworkInterface::local_addresses(&interface_name);
+    let mac = mac.or(local_mac).ok_or_else(|| format!("发送免费 ARP 失败: 无法获取接口 {} 的 MAC 地址", interface_name))?;
+    let ip = ip.or(local_ip).ok_or_else(|| format!("发送免费 ARP 失败: 接口 {} 没有 IPv4 地址", interface_name))?;
+    let packet_data = network::arp::gratuitous_packet(kind, &mac, &ip);
+    let namespace = interface_namespace(&interface_manager, Some(&interface_name));
+    network::send_packet(packet_data, Some(interface_name), namespace)
+        .await
+        .map_err(|e| format!("发送免费 ARP 失败: {}", e))
+}
+
+// 主机发现与端口扫描命令
+#[tauri::command]
+async fn start_scan(
+    interface_name: String,
+    config: ScanConfig,
+    scan_state: State<'_, ScanTaskMap>,
+    interface_manager: State<'_, InterfaceManagerState>,
+) -> Result<String, String> {
+    require_host_namespace(&interface_manager, &interface_name).map_err(|e| format!("启动扫描失败: {}", e))?;
+    // 先校验目标和端口，避免任务启动后才报错
+    network::scanner::parse_targets(&config.targets).map_err(|e| format!("启动扫描失败: {}", e))?;
+    if matches!(config.scan_type, network::scanner::ScanType::TcpSyn | network::scanner::ScanType::Udp) {
+        network::scanner::parse_ports(&config.ports).map_err(|e| format!("启动扫描失败: {}", e))?;
+    }
+
+    let task_id = Uuid::new_v4().to_string();
+    let status = Arc::new(Mutex::new(network::scanner::new_status(&task_id, config.scan_type)));
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let status_clone = status.clone();
+    let results_clone = results.clone();
+    let running_clone = running.clone();
+    tokio::task::spawn_blocking(move || {
+        let outcome = network::scanner::run_scan(&interface_name, &config, &status_clone, &results_clone, &running_clone);
+        let mut status = status_clone.lock().unwrap();
+        if let Err(e) = outcome {
+            status.error = Some(e.to_string());
+        }
+        status.running = false;
+        status.completed = true;
+        status.completed_at = Some(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs());
+    });
+
+    let mut map = scan_state.lock().unwrap();
+    network::prune_finished(
+        &mut map,
+        SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs(),
+        |handle: &ScanTaskHandle| handle.status.lock().ok().and_then(|s| s.completed_at),
+    );
+    map.insert(task_id.clone(), ScanTaskHandle { status, results, running });
+    Ok(task_id)
+}
+
+#[tauri::command]
+fn get_scan_status(task_id: String, scan_state: State<'_, ScanTaskMap>) -> Option<ScanTaskStatus> {
+    let map = scan_state.lock().unwrap();
+    map.get(&task_id).map(|handle| handle.status.lock().unwrap().clone())
+}
+
+#[tauri::command]
+fn get_scan_results(task_id: String, scan_state: State<'_, ScanTaskMap>) -> Vec<ScanResult> {
+    let map = scan_state.lock().unwrap();
+    map.get(&task_id)
+        .map(|handle| handle.results.lock().unwrap().clone())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn stop_scan(task_id: String, scan_state: State<'_, ScanTaskMap>) -> bool {
+    let map = scan_state.lock().unwrap();
+    match map.get(&task_id) {
+        Some(handle) => {
+            handle.running.store(false, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+// RFC 2544 风格的吞吐量、时延和丢包率基准测试
+#[tauri::command]
+async fn start_benchmark(
+    config: BenchmarkConfig,
+    benchmark_state: State<'_, BenchmarkTaskMap>,
+    interface_manager: State<'_, InterfaceManagerState>,
+) -> Result<String, String> {
+    network::benchmark::validate(&config).map_err(|e| format!("启动基准测试失败: {}", e))?;
+    let namespaces = capture_namespaces(&interface_manager);
+
+    let task_id = Uuid::new_v4().to_string();
+    let status = Arc::new(Mutex::new(network::benchmark::new_status(&task_id)));
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let status_clone = status.clone();
+    let results_clone = results.clone();
+    let running_clone = running.clone();
+    tokio::task::spawn_blocking(move || {
+        let outcome = network::benchmark::run_benchmark(&config, &namespaces, &status_clone, &results_clone, &running_clone);
+        let mut status = status_clone.lock().unwrap();
+        if let Err(e) = outcome {
+            status.error = Some(e.to_string());
+        }
+        status.running = false;
+        status.completed = true;
+        status.completed_at = Some(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs());
+    });
+
+    let mut map = benchmark_state.lock().unwrap();
+    network::prune_finished(
+        &mut map,
+        SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs(),
+        |handle: &BenchmarkTaskHandle| handle.status.lock().ok().and_then(|s| s.completed_at),
+    );
+    map.insert(task_id.clone(), BenchmarkTaskHandle { status, results, running });
+    Ok(task_id)
+}
+
+#[tauri::command]
+fn get_benchmark_status(task_id: String, benchmark_state: State<'_, BenchmarkTaskMap>) -> Option<BenchmarkStatus> {
+    let map = benchmark_state.lock().unwrap();
+    map.get(&task_id).map(|handle| handle.status.lock().unwrap().clone())
+}
+
+#[tauri::command]
+fn get_benchmark_results(task_id: String, benchmark_state: State<'_, BenchmarkTaskMap>) -> Vec<FrameSizeResult> {
+    let map = benchmark_state.lock().unwrap();
+    map.get(&task_id)
+        .map(|handle| handle.results.lock().unwrap().clone())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_benchmark_report(task_id: String, benchmark_state: State<'_, BenchmarkTaskMap>) -> Result<String, String> {
+    let map = benchmark_state.lock().map_err(|e| format!("获取任务列表失败: {}", e))?;
+    let handle = map.get(&task_id).ok_or_else(|| format!("未找到基准测试任务: {}", task_id))?;
+    let results = handle.results.lock().map_err(|e| format!("获取基准测试结果失败: {}", e))?;
+    Ok(network::benchmark::format_report(&results))
+}
+
+#[tauri::command]
+fn stop_benchmark(task_id: String, benchmark_state: State<'_, BenchmarkTaskMap>) -> bool {
+    let map = benchmark_state.lock().unwrap();
+    match map.get(&task_id) {
+        Some(handle) => {
+            handle.running.store(false, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
 // 响应监控相关命令
 #[tauri::command]
 async fn start_response_monitoring(
     interface_name: String,
     test_config: TestConfig,
     monitor_state: State<'_, MonitorState>,
-    sniffer_state: State<'_, SnifferState>
+    sniffer_state: State<'_, SnifferState>,
+    interface_manager: State<'_, InterfaceManagerState>
 ) -> Result<String, String> {
-    // 调试信息已移除以避免崩溃
-    
-    // 首先启动数据包捕获以接收响应
-    {
-        let mut sniffer = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
-        
-        // 创建适合响应监控的捕获过滤器
-        // 先尝试捕获所有相关协议的数据包，不限制IP，以便调试
+    start_monitoring_session(interface_name, vec![test_config], monitor_state, sniffer_state, interface_manager).await
+}
+
+// 同时监控多个目标，各目标独立的测试类型、间隔和统计，共用一个捕获
+#[tauri::command]
+async fn start_monitoring_session(
+    interface_name: String,
+    targets: Vec<TestConfig>,
+    monitor_state: State<'_, MonitorState>,
+    sniffer_state: State<'_, SnifferState>,
+    interface_manager: State<'_, InterfaceManagerState>
+) -> Result<String, String> {
+    require_host_namespace(&interface_manager, &interface_name).map_err(|e| format!("启动响应监控失败: {}", e))?;
+    if monitor_state.lock().map_err(|e| format!("获取监控器状态失败: {}", e))?.is_running() {
+        return Err("启动响应监控失败: 响应监控已在运行中".to_string());
+    }
+
+    // 下一跳的 ARP 解析会阻塞，在启动捕获和持有监控器锁之前完成
+    let capture_protocol = network::response_monitor::capture_protocol(&targets);
+    let name = interface_name.clone();
+    let targets = tokio::task::spawn_blocking(move || network::response_monitor::resolve_targets(&name, &targets))
+        .await
+        .map_err(|e| format!("解析监控目标失败: {}", e))?
+        .map_err(|e| format!("启动响应监控失败: {}", e))?;
+
+    let monitor = monitor_state.lock().map_err(|e| format!("获取监控器状态失败: {}", e))?;
+    if monitor.is_running() {
+        return Err("启动响应监控失败: 响应监控已在运行中".to_string());
+    }
+
+    // 监控使用独立的捕获会话，不影响用户正在进行的捕获；
+    // 监控订阅捕获线程的数据包流，应答匹配不依赖前端轮询
+    let packets = {
+        let mut sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
         let filters = CaptureFilters {
-            protocol: Some(match test_config.test_type.as_str() {
-                "ping" => "icmp".to_string(),
-                "arp" => "arp".to_string(), 
-                "tcp_connect" => "tcp".to_string(),
-                "udp_echo" => "udp".to_string(),
-                _ => "icmp".to_string(), // 默认ICMP
-            }),
+            protocol: Some(capture_protocol),
             src_mac: None,
             dst_mac: None,
-            src_ip: None, // 暂时不限制源IP，捕获所有ICMP数据包用于调试
-            dst_ip: None, // 不限制目标IP
+            src_ip: None,
+            dst_ip: None,
             port: None,
         };
-        
-        // 调试信息已移除以避免崩溃
-        
-        // 启动数据包捕获
-        sniffer.start_capture(interface_name.clone(), filters).map_err(|e| {
+
+        sessions.start_with(Some(MONITOR_SESSION), |sniffer| {
+            sniffer.start_capture(interface_name.clone(), &HashMap::new(), filters, None)?;
+            Ok(sniffer.subscribe())
+        }).map_err(|e| {
             format!("启动数据包捕获失败: {}", e)
-        })?;
-    }
-    
-    let monitor = monitor_state.lock().map_err(|e| format!("获取监控器状态失败: {}", e))?;
-    
-    match monitor.start_monitoring(interface_name.clone(), test_config) {
+        })?
+    };
+
+    match monitor.start_session(interface_name.clone(), targets, packets) {
         Ok(()) => {
+            // 所有目标完成后监控自行结束，此时关闭监控专用的捕获会话；
+            // 检查时持有监控器锁，与重新启动监控互斥
+            let monitor_state = monitor_state.inner().clone();
+            let sniffer_state = sniffer_state.inner().clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                let Ok(monitor) = monitor_state.lock() else { break };
+                if !monitor.is_running() {
+                    if let Ok(mut sessions) = sniffer_state.lock() {
+                        sessions.remove(Some(MONITOR_SESSION));
+                    }
+                    break;
+                }
+            });
             Ok(format!("开始在接口 {} 上进行响应监控", interface_name))
         },
         Err(e) => {
+            if let Ok(mut sessions) = sniffer_state.lock() {
+                sessions.remove(Some(MONITOR_SESSION));
+            }
             Err(format!("启动响应监控失败: {}", e))
         }
     }
@@ -772,10 +1301,10 @@ async fn stop_response_monitoring(
     let monitor = monitor_state.lock().map_err(|e| format!("获取监控器状态失败: {}", e))?;
     monitor.stop_monitoring();
     
-    // 停止数据包捕获
+    // 停止监控专用的捕获会话
     {
-        let mut sniffer = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
-        sniffer.stop_capture();
+        let mut sessions = sniffer_state.lock().map_err(|e| format!("获取嗅探器状态失败: {}", e))?;
+        sessions.remove(Some(MONITOR_SESSION));
     }
     
     Ok("响应监控已停止".to_string())
@@ -807,6 +1336,15 @@ fn get_test_results(max_count: Option<usize>, monitor_state: State<'_, MonitorSt
     }
 }
 
+#[tauri::command]
+fn get_traceroute_hops(target: Option<String>, monitor_state: State<'_, MonitorState>) -> Vec<TracerouteHop> {
+    if let Ok(monitor) = monitor_state.lock() {
+        monitor.get_traceroute_hops(target.as_deref())
+    } else {
+        Vec::new()
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -815,32 +1353,81 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .manage(network::TaskMap::default())
         .manage(network::SequenceTaskMap::default())
-        .manage(SnifferState::new(Mutex::new(SnifferManager::new())))
+        .manage(network::ScanTaskMap::default())
+        .manage(network::BenchmarkTaskMap::default())
+        .manage(SnifferState::new(Mutex::new(CaptureSessions::new())))
         .manage(MonitorState::new(Mutex::new(MonitorManager::new())))
         .manage(InterfaceManagerState::new(Mutex::new(InterfaceManager::new().expect("无法初始化接口管理器"))))
+        .setup(|app| {
+            // 启用隔离快照持久化；程序在隔离期间异常退出时，遗留的网卡由前端列出并恢复
+            let data_dir = app.path().app_data_dir()?;
+            let interface_manager = app.state::<InterfaceManagerState>();
+            let mut manager = interface_manager.lock().map_err(|e| format!("无法获取接口管理器: {}", e))?;
+            match manager.load_persisted_snapshots(&data_dir) {
+                Ok(stale) if !stale.is_empty() => eprintln!("发现上次运行遗留的隔离网卡: {}", stale.join(", ")),
+                Ok(_) => {}
+                Err(e) => eprintln!("加载网卡快照失败: {}", e),
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             build_packet_preview,
+            validate_packet,
+            get_packet_schema,
             send_packet,
             get_network_interfaces,
             check_admin_privileges,
+            get_interface_snapshot,
+            verify_interface_snapshot,
+            restore_interface_snapshot,
+            get_stale_isolations,
+            restore_stale_interfaces,
+            create_veth_pair,
+            delete_veth_pair,
             start_batch_send,
             get_batch_send_status,
             stop_batch_send,
+            get_batch_send_rate_series,
+            get_signature_report,
             start_sequence_send,
             get_sequence_send_status,
             stop_sequence_send,
             start_packet_capture,
+            start_multi_interface_capture,
             stop_packet_capture,
             get_capture_status,
+            list_capture_sessions,
+            close_capture_session,
             get_packet_statistics,
+            get_capture_rate_series,
+            get_ring_buffer_status,
             get_captured_packets,
             get_filtered_packets,
+            get_interface_correlation,
+            get_stream_conversations,
+            follow_stream,
+            get_endpoint_statistics,
+            get_conversation_statistics,
+            get_arp_table,
+            arp_scan,
+            send_gratuitous_arp,
+            start_scan,
+            get_scan_status,
+            get_scan_results,
+            stop_scan,
+            start_benchmark,
+            get_benchmark_status,
+            get_benchmark_results,
+            get_benchmark_report,
+            stop_benchmark,
             start_response_monitoring,
+            start_monitoring_session,
             stop_response_monitoring,
             get_monitoring_status,
             get_monitoring_statistics,
-            get_test_results
+            get_test_results,
+            get_traceroute_hops
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
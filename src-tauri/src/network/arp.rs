@@ -0,0 +1,530 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crate::network::{PacketBuilder, PacketData};
+use crate::network::interface::NetworkInterface;
+
+pub const OPCODE_REQUEST: u16 = 1;
+pub const OPCODE_REPLY: u16 = 2;
+
+// 单次扫描的地址上限，避免误填 /8 一类的大网段
+pub const MAX_SCAN_HOSTS: usize = 65536;
+
+// ARP 表最大条目数
+const MAX_TABLE_ENTRIES: usize = 50000;
+
+const BROADCAST_MAC: &str = "ff:ff:ff:ff:ff:ff";
+const ZERO_MAC: &str = "00:00:00:00:00:00";
+
+// 以太网/IPv4 ARP 报文中解析出的字段，MAC 为小写冒号格式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArpInfo {
+    pub opcode: u16,
+    pub sender_mac: String,
+    pub sender_ip: String,
+    pub target_mac: String,
+    pub target_ip: String,
+}
+
+impl ArpInfo {
+    // data 从 ARP 头部开始；只解析以太网 + IPv4 的 ARP
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 28 || data[4] != 6 || data[5] != 4 {
+            return None;
+        }
+        Some(Self {
+            opcode: u16::from_be_bytes([data[6], data[7]]),
+            sender_mac: format_mac(&data[8..14]),
+            sender_ip: Ipv4Addr::new(data[14], data[15], data[16], data[17]).to_string(),
+            target_mac: format_mac(&data[18..24]),
+            target_ip: Ipv4Addr::new(data[24], data[25], data[26], data[27]).to_string(),
+        })
+    }
+
+    pub fn is_reply(&self) -> bool {
+        self.opcode == OPCODE_REPLY
+    }
+
+    // 发送方地址为 0.0.0.0 的请求，用于地址冲突检测（RFC 5227）
+    pub fn is_probe(&self) -> bool {
+        self.opcode == OPCODE_REQUEST && self.sender_ip == "0.0.0.0"
+    }
+
+    // 发送方与目标 IP 相同：免费 ARP / 地址通告
+    pub fn is_gratuitous(&self) -> bool {
+        self.sender_ip == self.target_ip && self.sender_ip != "0.0.0.0"
+    }
+
+    pub fn summary(&self) -> String {
+        match self.opcode {
+            OPCODE_REQUEST if self.is_probe() => format!("Who has {}? (ARP Probe)", self.target_ip),
+            OPCODE_REQUEST if self.is_gratuitous() => format!("ARP Announcement for {}", self.sender_ip),
+            OPCODE_REQUEST => format!("Who has {}? Tell {}", self.target_ip, self.sender_ip),
+            OPCODE_REPLY if self.is_gratuitous() => format!("Gratuitous ARP for {} (Reply) is at {}", self.sender_ip, self.sender_mac),
+            OPCODE_REPLY => format!("{} is at {}", self.sender_ip, self.sender_mac),
+            other => format!("ARP opcode {} {} -> {}", other, self.sender_ip, self.target_ip),
+        }
+    }
+}
+
+fn format_mac(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GratuitousKind {
+    // 发送方与目标 IP 相同的请求
+    Announcement,
+    // 发送方与目标 IP 相同的应答，目标 MAC 为自身（RFC 2002）
+    Reply,
+    // 发送方 IP 为 0.0.0.0 的请求，探测地址是否已被占用
+    Probe,
+}
+
+// ARP 请求，由 PacketBuilder 按 arp 字段构建
+pub fn request_packet(sender_mac: &str, sender_ip: &str, target_ip: &str) -> PacketData {
+    arp_packet(OPCODE_REQUEST, BROADCAST_MAC, sender_mac, sender_ip, ZERO_MAC, target_ip)
+}
+
+pub fn gratuitous_packet(kind: GratuitousKind, mac: &str, ip: &str) -> PacketData {
+    match kind {
+        GratuitousKind::Announcement => arp_packet(OPCODE_REQUEST, BROADCAST_MAC, mac, ip, ZERO_MAC, ip),
+        GratuitousKind::Reply => arp_packet(OPCODE_REPLY, BROADCAST_MAC, mac, ip, mac, ip),
+        GratuitousKind::Probe => arp_packet(OPCODE_REQUEST, BROADCAST_MAC, mac, "0.0.0.0", ZERO_MAC, ip),
+    }
+}
+
+fn arp_packet(opcode: u16, eth_dst: &str, sender_mac: &str, sender_ip: &str, target_mac: &str, target_ip: &str) -> PacketData {
+    let fields = [
+        ("dst_mac", eth_dst),
+        ("src_mac", sender_mac),
+        ("opcode", &opcode.to_string()),
+        ("srcMac", sender_mac),
+        ("srcIp", sender_ip),
+        ("dstMac", target_mac),
+        ("dstIp", target_ip),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    PacketData { protocol: "arp".to_string(), fields, payload: None }
+}
+
+// 解析 "地址/前缀" 或单个地址为主机地址列表；前缀小于 31 时去掉网络地址和广播地址
+pub fn parse_cidr_hosts(cidr: &str) -> Result<Vec<Ipv4Addr>> {
+    let cidr = cidr.trim();
+    let (addr, prefix) = cidr.split_once('/').unwrap_or((cidr, "32"));
+    let addr: Ipv4Addr = addr.trim().parse().map_err(|_| anyhow!("无效的 IPv4 地址: {}", addr))?;
+    let prefix: u32 = prefix.trim().parse().ok().filter(|p| *p <= 32).ok_or_else(|| anyhow!("无效的前缀长度: {}", prefix))?;
+
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+    let network = u32::from(addr) & mask;
+    let size = 1u64 << (32 - prefix);
+    let (first, count) = if prefix < 31 { (network as u64 + 1, size - 2) } else { (network as u64, size) };
+    if count as usize > MAX_SCAN_HOSTS {
+        return Err(anyhow!("网段 {} 包含 {} 个地址，超过 {} 个上限", cidr, count, MAX_SCAN_HOSTS));
+    }
+    Ok((first..first + count).map(|n| Ipv4Addr::from(n as u32)).collect())
+}
+
+// 常见厂商的 OUI（MAC 前 3 字节），仅覆盖局域网中常见的设备和虚拟化平台
+const OUI_VENDORS: &[([u8; 3], &str)] = &[
+    ([0x00, 0x50, 0x56], "VMware"),
+    ([0x00, 0x0C, 0x29], "VMware"),
+    ([0x00, 0x05, 0x69], "VMware"),
+    ([0x00, 0x1C, 0x14], "VMware"),
+    ([0x08, 0x00, 0x27], "Oracle VirtualBox"),
+    ([0x52, 0x54, 0x00], "QEMU/KVM"),
+    ([0x00, 0x16, 0x3E], "Xen"),
+    ([0x00, 0x15, 0x5D], "Microsoft Hyper-V"),
+    ([0xB8, 0x27, 0xEB], "Raspberry Pi"),
+    ([0xDC, 0xA6, 0x32], "Raspberry Pi"),
+    ([0xE4, 0x5F, 0x01], "Raspberry Pi"),
+    ([0x00, 0x00, 0x0C], "Cisco"),
+    ([0x00, 0xE0, 0x4C], "Realtek"),
+    ([0x00, 0x1B, 0x21], "Intel"),
+    ([0x3C, 0xFD, 0xFE], "Intel"),
+    ([0x00, 0xE0, 0xFC], "Huawei"),
+    ([0x00, 0x14, 0x22], "Dell"),
+    ([0x00, 0x03, 0x93], "Apple"),
+    ([0x00, 0x1B, 0x63], "Apple"),
+    ([0x24, 0x0A, 0xC4], "Espressif"),
+    ([0x30, 0xAE, 0xA4], "Espressif"),
+    ([0x00, 0x1A, 0x11], "Google"),
+    ([0x00, 0x17, 0x88], "Philips Lighting"),
+    ([0x18, 0xB4, 0x30], "Nest Labs"),
+    ([0x50, 0xC7, 0xBF], "TP-Link"),
+    ([0xF4, 0xF2, 0x6D], "TP-Link"),
+    ([0x00, 0x09, 0x0F], "Fortinet"),
+    ([0x00, 0x1C, 0x7F], "Check Point"),
+    ([0x00, 0x0D, 0xB9], "PC Engines"),
+];
+
+// 按 OUI 查找厂商；未收录的本地管理地址（随机化 MAC、容器等）单独标注
+pub fn vendor_for_mac(mac: &str) -> Option<String> {
+    let bytes: Vec<u8> = mac.split([':', '-']).filter_map(|p| u8::from_str_radix(p, 16).ok()).collect();
+    if bytes.len() != 6 {
+        return None;
+    }
+    if let Some((_, vendor)) = OUI_VENDORS.iter().find(|(oui, _)| oui[..] == bytes[..3]) {
+        return Some(vendor.to_string());
+    }
+    if bytes[0] == 0x02 && bytes[1] == 0x42 {
+        return Some("Docker".to_string());
+    }
+    if bytes[0] & 0x02 != 0 {
+        return Some("本地管理地址".to_string());
+    }
+    None
+}
+
+// 从抓包中学习到的 IP 与 MAC 对应关系
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArpEntry {
+    pub ip: String,
+    pub mac: String,
+    pub vendor: Option<String>,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub packets: u64,
+    // 同一 IP 此前使用过的其他 MAC，非空可能意味着地址冲突或 ARP 欺骗
+    pub previous_macs: Vec<String>,
+}
+
+pub struct ArpTable {
+    entries: HashMap<String, ArpEntry>,
+}
+
+impl ArpTable {
+    pub fn new() -> Self {
+        Self { entries: HashMap::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // 以发送方地址为准；探测报文的发送方 IP 为 0.0.0.0，不代表真实绑定
+    pub fn record(&mut self, arp: &ArpInfo, timestamp: u64) {
+        if arp.is_probe() || arp.sender_ip == "0.0.0.0" {
+            return;
+        }
+        if let Some(entry) = self.entries.get_mut(&arp.sender_ip) {
+            if entry.mac != arp.sender_mac {
+                let old = std::mem::replace(&mut entry.mac, arp.sender_mac.clone());
+                entry.previous_macs.retain(|m| *m != arp.sender_mac);
+                entry.previous_macs.push(old);
+                entry.vendor = vendor_for_mac(&entry.mac);
+            }
+            entry.last_seen = timestamp;
+            entry.packets += 1;
+            return;
+        }
+        if self.entries.len() >= MAX_TABLE_ENTRIES {
+            return;
+        }
+        self.entries.insert(
+            arp.sender_ip.clone(),
+            ArpEntry {
+                ip: arp.sender_ip.clone(),
+                mac: arp.sender_mac.clone(),
+                vendor: vendor_for_mac(&arp.sender_mac),
+                first_seen: timestamp,
+                last_seen: timestamp,
+                packets: 1,
+                previous_macs: Vec::new(),
+            },
+        );
+    }
+
+    // 按 IP 地址排序
+    pub fn entries(&self) -> Vec<ArpEntry> {
+        let mut entries: Vec<ArpEntry> = self.entries.values().cloned().collect();
+        entries.sort_by_key(|e| e.ip.parse::<Ipv4Addr>().map(u32::from).unwrap_or(0));
+        entries
+    }
+}
+
+impl Default for ArpTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArpScanConfig {
+    pub cidr: String,
+    // 最后一个请求发出后等待应答的时间
+    #[serde(default = "default_scan_timeout")]
+    pub timeout_ms: u64,
+    // 未应答地址的重发次数
+    #[serde(default)]
+    pub retries: u32,
+    // 相邻请求的间隔，避免瞬间突发
+    #[serde(default = "default_scan_interval")]
+    pub interval_us: u64,
+}
+
+fn default_scan_timeout() -> u64 {
+    1000
+}
+
+fn default_scan_interval() -> u64 {
+    1000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArpHost {
+    pub ip: String,
+    pub mac: String,
+    pub vendor: Option<String>,
+    // 最近一次请求到首个应答的时间（毫秒）
+    pub rtt_ms: f64,
+}
+
+// 向网段内每个地址发送 ARP 请求，返回应答的主机（按 IP 排序）；阻塞直到超时
+pub fn arp_scan(interface_name: &str, config: &ArpScanConfig) -> Result<Vec<ArpHost>> {
+    let targets = parse_cidr_hosts(&config.cidr)?;
+    arp_resolve(interface_name, &targets, config, &AtomicBool::new(true))
+}
+
+// 解析一组地址的 MAC；running 置为 false 时提前结束并返回已收到的应答
+pub fn arp_resolve(interface_name: &str, targets: &[Ipv4Addr], config: &ArpScanConfig, running: &AtomicBool) -> Result<Vec<ArpHost>> {
+    let (local_mac, local_ip) = NetworkInterface::local_addresses(interface_name);
+    let local_mac = local_mac.ok_or_else(|| anyhow!("无法获取接口 {} 的 MAC 地址", interface_name))?;
+    let local_ip = local_ip.ok_or_else(|| anyhow!("接口 {} 没有 IPv4 地址", interface_name))?;
+    let mut capture = NetworkInterface::open_probe_capture(interface_name, "arp")?;
+
+    let mut sent_at: HashMap<String, Instant> = HashMap::new();
+    let mut hosts: HashMap<String, ArpHost> = HashMap::new();
+    let wanted: HashSet<String> = targets.iter().map(|ip| ip.to_string()).collect();
+    let interval = Duration::from_micros(config.interval_us);
+
+    for _ in 0..=config.retries {
+        for target in targets {
+            let target = target.to_string();
+            if !running.load(Ordering::Relaxed) {
+                break;
+            }
+            if hosts.contains_key(&target) {
+                continue;
+            }
+            let frame = PacketBuilder::new(request_packet(&local_mac, &local_ip, &target)).build()?;
+            capture.sendpacket(frame).map_err(|e| anyhow!("发送 ARP 请求失败: {}", e))?;
+            sent_at.insert(target, Instant::now());
+            collect_replies(&mut capture, &wanted, &sent_at, &mut hosts);
+            if !interval.is_zero() {
+                std::thread::sleep(interval);
+            }
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(config.timeout_ms);
+        while Instant::now() < deadline && hosts.len() < wanted.len() && running.load(Ordering::Relaxed) {
+            if !collect_replies(&mut capture, &wanted, &sent_at, &mut hosts) {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+
+    let mut result: Vec<ArpHost> = hosts.into_values().collect();
+    result.sort_by_key(|h| h.ip.parse::<Ipv4Addr>().map(u32::from).unwrap_or(0));
+    Ok(result)
+}
+
+// 解析发往各目标的帧应使用的下一跳 MAC：本网段的目标解析自身地址，其余目标统一经由接口上的网关。
+// 路由器不转发二层广播帧，网段外的目标必须使用网关 MAC，网关未应答时返回错误；
+// 本网段内未应答 ARP 的目标不在结果中，由调用方决定是否广播
+pub fn resolve_next_hops(interface_name: &str, targets: &[Ipv4Addr], config: &ArpScanConfig, running: &AtomicBool) -> Result<HashMap<Ipv4Addr, String>> {
+    let networks = NetworkInterface::ipv4_networks(interface_name);
+    let on_link = |ip: Ipv4Addr| networks.iter().any(|(addr, mask)| u32::from(*addr) & u32::from(*mask) == u32::from(ip) & u32::from(*mask));
+    let (direct, remote): (Vec<Ipv4Addr>, Vec<Ipv4Addr>) = targets.iter().copied().partition(|t| on_link(*t));
+
+    let gateway = match remote.first() {
+        Some(first) => Some(
+            route_gateways(interface_name, *first)
+                .into_iter()
+                .find(|g| on_link(*g))
+                .ok_or_else(|| anyhow!("目标 {} 不在接口 {} 的网段内，且未找到该接口上的网关，请指定下一跳 MAC", first, interface_name))?,
+        ),
+        None => None,
+    };
+
+    let mut query = direct.clone();
+    query.extend(gateway);
+    let macs: HashMap<Ipv4Addr, String> = arp_resolve(interface_name, &query, config, running)?
+        .into_iter()
+        .filter_map(|h| Some((h.ip.parse().ok()?, h.mac)))
+        .collect();
+
+    let mut resolved: HashMap<Ipv4Addr, String> = direct.into_iter().filter_map(|t| Some((t, macs.get(&t)?.clone()))).collect();
+    if let Some(gateway) = gateway {
+        let mac = macs.get(&gateway).ok_or_else(|| anyhow!("网关 {} 未应答 ARP 请求，无法确定下一跳 MAC", gateway))?;
+        resolved.extend(remote.into_iter().map(|t| (t, mac.clone())));
+    }
+    Ok(resolved)
+}
+
+// 查询系统路由表中到达目标的网关候选
+fn route_gateways(interface_name: &str, target: Ipv4Addr) -> Vec<Ipv4Addr> {
+    let target = target.to_string();
+    let output = if cfg!(target_os = "linux") {
+        Command::new("ip").args(["-4", "route", "get", &target, "oif", interface_name]).output()
+    } else if cfg!(target_os = "macos") {
+        Command::new("route").args(["-n", "get", "-ifscope", interface_name, &target]).output()
+    } else {
+        // Windows 的接口名是 pcap 设备名，无法直接用于路由查询，取全部默认网关再按网段筛选
+        Command::new("route").args(["print", "-4", "0.0.0.0"]).output()
+    };
+    output.map(|o| parse_route_gateways(&String::from_utf8_lossy(&o.stdout))).unwrap_or_default()
+}
+
+// 兼容 ip route get、route -n get 和 route print 的输出
+fn parse_route_gateways(output: &str) -> Vec<Ipv4Addr> {
+    let mut gateways = Vec::new();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let candidate = match parts.as_slice() {
+            ["gateway:", gateway, ..] => Some(*gateway),
+            ["0.0.0.0", "0.0.0.0", gateway, ..] => Some(*gateway),
+            _ => parts.iter().position(|p| *p == "via").and_then(|i| parts.get(i + 1).copied()),
+        };
+        if let Some(gateway) = candidate.and_then(|g| g.parse().ok()) {
+            gateways.push(gateway);
+        }
+    }
+    gateways
+}
+
+// 读取当前可用的全部报文，返回是否读到了报文
+fn collect_replies(
+    capture: &mut pcap::Capture<pcap::Active>,
+    wanted: &HashSet<String>,
+    sent_at: &HashMap<String, Instant>,
+    hosts: &mut HashMap<String, ArpHost>,
+) -> bool {
+    let mut any = false;
+    while let Ok(packet) = capture.next_packet() {
+        any = true;
+        let data = packet.data;
+        if data.len() < 14 || data[12..14] != [0x08, 0x06] {
+            continue;
+        }
+        let Some(arp) = ArpInfo::parse(&data[14..]) else { continue };
+        if !arp.is_reply() || !wanted.contains(&arp.sender_ip) || hosts.contains_key(&arp.sender_ip) {
+            continue;
+        }
+        let rtt_ms = sent_at.get(&arp.sender_ip).map(|t| t.elapsed().as_secs_f64() * 1000.0).unwrap_or(0.0);
+        hosts.insert(
+            arp.sender_ip.clone(),
+            ArpHost { ip: arp.sender_ip.clone(), vendor: vendor_for_mac(&arp.sender_mac), mac: arp.sender_mac, rtt_ms },
+        );
+    }
+    any
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_gateways_from_each_platform() {
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        assert_eq!(parse_route_gateways("8.8.8.8 via 192.168.1.1 dev eth0 src 192.168.1.10 uid 0\n    cache\n"), vec![gateway]);
+        assert!(parse_route_gateways("192.168.1.20 dev eth0 src 192.168.1.10 uid 0\n").is_empty());
+        assert_eq!(parse_route_gateways("   route to: 8.8.8.8\n    gateway: 192.168.1.1\n  interface: en0\n"), vec![gateway]);
+        let windows = "\
+Network Destination        Netmask          Gateway       Interface  Metric
+          0.0.0.0          0.0.0.0      192.168.1.1    192.168.1.10     25
+          0.0.0.0          0.0.0.0         10.0.0.1        10.0.0.5     35
+";
+        assert_eq!(parse_route_gateways(windows), vec![gateway, Ipv4Addr::new(10, 0, 0, 1)]);
+    }
+
+    fn parse_built(packet: PacketData) -> ArpInfo {
+        let frame = PacketBuilder::new(packet).build().unwrap();
+        assert_eq!(&frame[12..14], &[0x08, 0x06]);
+        ArpInfo::parse(&frame[14..]).unwrap()
+    }
+
+    #[test]
+    fn request_and_reply_summaries() {
+        let request = parse_built(request_packet("00:11:22:33:44:55", "192.168.1.10", "192.168.1.1"));
+        assert_eq!(request.opcode, OPCODE_REQUEST);
+        assert_eq!(request.summary(), "Who has 192.168.1.1? Tell 192.168.1.10");
+
+        let reply = ArpInfo {
+            opcode: OPCODE_REPLY,
+            sender_ip: "192.168.1.1".to_string(),
+            target_ip: "192.168.1.10".to_string(),
+            ..request
+        };
+        assert!(reply.is_reply() && !reply.is_gratuitous());
+        assert_eq!(reply.summary(), "192.168.1.1 is at 00:11:22:33:44:55");
+    }
+
+    #[test]
+    fn gratuitous_variants() {
+        let mac = "aa:bb:cc:dd:ee:ff";
+        let announcement = parse_built(gratuitous_packet(GratuitousKind::Announcement, mac, "10.0.0.5"));
+        assert!(announcement.is_gratuitous() && !announcement.is_reply());
+        assert_eq!(announcement.target_mac, ZERO_MAC);
+        assert_eq!(announcement.summary(), "ARP Announcement for 10.0.0.5");
+
+        let reply = parse_built(gratuitous_packet(GratuitousKind::Reply, mac, "10.0.0.5"));
+        assert!(reply.is_gratuitous() && reply.is_reply());
+        assert_eq!(reply.target_mac, mac);
+
+        let probe = parse_built(gratuitous_packet(GratuitousKind::Probe, mac, "10.0.0.5"));
+        assert!(probe.is_probe() && !probe.is_gratuitous());
+        assert_eq!(probe.summary(), "Who has 10.0.0.5? (ARP Probe)");
+    }
+
+    #[test]
+    fn cidr_hosts() {
+        let hosts = parse_cidr_hosts("192.168.1.77/24").unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hosts[253], Ipv4Addr::new(192, 168, 1, 254));
+        assert_eq!(parse_cidr_hosts("10.0.0.0/31").unwrap().len(), 2);
+        assert_eq!(parse_cidr_hosts("10.0.0.9").unwrap(), vec![Ipv4Addr::new(10, 0, 0, 9)]);
+        assert!(parse_cidr_hosts("10.0.0.0/8").is_err());
+        assert!(parse_cidr_hosts("10.0.0.0/33").is_err());
+    }
+
+    #[test]
+    fn vendor_lookup() {
+        assert_eq!(vendor_for_mac("08:00:27:12:34:56").as_deref(), Some("Oracle VirtualBox"));
+        assert_eq!(vendor_for_mac("B8-27-EB-00-00-01").as_deref(), Some("Raspberry Pi"));
+        assert_eq!(vendor_for_mac("02:42:ac:11:00:02").as_deref(), Some("Docker"));
+        assert_eq!(vendor_for_mac("da:00:00:00:00:01").as_deref(), Some("本地管理地址"));
+        assert_eq!(vendor_for_mac("00:00:01:00:00:01"), None);
+    }
+
+    #[test]
+    fn table_tracks_mac_changes() {
+        let mut table = ArpTable::new();
+        let mut arp = ArpInfo {
+            opcode: OPCODE_REPLY,
+            sender_mac: "00:50:56:00:00:01".to_string(),
+            sender_ip: "10.0.0.1".to_string(),
+            target_mac: ZERO_MAC.to_string(),
+            target_ip: "10.0.0.2".to_string(),
+        };
+        table.record(&arp, 1);
+        arp.sender_mac = "00:50:56:00:00:02".to_string();
+        table.record(&arp, 2);
+        table.record(&ArpInfo { sender_ip: "0.0.0.0".to_string(), ..arp.clone() }, 3);
+
+        let entries = table.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].mac, "00:50:56:00:00:02");
+        assert_eq!(entries[0].previous_macs, vec!["00:50:56:00:00:01"]);
+        assert_eq!((entries[0].packets, entries[0].first_seen, entries[0].last_seen), (2, 1, 2));
+        assert_eq!(entries[0].vendor.as_deref(), Some("VMware"));
+    }
+}
@@ -0,0 +1,478 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use crate::network::{netns, PacketBuilder, PacketData};
+use crate::network::interface::{NetworkInterface, NetworkSender};
+use crate::network::rtt_stats::RttSummary;
+use crate::network::signature::{SignatureStream, SignatureTracker, SIGNATURE_LEN};
+
+// 帧长包含 4 字节 FCS，发送的帧不含 FCS
+const FCS_LEN: usize = 4;
+// 实际发送速率低于目标速率的该比例时，测试视为未通过
+const RATE_SHORTFALL: f64 = 0.01;
+// 接收侧内核缓冲区，高速率下避免捕获自身丢包
+const CAPTURE_BUFFER_BYTES: i32 = 64 * 1024 * 1024;
+const CAPTURE_TIMEOUT_MS: i32 = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkConfig {
+    pub send_interface: String,
+    pub receive_interface: String,
+    // 报文模板，按帧长在末尾补零并追加签名
+    pub packet: PacketData,
+    // 以太网帧长（含 FCS）
+    #[serde(default = "default_frame_sizes")]
+    pub frame_sizes: Vec<usize>,
+    // 吞吐量搜索的上限（帧/秒）
+    #[serde(default = "default_max_rate")]
+    pub max_rate_pps: u64,
+    #[serde(default = "default_trial_duration")]
+    pub trial_duration_ms: u64,
+    // 每次测试停止发送后等待在途报文的时间
+    #[serde(default = "default_settle")]
+    pub settle_ms: u64,
+    // 搜索区间缩小到最大速率的该百分比以内时结束
+    #[serde(default = "default_resolution")]
+    pub resolution_percent: f64,
+    // 允许的丢包率，RFC 2544 吞吐量要求为 0
+    #[serde(default)]
+    pub loss_tolerance_percent: f64,
+    // 在吞吐量的这些百分比下测量时延和丢包率
+    #[serde(default = "default_load_percentages")]
+    pub load_percentages: Vec<f64>,
+}
+
+fn default_frame_sizes() -> Vec<usize> {
+    vec![64, 128, 256, 512, 1024, 1280, 1518]
+}
+
+fn default_max_rate() -> u64 {
+    100_000
+}
+
+fn default_trial_duration() -> u64 {
+    2000
+}
+
+fn default_settle() -> u64 {
+    1000
+}
+
+fn default_resolution() -> f64 {
+    1.0
+}
+
+fn default_load_percentages() -> Vec<f64> {
+    vec![10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 100.0]
+}
+
+// 一次定速发送测试的结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrialResult {
+    pub rate_pps: u64,
+    pub sent: u64,
+    pub received: u64,
+    pub loss_percent: f64,
+    // 实际达到的发送速率，发送端跟不上时低于 rate_pps
+    pub offered_pps: f64,
+    pub latency: RttSummary,
+}
+
+impl TrialResult {
+    // 丢包在允许范围内，且发送端确实达到了目标速率
+    pub fn passed(&self, loss_tolerance_percent: f64) -> bool {
+        self.sent > 0
+            && self.loss_percent <= loss_tolerance_percent
+            && self.offered_pps >= self.rate_pps as f64 * (1.0 - RATE_SHORTFALL)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LoadResult {
+    pub percent: f64,
+    pub trial: TrialResult,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FrameSizeResult {
+    pub frame_size: usize,
+    // 满足丢包要求的最大速率，0 表示最低速率也有丢包
+    pub throughput_pps: u64,
+    // 按帧长（含 FCS）计算的比特率
+    pub throughput_mbps: f64,
+    // 二分搜索的各次测试
+    pub search: Vec<TrialResult>,
+    pub loads: Vec<LoadResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BenchmarkStatus {
+    pub task_id: String,
+    pub start_time: u64,
+    pub frame_size: Option<usize>,
+    // "throughput" 或 "load"
+    pub phase: Option<String>,
+    pub rate_pps: u64,
+    pub completed_trials: u64,
+    pub running: bool,
+    pub completed: bool,
+    // 完成时间（Unix 秒），超过保留时间后任务被清理
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+pub struct BenchmarkTaskHandle {
+    pub status: Arc<Mutex<BenchmarkStatus>>,
+    pub results: Arc<Mutex<Vec<FrameSizeResult>>>,
+    pub running: Arc<AtomicBool>,
+}
+
+pub fn new_status(task_id: &str) -> BenchmarkStatus {
+    BenchmarkStatus {
+        task_id: task_id.to_string(),
+        start_time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        frame_size: None,
+        phase: None,
+        rate_pps: 0,
+        completed_trials: 0,
+        running: true,
+        completed: false,
+        completed_at: None,
+        error: None,
+    }
+}
+
+pub fn validate(config: &BenchmarkConfig) -> Result<()> {
+    if config.frame_sizes.is_empty() {
+        return Err(anyhow!("未指定帧长"));
+    }
+    if config.max_rate_pps == 0 || config.trial_duration_ms == 0 {
+        return Err(anyhow!("最大速率和测试时长必须大于 0"));
+    }
+    if !(0.0..100.0).contains(&config.loss_tolerance_percent) {
+        return Err(anyhow!("允许丢包率必须在 0 到 100 之间"));
+    }
+    if config.load_percentages.iter().any(|p| !(*p > 0.0 && *p <= 100.0)) {
+        return Err(anyhow!("负载百分比必须在 0 到 100 之间"));
+    }
+    Ok(())
+}
+
+// 将报文模板补零到指定帧长，留出末尾签名的位置
+pub fn pad_template(template: &[u8], frame_size: usize) -> Result<Vec<u8>> {
+    let body_len = frame_size.checked_sub(FCS_LEN + SIGNATURE_LEN).filter(|len| *len >= template.len());
+    let body_len = body_len.ok_or_else(|| {
+        anyhow!("帧长 {} 字节不足以容纳 {} 字节的报文模板和签名", frame_size, template.len())
+    })?;
+    let mut frame = template.to_vec();
+    frame.resize(body_len, 0);
+    Ok(frame)
+}
+
+// 在 (0, max_rate] 中二分搜索通过测试的最大速率，区间缩小到 step 以内时结束
+pub fn search_max_rate(max_rate: u64, step: u64, mut trial: impl FnMut(u64) -> Result<bool>) -> Result<u64> {
+    let (mut low, mut high) = (0, max_rate);
+    let mut rate = max_rate;
+    loop {
+        if trial(rate)? {
+            low = rate;
+        } else {
+            high = rate;
+        }
+        if high - low <= step.max(1) {
+            return Ok(low);
+        }
+        rate = low + (high - low) / 2;
+    }
+}
+
+// 按模板的 IPv4 协议和目的地址过滤接收侧流量，非 IPv4 模板不过滤
+pub fn receive_filter(template: &[u8]) -> String {
+    match template.get(12..14) {
+        Some([0x08, 0x00]) if template.len() >= 34 => format!(
+            "ip proto {} and dst host {}",
+            template[23],
+            std::net::Ipv4Addr::new(template[30], template[31], template[32], template[33])
+        ),
+        _ => String::new(),
+    }
+}
+
+pub fn throughput_mbps(frame_size: usize, pps: u64) -> f64 {
+    (frame_size * 8) as f64 * pps as f64 / 1_000_000.0
+}
+
+struct TrialRunner<'a> {
+    config: &'a BenchmarkConfig,
+    builder: PacketBuilder,
+    sender: NetworkSender,
+    // 接收接口隔离到命名空间时在其中打开捕获
+    receive_namespace: Option<String>,
+    running: &'a AtomicBool,
+}
+
+impl TrialRunner<'_> {
+    // 以固定速率发送 trial_duration_ms，同时在接收接口上匹配签名
+    fn run(&mut self, base: &[u8], rate: u64) -> Result<TrialResult> {
+        let stream = Arc::new(SignatureStream::new(trial_stream_id()));
+        let tracker = Arc::new(Mutex::new(SignatureTracker::new(stream.clone(), Arc::new(AtomicU64::new(0)))));
+        let receiving = Arc::new(AtomicBool::new(true));
+
+        let receive_interface = self.config.receive_interface.clone();
+        let filter = receive_filter(base);
+        let mut capture = netns::open_in(self.receive_namespace.as_deref(), move || {
+            NetworkInterface::open_receive_capture(&receive_interface, &filter, CAPTURE_BUFFER_BYTES, CAPTURE_TIMEOUT_MS)
+        })?;
+        let tracker_for_capture = tracker.clone();
+        let receiving_for_capture = receiving.clone();
+        // 返回捕获期间内核丢弃的报文数
+        let receiver = std::thread::spawn(move || -> Result<u32> {
+            while receiving_for_capture.load(Ordering::Relaxed) {
+                match capture.next_packet() {
+                    Ok(packet) => {
+                        let ts_us = packet.header.ts.tv_sec as u64 * 1_000_000 + packet.header.ts.tv_usec as u64;
+                        if let Ok(mut tracker) = tracker_for_capture.lock() {
+                            tracker.record_frame(packet.data, ts_us);
+                        }
+                    }
+                    Err(pcap::Error::TimeoutExpired) => {}
+                    Err(e) => return Err(anyhow!("读取接收接口失败: {}", e)),
+                }
+            }
+            let stats = capture.stats().map_err(|e| anyhow!("获取捕获统计失败: {}", e))?;
+            Ok(stats.dropped + stats.if_dropped)
+        });
+
+        let interval = Duration::from_nanos(1_000_000_000 / rate.max(1));
+        let duration = Duration::from_millis(self.config.trial_duration_ms);
+        let start = Instant::now();
+        let mut next_send = start;
+        let mut result = Ok(());
+        while self.running.load(Ordering::Relaxed) && start.elapsed() < duration {
+            let now = Instant::now();
+            if now < next_send {
+                // 短间隔忙等，长间隔让出 CPU
+                if next_send - now > Duration::from_micros(200) {
+                    std::thread::sleep(Duration::from_micros(100));
+                }
+                continue;
+            }
+            let mut frame = stream.stamp(base);
+            result = self.builder.fixup_ipv4_frame(&mut frame).and_then(|_| self.sender.send(&frame));
+            if result.is_err() {
+                break;
+            }
+            next_send += interval;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+
+        std::thread::sleep(Duration::from_millis(self.config.settle_ms));
+        receiving.store(false, Ordering::Relaxed);
+        let dropped = receiver.join().map_err(|_| anyhow!("接收线程异常退出"))??;
+        result?;
+        // 捕获侧丢包无法与被测设备丢包区分，本次测试结果无效
+        if dropped > 0 {
+            return Err(anyhow!("接收接口捕获丢弃了 {} 个报文，测试结果无效，请降低最大速率", dropped));
+        }
+
+        let report = tracker.lock().map_err(|_| anyhow!("签名匹配结果不可用"))?.report();
+        Ok(TrialResult {
+            rate_pps: rate,
+            sent: report.sent,
+            received: report.received,
+            loss_percent: report.loss_percent,
+            offered_pps: if elapsed > 0.0 { report.sent as f64 / elapsed } else { 0.0 },
+            latency: report.latency,
+        })
+    }
+}
+
+fn trial_stream_id() -> u32 {
+    uuid::Uuid::new_v4().as_u128() as u32
+}
+
+// 阻塞执行基准测试：每个帧长先搜索吞吐量，再按吞吐量的百分比测量时延和丢包；
+// 每完成一个帧长追加一条结果，running 置为 false 时在当前测试后结束；
+// namespaces 为命名空间隔离中的网卡所在的命名空间
+pub fn run_benchmark(
+    config: &BenchmarkConfig,
+    namespaces: &HashMap<String, String>,
+    status: &Arc<Mutex<BenchmarkStatus>>,
+    results: &Arc<Mutex<Vec<FrameSizeResult>>>,
+    running: &AtomicBool,
+) -> Result<()> {
+    let builder = PacketBuilder::new(PacketData {
+        protocol: config.packet.protocol.clone(),
+        fields: config.packet.fields.clone(),
+        payload: config.packet.payload.clone(),
+    });
+    // 模板自带的以太网填充去掉后再按帧长补零
+    let mut template = builder.build()?;
+    template.truncate(crate::network::packet_builder::ipv4_frame_end(&template));
+    let bases = config
+        .frame_sizes
+        .iter()
+        .map(|size| pad_template(&template, *size))
+        .collect::<Result<Vec<_>>>()?;
+    let mut runner = TrialRunner {
+        config,
+        builder,
+        sender: netns::open_sender(&config.send_interface, namespaces.get(&config.send_interface).map(String::as_str))?,
+        receive_namespace: namespaces.get(&config.receive_interface).cloned(),
+        running,
+    };
+    let step = (config.max_rate_pps as f64 * config.resolution_percent / 100.0) as u64;
+
+    let update = |frame_size: usize, phase: &str, rate: u64| {
+        if let Ok(mut s) = status.lock() {
+            s.frame_size = Some(frame_size);
+            s.phase = Some(phase.to_string());
+            s.rate_pps = rate;
+        }
+    };
+    let finish_trial = || {
+        if let Ok(mut s) = status.lock() {
+            s.completed_trials += 1;
+        }
+    };
+
+    for (frame_size, base) in config.frame_sizes.iter().copied().zip(bases) {
+        let mut search = Vec::new();
+        let throughput_pps = search_max_rate(config.max_rate_pps, step, |rate| {
+            if !running.load(Ordering::Relaxed) {
+                return Err(anyhow!("基准测试已停止"));
+            }
+            update(frame_size, "throughput", rate);
+            let trial = runner.run(&base, rate)?;
+            finish_trial();
+            let passed = trial.passed(config.loss_tolerance_percent);
+            search.push(trial);
+            Ok(passed)
+        });
+        let throughput_pps = match throughput_pps {
+            Ok(pps) => pps,
+            Err(_) if !running.load(Ordering::Relaxed) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let mut loads = Vec::new();
+        for percent in &config.load_percentages {
+            let rate = (throughput_pps as f64 * percent / 100.0).round() as u64;
+            if rate == 0 || !running.load(Ordering::Relaxed) {
+                continue;
+            }
+            update(frame_size, "load", rate);
+            loads.push(LoadResult { percent: *percent, trial: runner.run(&base, rate)? });
+            finish_trial();
+        }
+
+        if let Ok(mut out) = results.lock() {
+            out.push(FrameSizeResult {
+                frame_size,
+                throughput_pps,
+                throughput_mbps: throughput_mbps(frame_size, throughput_pps),
+                search,
+                loads,
+            });
+        }
+        if !running.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// 文本报告：吞吐量表，以及各帧长在不同负载下的丢包率和时延表
+pub fn format_report(results: &[FrameSizeResult]) -> String {
+    let mut out = String::from("吞吐量\n");
+    out.push_str(&format!("{:>10} {:>14} {:>12}\n", "帧长", "吞吐量(帧/秒)", "Mbit/s"));
+    for result in results {
+        out.push_str(&format!("{:>10} {:>14} {:>12.2}\n", result.frame_size, result.throughput_pps, result.throughput_mbps));
+    }
+    for result in results.iter().filter(|r| !r.loads.is_empty()) {
+        out.push_str(&format!("\n帧长 {} 字节的丢包率与时延\n", result.frame_size));
+        out.push_str(&format!(
+            "{:>6} {:>10} {:>12} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+            "负载%", "帧/秒", "实际帧/秒", "丢包%", "最小ms", "平均ms", "最大ms", "P99ms", "抖动ms"
+        ));
+        for load in &result.loads {
+            let t = &load.trial;
+            out.push_str(&format!(
+                "{:>6.0} {:>10} {:>12.0} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>10.3}\n",
+                load.percent, t.rate_pps, t.offered_pps, t.loss_percent, t.latency.min, t.latency.average, t.latency.max, t.latency.p99, t.latency.jitter
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_search_converges_to_threshold() {
+        let mut tried = Vec::new();
+        let rate = search_max_rate(1000, 10, |rate| {
+            tried.push(rate);
+            Ok(rate <= 637)
+        }).unwrap();
+        assert!((627..=637).contains(&rate));
+        assert_eq!(&tried[..3], &[1000, 500, 750]);
+
+        assert_eq!(search_max_rate(1000, 10, |_| Ok(true)).unwrap(), 1000);
+        assert_eq!(search_max_rate(1000, 10, |_| Ok(false)).unwrap(), 0);
+        assert!(search_max_rate(1000, 10, |_| Err(anyhow!("stop"))).is_err());
+    }
+
+    #[test]
+    fn template_padding_leaves_room_for_signature() {
+        // 以太网 + IPv4 + UDP 头部
+        let template = vec![0u8; 42];
+        assert_eq!(pad_template(&template, 64).unwrap().len(), 64 - FCS_LEN - SIGNATURE_LEN);
+        assert_eq!(pad_template(&template, 1518).unwrap().len() + SIGNATURE_LEN + FCS_LEN, 1518);
+        assert!(pad_template(&[0u8; 50], 64).is_err());
+        assert!(pad_template(&template, 10).is_err());
+        assert!((throughput_mbps(64, 148_810) - 76.19).abs() < 0.01);
+
+        let mut udp = vec![0u8; 42];
+        udp[12..14].copy_from_slice(&[0x08, 0x00]);
+        udp[23] = 17;
+        udp[30..34].copy_from_slice(&[192, 168, 2, 1]);
+        assert_eq!(receive_filter(&udp), "ip proto 17 and dst host 192.168.2.1");
+        assert_eq!(receive_filter(&[0u8; 14]), "");
+    }
+
+    #[test]
+    fn report_lists_throughput_and_loads() {
+        let trial = TrialResult {
+            rate_pps: 500,
+            sent: 1000,
+            received: 1000,
+            loss_percent: 0.0,
+            offered_pps: 500.0,
+            latency: RttSummary { min: 0.1, average: 0.2, max: 0.5, ..Default::default() },
+        };
+        let results = vec![FrameSizeResult {
+            frame_size: 64,
+            throughput_pps: 1000,
+            throughput_mbps: throughput_mbps(64, 1000),
+            search: vec![],
+            loads: vec![LoadResult { percent: 50.0, trial }],
+        }];
+        // 丢包为 0 但发送端未达到目标速率时不算通过
+        assert!(results[0].loads[0].trial.passed(0.0));
+        let slow = TrialResult { offered_pps: 480.0, ..results[0].loads[0].trial.clone() };
+        assert!(!slow.passed(0.0));
+        assert!(!TrialResult { sent: 0, ..slow.clone() }.passed(100.0));
+
+        let report = format_report(&results);
+        assert!(report.contains("帧长 64 字节的丢包率与时延"));
+        assert!(report.lines().any(|l| l.split_whitespace().collect::<Vec<_>>() == ["64", "1000", "0.51"]));
+        assert!(report.lines().any(|l| l.trim_start().starts_with("50") && l.contains("0.500")));
+    }
+}
@@ -0,0 +1,130 @@
+use serde::{Serialize, Deserialize};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use crate::network::packet_sniffer::{SnifferManager, CaptureFilters, PacketStatistics};
+
+// 未指定会话名时使用的会话，兼容只有一个捕获的旧调用方式
+pub const DEFAULT_SESSION: &str = "default";
+// 响应监控专用的捕获会话，与用户的捕获互不影响
+pub const MONITOR_SESSION: &str = "response_monitor";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureSessionInfo {
+    pub name: String,
+    // 捕获的接口，多接口捕获时有多个
+    pub interfaces: Vec<String>,
+    pub filters: Option<CaptureFilters>,
+    pub running: bool,
+    pub statistics: Option<PacketStatistics>,
+}
+
+// 按名称管理的捕获会话，每个会话有独立的接口、过滤条件、缓存和统计
+#[derive(Default)]
+pub struct CaptureSessions {
+    sessions: BTreeMap<String, SnifferManager>,
+}
+
+fn session_key(name: Option<&str>) -> &str {
+    match name.map(str::trim) {
+        Some(name) if !name.is_empty() => name,
+        _ => DEFAULT_SESSION,
+    }
+}
+
+impl CaptureSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: Option<&str>) -> Option<&SnifferManager> {
+        self.sessions.get(session_key(name))
+    }
+
+    pub fn get_mut(&mut self, name: Option<&str>) -> Option<&mut SnifferManager> {
+        self.sessions.get_mut(session_key(name))
+    }
+
+    pub fn get_or_create(&mut self, name: Option<&str>) -> &mut SnifferManager {
+        self.sessions.entry(session_key(name).to_string()).or_default()
+    }
+
+    // 在会话上启动捕获；会话为此新建而启动失败时删除，不留下空会话
+    pub fn start_with<T>(&mut self, name: Option<&str>, start: impl FnOnce(&mut SnifferManager) -> Result<T>) -> Result<T> {
+        let key = session_key(name).to_string();
+        let created = !self.sessions.contains_key(&key);
+        let result = start(self.sessions.entry(key.clone()).or_default());
+        if result.is_err() && created {
+            self.sessions.remove(&key);
+        }
+        result
+    }
+
+    // 保留给内部使用的会话名，用户的捕获命令不能使用
+    pub fn is_reserved(name: Option<&str>) -> bool {
+        session_key(name) == MONITOR_SESSION
+    }
+
+    // 停止并删除会话，订阅者随之收不到新数据包
+    pub fn remove(&mut self, name: Option<&str>) -> bool {
+        match self.sessions.remove(session_key(name)) {
+            Some(mut sniffer) => {
+                sniffer.stop_capture();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn list(&self) -> Vec<CaptureSessionInfo> {
+        self.sessions
+            .iter()
+            .map(|(name, sniffer)| CaptureSessionInfo {
+                name: name.clone(),
+                interfaces: sniffer.interface_names().to_vec(),
+                filters: sniffer.filters().cloned(),
+                running: sniffer.is_running(),
+                statistics: sniffer.get_statistics(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_are_independent_and_named() {
+        let mut sessions = CaptureSessions::new();
+        assert!(sessions.get(None).is_none());
+
+        sessions.get_or_create(None);
+        sessions.get_or_create(Some("dut-b"));
+        sessions.get_or_create(Some("  "));
+        let names: Vec<String> = sessions.list().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec![DEFAULT_SESSION, "dut-b"]);
+        assert!(sessions.get(Some(DEFAULT_SESSION)).is_some());
+        assert!(!sessions.get(Some("dut-b")).unwrap().is_running());
+
+        assert!(sessions.remove(Some("dut-b")));
+        assert!(!sessions.remove(Some("dut-b")));
+        assert_eq!(sessions.list().len(), 1);
+
+        assert!(CaptureSessions::is_reserved(Some(" response_monitor ")));
+        assert!(!CaptureSessions::is_reserved(None));
+    }
+
+    #[test]
+    fn failed_start_removes_only_new_sessions() {
+        let mut sessions = CaptureSessions::new();
+        let failed: Result<()> = sessions.start_with(Some("dut-b"), |_| Err(anyhow::anyhow!("no device")));
+        assert!(failed.is_err());
+        assert!(sessions.get(Some("dut-b")).is_none());
+
+        // 已有的会话保留缓存和统计
+        sessions.get_or_create(None);
+        assert!(sessions.start_with(None, |_| Err::<(), _>(anyhow::anyhow!("no device"))).is_err());
+        assert!(sessions.get(None).is_some());
+        assert_eq!(sessions.start_with(None, |_| Ok(1)).unwrap(), 1);
+    }
+}
@@ -0,0 +1,279 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::network::CapturedPacket;
+
+// 每张统计表的最大条目数，超出后不再新增条目（已有条目继续累计）
+const MAX_TABLE_ENTRIES: usize = 50000;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EndpointStats {
+    pub address: String,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationStats {
+    pub kind: String, // ip / tcp / udp
+    pub address_a: String,
+    pub port_a: Option<u16>,
+    pub address_b: String,
+    pub port_b: Option<u16>,
+    pub packets_a_to_b: u64,
+    pub bytes_a_to_b: u64,
+    pub packets_b_to_a: u64,
+    pub bytes_b_to_a: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub duration_ms: u64,
+}
+
+// 无方向的会话键：端点 A 为较小的一端
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ConversationKey {
+    a: (String, Option<u16>),
+    b: (String, Option<u16>),
+}
+
+pub struct TrafficStatistics {
+    mac_endpoints: HashMap<String, EndpointStats>,
+    ip_endpoints: HashMap<String, EndpointStats>,
+    ip_conversations: HashMap<ConversationKey, ConversationStats>,
+    tcp_conversations: HashMap<ConversationKey, ConversationStats>,
+    udp_conversations: HashMap<ConversationKey, ConversationStats>,
+}
+
+impl TrafficStatistics {
+    pub fn new() -> Self {
+        Self {
+            mac_endpoints: HashMap::new(),
+            ip_endpoints: HashMap::new(),
+            ip_conversations: HashMap::new(),
+            tcp_conversations: HashMap::new(),
+            udp_conversations: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.mac_endpoints.clear();
+        self.ip_endpoints.clear();
+        self.ip_conversations.clear();
+        self.tcp_conversations.clear();
+        self.udp_conversations.clear();
+    }
+
+    // 在捕获线程中对每个数据包增量更新
+    pub fn record(&mut self, packet: &CapturedPacket) {
+        let bytes = packet.size as u64;
+        let ts = packet.timestamp;
+
+        Self::record_endpoints(&mut self.mac_endpoints, &packet.src_mac, &packet.dst_mac, bytes, ts);
+
+        let (src_ip, dst_ip) = match (&packet.src_ip, &packet.dst_ip) {
+            (Some(s), Some(d)) => (s, d),
+            _ => return,
+        };
+
+        // ARP 的地址是被询问的地址，不计入 IP 端点和会话
+        if packet.protocol == "arp" {
+            return;
+        }
+
+        Self::record_endpoints(&mut self.ip_endpoints, src_ip, dst_ip, bytes, ts);
+        Self::record_conversation(&mut self.ip_conversations, "ip", (src_ip, None), (dst_ip, None), bytes, ts);
+
+        if let (Some(sp), Some(dp)) = (packet.src_port, packet.dst_port) {
+            let table = match packet.protocol.as_str() {
+                "tcp" => &mut self.tcp_conversations,
+                "udp" => &mut self.udp_conversations,
+                _ => return,
+            };
+            Self::record_conversation(table, &packet.protocol, (src_ip, Some(sp)), (dst_ip, Some(dp)), bytes, ts);
+        }
+    }
+
+    // kind: mac / ip，按总字节数降序
+    pub fn endpoints(&self, kind: &str) -> Vec<EndpointStats> {
+        let table = match kind {
+            "mac" => &self.mac_endpoints,
+            _ => &self.ip_endpoints,
+        };
+        let mut list: Vec<EndpointStats> = table.values().cloned().collect();
+        list.sort_by_key(|e| std::cmp::Reverse(e.tx_bytes + e.rx_bytes));
+        list
+    }
+
+    // kind: ip / tcp / udp，按总字节数降序
+    pub fn conversations(&self, kind: &str) -> Vec<ConversationStats> {
+        let table = match kind {
+            "tcp" => &self.tcp_conversations,
+            "udp" => &self.udp_conversations,
+            _ => &self.ip_conversations,
+        };
+        let mut list: Vec<ConversationStats> = table.values().cloned().collect();
+        list.sort_by_key(|c| std::cmp::Reverse(c.bytes_a_to_b + c.bytes_b_to_a));
+        list
+    }
+
+    fn record_endpoints(table: &mut HashMap<String, EndpointStats>, src: &str, dst: &str, bytes: u64, ts: u64) {
+        if let Some(endpoint) = Self::endpoint_entry(table, src, ts) {
+            endpoint.tx_packets += 1;
+            endpoint.tx_bytes += bytes;
+        }
+        if let Some(endpoint) = Self::endpoint_entry(table, dst, ts) {
+            endpoint.rx_packets += 1;
+            endpoint.rx_bytes += bytes;
+        }
+    }
+
+    fn endpoint_entry<'a>(table: &'a mut HashMap<String, EndpointStats>, address: &str, ts: u64) -> Option<&'a mut EndpointStats> {
+        if !table.contains_key(address) {
+            if table.len() >= MAX_TABLE_ENTRIES {
+                return None;
+            }
+            table.insert(address.to_string(), EndpointStats {
+                address: address.to_string(),
+                tx_packets: 0,
+                tx_bytes: 0,
+                rx_packets: 0,
+                rx_bytes: 0,
+                first_seen: ts,
+                last_seen: ts,
+                duration_ms: 0,
+            });
+        }
+
+        let endpoint = table.get_mut(address)?;
+        endpoint.last_seen = endpoint.last_seen.max(ts);
+        endpoint.duration_ms = endpoint.last_seen.saturating_sub(endpoint.first_seen);
+        Some(endpoint)
+    }
+
+    fn record_conversation(
+        table: &mut HashMap<ConversationKey, ConversationStats>,
+        kind: &str,
+        src: (&str, Option<u16>),
+        dst: (&str, Option<u16>),
+        bytes: u64,
+        ts: u64,
+    ) {
+        let src = (src.0.to_string(), src.1);
+        let dst = (dst.0.to_string(), dst.1);
+        let src_is_a = src <= dst;
+        let key = if src_is_a {
+            ConversationKey { a: src, b: dst }
+        } else {
+            ConversationKey { a: dst, b: src }
+        };
+
+        if !table.contains_key(&key) {
+            if table.len() >= MAX_TABLE_ENTRIES {
+                return;
+            }
+            table.insert(key.clone(), ConversationStats {
+                kind: kind.to_string(),
+                address_a: key.a.0.clone(),
+                port_a: key.a.1,
+                address_b: key.b.0.clone(),
+                port_b: key.b.1,
+                packets_a_to_b: 0,
+                bytes_a_to_b: 0,
+                packets_b_to_a: 0,
+                bytes_b_to_a: 0,
+                first_seen: ts,
+                last_seen: ts,
+                duration_ms: 0,
+            });
+        }
+
+        if let Some(conversation) = table.get_mut(&key) {
+            if src_is_a {
+                conversation.packets_a_to_b += 1;
+                conversation.bytes_a_to_b += bytes;
+            } else {
+                conversation.packets_b_to_a += 1;
+                conversation.bytes_b_to_a += bytes;
+            }
+            conversation.last_seen = conversation.last_seen.max(ts);
+            conversation.duration_ms = conversation.last_seen.saturating_sub(conversation.first_seen);
+        }
+    }
+}
+
+impl Default for TrafficStatistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(protocol: &str, src: (&str, &str, Option<u16>), dst: (&str, &str, Option<u16>), size: usize, ts: u64) -> CapturedPacket {
+        CapturedPacket {
+            timestamp: ts,
+            protocol: protocol.to_string(),
+            src_mac: src.0.to_string(),
+            dst_mac: dst.0.to_string(),
+            src_ip: Some(src.1.to_string()),
+            dst_ip: Some(dst.1.to_string()),
+            src_port: src.2,
+            dst_port: dst.2,
+            size,
+            ..CapturedPacket::from_frame(Vec::new())
+        }
+    }
+
+    const MAC_A: &str = "00:00:00:00:00:0a";
+    const MAC_B: &str = "00:00:00:00:00:0b";
+
+    #[test]
+    fn endpoints_track_both_directions() {
+        let mut stats = TrafficStatistics::new();
+        stats.record(&packet("tcp", (MAC_A, "10.0.0.1", Some(1234)), (MAC_B, "10.0.0.2", Some(80)), 100, 1000));
+        stats.record(&packet("tcp", (MAC_B, "10.0.0.2", Some(80)), (MAC_A, "10.0.0.1", Some(1234)), 300, 1500));
+
+        let ips = stats.endpoints("ip");
+        assert_eq!(ips.len(), 2);
+        let a = ips.iter().find(|e| e.address == "10.0.0.1").unwrap();
+        assert_eq!((a.tx_packets, a.tx_bytes, a.rx_packets, a.rx_bytes), (1, 100, 1, 300));
+        assert_eq!(a.duration_ms, 500);
+
+        let macs = stats.endpoints("mac");
+        assert_eq!(macs.len(), 2);
+    }
+
+    #[test]
+    fn conversations_are_direction_independent() {
+        let mut stats = TrafficStatistics::new();
+        stats.record(&packet("udp", (MAC_B, "10.0.0.2", Some(53)), (MAC_A, "10.0.0.1", Some(5353)), 80, 10));
+        stats.record(&packet("udp", (MAC_A, "10.0.0.1", Some(5353)), (MAC_B, "10.0.0.2", Some(53)), 60, 20));
+        stats.record(&packet("tcp", (MAC_A, "10.0.0.1", Some(4000)), (MAC_B, "10.0.0.2", Some(80)), 40, 30));
+
+        let ip = stats.conversations("ip");
+        assert_eq!(ip.len(), 1);
+        assert_eq!(ip[0].address_a, "10.0.0.1");
+        assert_eq!((ip[0].packets_a_to_b, ip[0].packets_b_to_a), (2, 1));
+        assert_eq!(ip[0].duration_ms, 20);
+
+        let udp = stats.conversations("udp");
+        assert_eq!(udp.len(), 1);
+        assert_eq!((udp[0].bytes_a_to_b, udp[0].bytes_b_to_a), (60, 80));
+        assert_eq!(stats.conversations("tcp").len(), 1);
+    }
+
+    #[test]
+    fn arp_counts_only_mac_endpoints() {
+        let mut stats = TrafficStatistics::new();
+        stats.record(&packet("arp", (MAC_A, "10.0.0.1", None), ("ff:ff:ff:ff:ff:ff", "10.0.0.2", None), 60, 1));
+        assert_eq!(stats.endpoints("mac").len(), 2);
+        assert!(stats.endpoints("ip").is_empty());
+        assert!(stats.conversations("ip").is_empty());
+    }
+}
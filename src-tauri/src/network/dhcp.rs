@@ -0,0 +1,237 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::net::Ipv4Addr;
+
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+// BOOTP 报文最短 300 字节，部分服务器不处理更短的请求
+const MIN_MESSAGE_LEN: usize = 300;
+
+pub const OPTION_SUBNET_MASK: u8 = 1;
+pub const OPTION_ROUTER: u8 = 3;
+pub const OPTION_DNS_SERVERS: u8 = 6;
+pub const OPTION_HOSTNAME: u8 = 12;
+pub const OPTION_REQUESTED_IP: u8 = 50;
+pub const OPTION_LEASE_TIME: u8 = 51;
+pub const OPTION_MESSAGE_TYPE: u8 = 53;
+pub const OPTION_SERVER_ID: u8 = 54;
+pub const OPTION_PARAMETER_LIST: u8 = 55;
+
+const MESSAGE_TYPES: &[(&str, u8)] = &[
+    ("discover", 1),
+    ("offer", 2),
+    ("request", 3),
+    ("decline", 4),
+    ("ack", 5),
+    ("nak", 6),
+    ("release", 7),
+    ("inform", 8),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DhcpMessage {
+    // 1 = BOOTREQUEST，2 = BOOTREPLY
+    pub op: u8,
+    pub hops: u8,
+    pub xid: u32,
+    pub secs: u16,
+    pub broadcast: bool,
+    pub ciaddr: [u8; 4],
+    pub yiaddr: [u8; 4],
+    pub siaddr: [u8; 4],
+    pub giaddr: [u8; 4],
+    pub chaddr: [u8; 6],
+    // 不含 Pad 和 End 的选项，按出现顺序
+    pub options: Vec<(u8, Vec<u8>)>,
+}
+
+pub fn message_type_code(name: &str) -> Option<u8> {
+    let lower = name.trim().to_lowercase();
+    MESSAGE_TYPES.iter().find(|(n, _)| *n == lower).map(|(_, c)| *c)
+}
+
+pub fn message_type_names() -> Vec<&'static str> {
+    MESSAGE_TYPES.iter().map(|(n, _)| *n).collect()
+}
+
+// 服务器发出的消息类型使用 BOOTREPLY
+pub fn op_for_message_type(code: u8) -> u8 {
+    match code {
+        2 | 5 | 6 => 2,
+        _ => 1,
+    }
+}
+
+impl DhcpMessage {
+    pub fn option(&self, code: u8) -> Option<&[u8]> {
+        self.options.iter().find(|(c, _)| *c == code).map(|(_, v)| v.as_slice())
+    }
+
+    pub fn message_type(&self) -> Option<u8> {
+        self.option(OPTION_MESSAGE_TYPE).and_then(|v| v.first().copied())
+    }
+}
+
+// 额外选项："代码:十六进制值"，多项以分号分隔
+pub fn parse_raw_options(spec: &str) -> Result<Vec<(u8, Vec<u8>)>> {
+    spec.split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|item| {
+            let invalid = || anyhow!("无效的 DHCP 选项: {}", item);
+            let (code, hex) = item.split_once(':').unwrap_or((item, ""));
+            let code: u8 = code.trim().parse().map_err(|_| invalid())?;
+            if code == 0 || code == 255 {
+                return Err(invalid());
+            }
+            let value = if hex.trim().is_empty() {
+                Vec::new()
+            } else {
+                crate::network::packet_validation::parse_hex_data(hex).ok_or_else(invalid)?
+            };
+            if value.len() > 255 {
+                return Err(invalid());
+            }
+            Ok((code, value))
+        })
+        .collect()
+}
+
+pub fn encode(message: &DhcpMessage) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(MIN_MESSAGE_LEN);
+    out.extend_from_slice(&[message.op, 1, 6, message.hops]);
+    out.extend_from_slice(&message.xid.to_be_bytes());
+    out.extend_from_slice(&message.secs.to_be_bytes());
+    out.extend_from_slice(&(if message.broadcast { 0x8000u16 } else { 0 }).to_be_bytes());
+    for addr in [message.ciaddr, message.yiaddr, message.siaddr, message.giaddr] {
+        out.extend_from_slice(&addr);
+    }
+    // chaddr 16 字节，sname 64 字节，file 128 字节
+    out.extend_from_slice(&message.chaddr);
+    out.resize(out.len() + 10 + 64 + 128, 0);
+    out.extend_from_slice(&MAGIC_COOKIE);
+
+    for (code, value) in &message.options {
+        if value.len() > 255 {
+            return Err(anyhow!("DHCP 选项 {} 超过 255 字节", code));
+        }
+        out.push(*code);
+        out.push(value.len() as u8);
+        out.extend_from_slice(value);
+    }
+    out.push(255);
+    if out.len() < MIN_MESSAGE_LEN {
+        out.resize(MIN_MESSAGE_LEN, 0);
+    }
+    Ok(out)
+}
+
+pub fn decode(data: &[u8]) -> Option<DhcpMessage> {
+    if data.len() < 240 || data[236..240] != MAGIC_COOKIE || data[1] != 1 || data[2] != 6 {
+        return None;
+    }
+    let addr = |i: usize| -> [u8; 4] { [data[i], data[i + 1], data[i + 2], data[i + 3]] };
+
+    let mut options = Vec::new();
+    let mut i = 240;
+    while i < data.len() {
+        match data[i] {
+            0 => i += 1,
+            255 => break,
+            code => {
+                let len = *data.get(i + 1)? as usize;
+                options.push((code, data.get(i + 2..i + 2 + len)?.to_vec()));
+                i += 2 + len;
+            }
+        }
+    }
+
+    Some(DhcpMessage {
+        op: data[0],
+        hops: data[3],
+        xid: u32::from_be_bytes(addr(4)),
+        secs: u16::from_be_bytes([data[8], data[9]]),
+        broadcast: data[10] & 0x80 != 0,
+        ciaddr: addr(12),
+        yiaddr: addr(16),
+        siaddr: addr(20),
+        giaddr: addr(24),
+        chaddr: [data[28], data[29], data[30], data[31], data[32], data[33]],
+        options,
+    })
+}
+
+pub fn describe(message: &DhcpMessage) -> String {
+    let kind = match message.message_type() {
+        Some(code) => MESSAGE_TYPES
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|(n, _)| {
+                let mut name = n.to_string();
+                name[..1].make_ascii_uppercase();
+                name
+            })
+            .unwrap_or_else(|| format!("Type {}", code)),
+        None => "BOOTP".to_string(),
+    };
+    let mut text = format!("{} - Transaction ID 0x{:08x}", kind, message.xid);
+    if message.yiaddr != [0; 4] {
+        text.push_str(&format!(" Your IP {}", Ipv4Addr::from(message.yiaddr)));
+    }
+    if let Some(ip) = message.option(OPTION_REQUESTED_IP).and_then(|v| <[u8; 4]>::try_from(v).ok()) {
+        text.push_str(&format!(" Requested IP {}", Ipv4Addr::from(ip)));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discover() -> DhcpMessage {
+        DhcpMessage {
+            op: 1,
+            hops: 0,
+            xid: 0xDEADBEEF,
+            secs: 0,
+            broadcast: true,
+            ciaddr: [0; 4],
+            yiaddr: [0; 4],
+            siaddr: [0; 4],
+            giaddr: [0; 4],
+            chaddr: [0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+            options: vec![(OPTION_MESSAGE_TYPE, vec![1]), (OPTION_PARAMETER_LIST, vec![1, 3, 6])],
+        }
+    }
+
+    #[test]
+    fn discover_layout_and_round_trip() {
+        let bytes = encode(&discover()).unwrap();
+        assert_eq!(bytes.len(), MIN_MESSAGE_LEN);
+        assert_eq!(&bytes[..4], &[1, 1, 6, 0]);
+        assert_eq!(&bytes[10..12], &[0x80, 0]);
+        assert_eq!(&bytes[236..240], &MAGIC_COOKIE);
+        assert_eq!(&bytes[240..243], &[53, 1, 1]);
+        assert_eq!(bytes[248], 255);
+        assert_eq!(decode(&bytes).unwrap(), discover());
+        assert_eq!(describe(&discover()), "Discover - Transaction ID 0xdeadbeef");
+    }
+
+    #[test]
+    fn offer_describes_assigned_address() {
+        let mut offer = discover();
+        offer.op = op_for_message_type(2);
+        offer.yiaddr = [192, 168, 1, 50];
+        offer.options = vec![(OPTION_MESSAGE_TYPE, vec![2]), (OPTION_SERVER_ID, vec![192, 168, 1, 1])];
+        let decoded = decode(&encode(&offer).unwrap()).unwrap();
+        assert_eq!(decoded.op, 2);
+        assert_eq!(describe(&decoded), "Offer - Transaction ID 0xdeadbeef Your IP 192.168.1.50");
+    }
+
+    #[test]
+    fn raw_option_spec() {
+        assert_eq!(parse_raw_options("60:4d53465420352e30; 80").unwrap(), vec![(60, b"MSFT 5.0".to_vec()), (80, vec![])]);
+        assert!(parse_raw_options("255:00").is_err());
+        assert!(parse_raw_options("300:00").is_err());
+        assert!(decode(&[0u8; 240]).is_none());
+    }
+}
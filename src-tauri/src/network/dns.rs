@@ -0,0 +1,390 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsQuestion {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+// 记录数据使用与区域文件相同的文本写法，编码时再转为 RDATA
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub name: String,
+    pub rtype: u16,
+    pub class: u16,
+    pub ttl: u32,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsMessage {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<DnsQuestion>,
+    pub answers: Vec<DnsRecord>,
+    pub authorities: Vec<DnsRecord>,
+    pub additionals: Vec<DnsRecord>,
+}
+
+const TYPES: &[(&str, u16)] = &[
+    ("A", 1),
+    ("NS", 2),
+    ("CNAME", 5),
+    ("SOA", 6),
+    ("PTR", 12),
+    ("MX", 15),
+    ("TXT", 16),
+    ("AAAA", 28),
+    ("SRV", 33),
+    ("ANY", 255),
+];
+
+const CLASS_IN: u16 = 1;
+
+// 类型名或 TYPE<n>/纯数字
+pub fn type_code(name: &str) -> Option<u16> {
+    let upper = name.trim().to_uppercase();
+    TYPES
+        .iter()
+        .find(|(n, _)| *n == upper)
+        .map(|(_, c)| *c)
+        .or_else(|| upper.strip_prefix("TYPE").unwrap_or(&upper).parse().ok())
+}
+
+pub fn type_name(code: u16) -> String {
+    TYPES
+        .iter()
+        .find(|(_, c)| *c == code)
+        .map(|(n, _)| n.to_string())
+        .unwrap_or_else(|| format!("TYPE{}", code))
+}
+
+fn class_code(name: &str) -> Option<u16> {
+    match name.trim().to_uppercase().as_str() {
+        "IN" => Some(1),
+        "CH" => Some(3),
+        "HS" => Some(4),
+        "ANY" => Some(255),
+        other => other.strip_prefix("CLASS").unwrap_or(other).parse().ok(),
+    }
+}
+
+// 问题列表："名称 [类型 [类]]"，多项以分号或换行分隔
+pub fn parse_questions(spec: &str) -> Result<Vec<DnsQuestion>> {
+    split_items(spec)
+        .map(|item| {
+            let parts: Vec<&str> = item.split_whitespace().collect();
+            let invalid = || anyhow!("无效的 DNS 问题: {}", item);
+            let (name, qtype, qclass) = match parts.as_slice() {
+                [name] => (*name, 1, CLASS_IN),
+                [name, t] => (*name, type_code(t).ok_or_else(invalid)?, CLASS_IN),
+                [name, t, c] => (*name, type_code(t).ok_or_else(invalid)?, class_code(c).ok_or_else(invalid)?),
+                _ => return Err(invalid()),
+            };
+            encode_name(name, &mut Vec::new())?;
+            Ok(DnsQuestion { name: name.to_string(), qtype, qclass })
+        })
+        .collect()
+}
+
+// 资源记录列表："名称 类型 TTL 数据"，多项以分号或换行分隔；数据格式见 encode_rdata
+pub fn parse_records(spec: &str) -> Result<Vec<DnsRecord>> {
+    split_items(spec)
+        .map(|item| {
+            let parts: Vec<&str> = item.splitn(4, char::is_whitespace).map(str::trim).collect();
+            let invalid = || anyhow!("无效的 DNS 记录: {}", item);
+            let [name, rtype, ttl, data] = parts.as_slice() else {
+                return Err(invalid());
+            };
+            let record = DnsRecord {
+                name: name.to_string(),
+                rtype: type_code(rtype).ok_or_else(invalid)?,
+                class: CLASS_IN,
+                ttl: ttl.parse().map_err(|_| invalid())?,
+                data: data.to_string(),
+            };
+            encode_name(&record.name, &mut Vec::new())?;
+            encode_rdata(record.rtype, &record.data)?;
+            Ok(record)
+        })
+        .collect()
+}
+
+fn split_items(spec: &str) -> impl Iterator<Item = &str> {
+    spec.split([';', '\n']).map(str::trim).filter(|s| !s.is_empty())
+}
+
+pub fn encode_message(message: &DnsMessage) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&message.id.to_be_bytes());
+    out.extend_from_slice(&message.flags.to_be_bytes());
+    for count in [
+        message.questions.len(),
+        message.answers.len(),
+        message.authorities.len(),
+        message.additionals.len(),
+    ] {
+        let count = u16::try_from(count).map_err(|_| anyhow!("DNS 记录数量过多"))?;
+        out.extend_from_slice(&count.to_be_bytes());
+    }
+
+    for question in &message.questions {
+        encode_name(&question.name, &mut out)?;
+        out.extend_from_slice(&question.qtype.to_be_bytes());
+        out.extend_from_slice(&question.qclass.to_be_bytes());
+    }
+    for record in message.answers.iter().chain(&message.authorities).chain(&message.additionals) {
+        encode_name(&record.name, &mut out)?;
+        out.extend_from_slice(&record.rtype.to_be_bytes());
+        out.extend_from_slice(&record.class.to_be_bytes());
+        out.extend_from_slice(&record.ttl.to_be_bytes());
+        let rdata = encode_rdata(record.rtype, &record.data)?;
+        out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(&rdata);
+    }
+    Ok(out)
+}
+
+// 不使用名称压缩
+fn encode_name(name: &str, out: &mut Vec<u8>) -> Result<()> {
+    let trimmed = name.trim().trim_end_matches('.');
+    let start = out.len();
+    if !trimmed.is_empty() {
+        for label in trimmed.split('.') {
+            if label.is_empty() || label.len() > 63 {
+                return Err(anyhow!("无效的域名: {}", name));
+            }
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+        }
+    }
+    out.push(0);
+    if out.len() - start > 255 {
+        return Err(anyhow!("域名超过 255 字节: {}", name));
+    }
+    Ok(())
+}
+
+// A/AAAA 为地址，NS/CNAME/PTR 为域名，MX 为 "优先级 域名"，TXT 为文本，
+// SRV 为 "优先级 权重 端口 目标"，SOA 为 "主服务器 邮箱 序列号 刷新 重试 过期 最小TTL"，其他类型为十六进制
+fn encode_rdata(rtype: u16, data: &str) -> Result<Vec<u8>> {
+    let invalid = || anyhow!("{} 记录数据无效: {}", type_name(rtype), data);
+    let parts: Vec<&str> = data.split_whitespace().collect();
+    let mut out = Vec::new();
+    match rtype {
+        1 => out.extend_from_slice(&data.trim().parse::<Ipv4Addr>().map_err(|_| invalid())?.octets()),
+        28 => out.extend_from_slice(&data.trim().parse::<Ipv6Addr>().map_err(|_| invalid())?.octets()),
+        2 | 5 | 12 => encode_name(data, &mut out)?,
+        15 => {
+            let [pref, exchange] = parts.as_slice() else { return Err(invalid()) };
+            out.extend_from_slice(&pref.parse::<u16>().map_err(|_| invalid())?.to_be_bytes());
+            encode_name(exchange, &mut out)?;
+        }
+        16 => {
+            for chunk in data.as_bytes().chunks(255) {
+                out.push(chunk.len() as u8);
+                out.extend_from_slice(chunk);
+            }
+        }
+        33 => {
+            let [prio, weight, port, target] = parts.as_slice() else { return Err(invalid()) };
+            for n in [prio, weight, port] {
+                out.extend_from_slice(&n.parse::<u16>().map_err(|_| invalid())?.to_be_bytes());
+            }
+            encode_name(target, &mut out)?;
+        }
+        6 => {
+            let [mname, rname, numbers @ ..] = parts.as_slice() else { return Err(invalid()) };
+            if numbers.len() != 5 {
+                return Err(invalid());
+            }
+            encode_name(mname, &mut out)?;
+            encode_name(rname, &mut out)?;
+            for n in numbers {
+                out.extend_from_slice(&n.parse::<u32>().map_err(|_| invalid())?.to_be_bytes());
+            }
+        }
+        _ => out = crate::network::packet_validation::parse_hex_data(data).ok_or_else(invalid)?,
+    }
+    if out.len() > u16::MAX as usize {
+        return Err(invalid());
+    }
+    Ok(out)
+}
+
+pub fn decode_message(data: &[u8]) -> Option<DnsMessage> {
+    if data.len() < 12 {
+        return None;
+    }
+    let read_u16 = |pos: usize| Some(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]));
+    let counts: Vec<usize> = (0..4).map(|i| read_u16(4 + i * 2).map(|n| n as usize)).collect::<Option<_>>()?;
+
+    let mut pos = 12;
+    let mut questions = Vec::new();
+    for _ in 0..counts[0] {
+        let (name, next) = decode_name(data, pos)?;
+        questions.push(DnsQuestion { name, qtype: read_u16(next)?, qclass: read_u16(next + 2)? });
+        pos = next + 4;
+    }
+
+    let mut sections: Vec<Vec<DnsRecord>> = Vec::new();
+    for &count in &counts[1..] {
+        let mut records = Vec::new();
+        for _ in 0..count {
+            let (name, next) = decode_name(data, pos)?;
+            let rtype = read_u16(next)?;
+            let class = read_u16(next + 2)?;
+            let ttl = u32::from_be_bytes(data.get(next + 4..next + 8)?.try_into().ok()?);
+            let rdlength = read_u16(next + 8)? as usize;
+            let rdata_start = next + 10;
+            data.get(rdata_start..rdata_start + rdlength)?;
+            records.push(DnsRecord { name, rtype, class, ttl, data: decode_rdata(data, rtype, rdata_start, rdlength)? });
+            pos = rdata_start + rdlength;
+        }
+        sections.push(records);
+    }
+
+    let additionals = sections.pop()?;
+    let authorities = sections.pop()?;
+    let answers = sections.pop()?;
+    Some(DnsMessage { id: read_u16(0)?, flags: read_u16(2)?, questions, answers, authorities, additionals })
+}
+
+// 支持名称压缩指针，返回名称和名称之后的位置
+fn decode_name(data: &[u8], start: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut pos = start;
+    let mut end = None;
+    // 防止压缩指针形成环
+    for _ in 0..128 {
+        let len = *data.get(pos)? as usize;
+        if len == 0 {
+            let name = if labels.is_empty() { ".".to_string() } else { labels.join(".") };
+            return Some((name, end.unwrap_or(pos + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            let pointer = ((len & 0x3F) << 8) | *data.get(pos + 1)? as usize;
+            end.get_or_insert(pos + 2);
+            pos = pointer;
+            continue;
+        }
+        let label = data.get(pos + 1..pos + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += 1 + len;
+    }
+    None
+}
+
+fn decode_rdata(data: &[u8], rtype: u16, start: usize, len: usize) -> Option<String> {
+    let rdata = &data[start..start + len];
+    let read_u16 = |b: &[u8], i: usize| Some(u16::from_be_bytes([*b.get(i)?, *b.get(i + 1)?]));
+    Some(match rtype {
+        1 if len == 4 => Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]).to_string(),
+        28 if len == 16 => Ipv6Addr::from(<[u8; 16]>::try_from(rdata).ok()?).to_string(),
+        2 | 5 | 12 => decode_name(data, start)?.0,
+        15 => format!("{} {}", read_u16(rdata, 0)?, decode_name(data, start + 2)?.0),
+        16 => {
+            let mut text = String::new();
+            let mut i = 0;
+            while i < rdata.len() {
+                let n = rdata[i] as usize;
+                text.push_str(&String::from_utf8_lossy(rdata.get(i + 1..i + 1 + n)?));
+                i += 1 + n;
+            }
+            text
+        }
+        33 => format!(
+            "{} {} {} {}",
+            read_u16(rdata, 0)?,
+            read_u16(rdata, 2)?,
+            read_u16(rdata, 4)?,
+            decode_name(data, start + 6)?.0
+        ),
+        6 => {
+            let (mname, next) = decode_name(data, start)?;
+            let (rname, next) = decode_name(data, next)?;
+            let numbers: Vec<String> = (0..5)
+                .map(|i| data.get(next + i * 4..next + i * 4 + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]).to_string()))
+                .collect::<Option<_>>()?;
+            format!("{} {} {}", mname, rname, numbers.join(" "))
+        }
+        _ => rdata.iter().map(|b| format!("{:02x}", b)).collect(),
+    })
+}
+
+// 与抓包工具类似的一行摘要
+pub fn describe(message: &DnsMessage) -> String {
+    let response = message.flags & 0x8000 != 0;
+    let mut text = format!(
+        "Standard query{} 0x{:04x}",
+        if response { " response" } else { "" },
+        message.id
+    );
+    let rcode = message.flags & 0x000F;
+    if response && rcode != 0 {
+        text.push_str(match rcode {
+            1 => " Format error",
+            2 => " Server failure",
+            3 => " No such name",
+            5 => " Refused",
+            _ => " Error",
+        });
+    }
+    for q in &message.questions {
+        text.push_str(&format!(" {} {}", type_name(q.qtype), q.name));
+    }
+    for r in &message.answers {
+        text.push_str(&format!(" {} {}", type_name(r.rtype), r.data));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_and_response_round_trip() {
+        let message = DnsMessage {
+            id: 0x1234,
+            flags: 0x8180,
+            questions: parse_questions("example.com A; example.com MX").unwrap(),
+            answers: parse_records(
+                "example.com A 300 93.184.216.34; example.com AAAA 60 2606:2800:220:1::1; example.com MX 300 10 mail.example.com",
+            )
+            .unwrap(),
+            authorities: parse_records("example.com NS 3600 a.iana-servers.net").unwrap(),
+            additionals: parse_records("example.com TXT 60 v=spf1 -all; _sip._udp.example.com SRV 60 10 5 5060 sip.example.com").unwrap(),
+        };
+        let bytes = encode_message(&message).unwrap();
+        assert_eq!(&bytes[4..12], &[0, 2, 0, 3, 0, 1, 0, 2]);
+        assert_eq!(decode_message(&bytes).unwrap(), message);
+        assert_eq!(
+            describe(&message),
+            "Standard query response 0x1234 A example.com MX example.com A 93.184.216.34 AAAA 2606:2800:220:1::1 MX 10 mail.example.com"
+        );
+    }
+
+    #[test]
+    fn decodes_compressed_names() {
+        // 应答名称使用指向问题名称的压缩指针
+        let mut bytes = vec![0, 1, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        bytes.extend_from_slice(&[1, b'a', 2, b'b', b'c', 0, 0, 1, 0, 1]);
+        bytes.extend_from_slice(&[0xC0, 12, 0, 5, 0, 1, 0, 0, 0, 10, 0, 4, 1, b'x', 0xC0, 14]);
+        let message = decode_message(&bytes).unwrap();
+        assert_eq!(message.answers[0].name, "a.bc");
+        assert_eq!(message.answers[0].data, "x.bc");
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert!(parse_questions("example.com BOGUS").is_err());
+        assert!(parse_records("example.com A 300 999.1.1.1").is_err());
+        assert!(parse_records("example.com A").is_err());
+        assert!(parse_questions(&format!("{}.com", "a".repeat(64))).is_err());
+        assert!(decode_message(&[0, 1, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0]).is_none());
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::{Result, anyhow};
+
+// 单个数据报最多拆出的分片数，防止误填极小分片导致内存暴涨
+const MAX_FRAGMENTS: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FragmentOrder {
+    InOrder,
+    Reverse,
+    // 最后一个分片先发，其余按顺序
+    LastFirst,
+    Random,
+}
+
+impl FragmentOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "in_order" => Some(Self::InOrder),
+            "reverse" => Some(Self::Reverse),
+            "last_first" => Some(Self::LastFirst),
+            "random" => Some(Self::Random),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FragmentOptions {
+    // 每个分片携带的数据字节数，须为 8 的倍数
+    pub size: usize,
+    // 第一个分片的数据字节数，0 表示与 size 相同；用于构造极小的首分片
+    pub first_size: usize,
+    // 后续分片与前一分片重叠的字节数，须为 8 的倍数
+    pub overlap: usize,
+    pub order: FragmentOrder,
+    // 每个分片额外重复的次数
+    pub duplicates: usize,
+    // Random 顺序使用的种子
+    pub seed: u64,
+}
+
+// 将 frame 中从 ip_offset 开始的 IPv4 数据报拆成多个帧，ip_offset 之前的链路层头部原样复制。
+// 分片清除 DF，按 RFC 791 仅在后续分片中保留带复制位的选项，校验和重新计算。
+pub fn fragment_ipv4(frame: &[u8], ip_offset: usize, options: &FragmentOptions) -> Result<Vec<Vec<u8>>> {
+    if options.size < 8 || !options.size.is_multiple_of(8) {
+        return Err(anyhow!("分片大小须为不小于 8 的 8 的倍数: {}", options.size));
+    }
+    if !options.first_size.is_multiple_of(8) || !options.overlap.is_multiple_of(8) {
+        return Err(anyhow!("首分片大小和重叠字节数须为 8 的倍数"));
+    }
+    // 重叠须小于首分片和后续分片的大小，否则起点回退到首分片之前
+    let first_size = if options.first_size > 0 { options.first_size } else { options.size };
+    if options.overlap >= options.size || options.overlap >= first_size {
+        return Err(anyhow!("重叠字节数须小于首分片大小和分片大小"));
+    }
+
+    let ip = frame
+        .get(ip_offset..)
+        .filter(|ip| ip.len() >= 20 && ip[0] >> 4 == 4)
+        .ok_or_else(|| anyhow!("不是有效的 IPv4 数据报"))?;
+    let header_len = ((ip[0] & 0x0F) as usize) * 4;
+    let total_len = (u16::from_be_bytes([ip[2], ip[3]]) as usize).min(ip.len());
+    if header_len < 20 || header_len > total_len {
+        return Err(anyhow!("IPv4 头部长度无效: {}", header_len));
+    }
+
+    let header = &ip[..header_len];
+    let data = &ip[header_len..total_len];
+    let base_offset = ((u16::from_be_bytes([ip[6], ip[7]]) & 0x1FFF) as usize) * 8;
+    let original_mf = ip[6] & 0x20 != 0;
+    let copied_header = header_with_copied_options(header);
+
+    // 计算每个分片在原数据中的起止位置
+    let mut ranges = Vec::new();
+    let mut end = first_size.min(data.len());
+    ranges.push((0, end));
+    while end < data.len() {
+        let start = end - options.overlap;
+        end = (start + options.size).min(data.len());
+        ranges.push((start, end));
+        if ranges.len() > MAX_FRAGMENTS {
+            return Err(anyhow!("分片数量超过 {} 个", MAX_FRAGMENTS));
+        }
+    }
+
+    let last = ranges.len() - 1;
+    let mut fragments: Vec<Vec<u8>> = ranges
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, end))| {
+            let header = if i == 0 { header } else { copied_header.as_slice() };
+            let more = i != last || original_mf;
+            build_fragment(&frame[..ip_offset], header, &data[start..end], base_offset + start, more)
+        })
+        .collect();
+
+    match options.order {
+        FragmentOrder::InOrder => {}
+        FragmentOrder::Reverse => fragments.reverse(),
+        FragmentOrder::LastFirst => fragments.rotate_right(1),
+        FragmentOrder::Random => shuffle(&mut fragments, options.seed),
+    }
+
+    if options.duplicates > 0 {
+        fragments = fragments
+            .into_iter()
+            .flat_map(|f| std::iter::repeat_n(f, options.duplicates + 1))
+            .collect();
+    }
+    Ok(fragments)
+}
+
+fn build_fragment(link: &[u8], header: &[u8], data: &[u8], offset: usize, more: bool) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(link.len() + header.len() + data.len());
+    frame.extend_from_slice(link);
+    frame.extend_from_slice(header);
+    frame.extend_from_slice(data);
+
+    let ip = &mut frame[link.len()..];
+    ip[0] = (ip[0] & 0xF0) | (header.len() / 4) as u8;
+    let total_len = ip.len() as u16;
+    ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+    // 保留保留位，清除 DF，按需设置 MF
+    let flags = (ip[6] & 0x80) as u16 | if more { 0x20 } else { 0x00 };
+    let frag = (flags << 8) | ((offset / 8) as u16 & 0x1FFF);
+    ip[6..8].copy_from_slice(&frag.to_be_bytes());
+    ip[10..12].copy_from_slice(&[0, 0]);
+    let checksum = ipv4_checksum(&ip[..header.len()]);
+    ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+    frame
+}
+
+// 后续分片的头部：只保留类型字节最高位（复制位）为 1 的选项
+fn header_with_copied_options(header: &[u8]) -> Vec<u8> {
+    let mut result = header[..20].to_vec();
+    let options = &header[20..];
+    let mut i = 0;
+    while i < options.len() {
+        match options[i] {
+            0 => break,
+            1 => i += 1,
+            kind => {
+                let len = options.get(i + 1).copied().unwrap_or(0) as usize;
+                if len < 2 || i + len > options.len() {
+                    break;
+                }
+                if kind & 0x80 != 0 {
+                    result.extend_from_slice(&options[i..i + len]);
+                }
+                i += len;
+            }
+        }
+    }
+    while !result.len().is_multiple_of(4) {
+        result.push(0);
+    }
+    result
+}
+
+pub fn ipv4_checksum(header: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    for chunk in header.chunks(2) {
+        let word = if chunk.len() == 2 { u16::from_be_bytes([chunk[0], chunk[1]]) } else { (chunk[0] as u16) << 8 };
+        sum += word as u32;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !sum as u16
+}
+
+// Fisher-Yates 洗牌，xorshift64 作为随机源，避免引入额外依赖
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed | 1;
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以太网头 + 20 字节 IPv4 头（DF 置位）+ 指定长度的数据
+    fn datagram(data_len: usize, options: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 14];
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        let header_len = 20 + options.len();
+        let total = (header_len + data_len) as u16;
+        frame.push(0x40 | (header_len / 4) as u8);
+        frame.push(0);
+        frame.extend_from_slice(&total.to_be_bytes());
+        frame.extend_from_slice(&[0x12, 0x34, 0x40, 0x00, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2]);
+        frame.extend_from_slice(options);
+        frame.extend((0..data_len).map(|i| i as u8));
+        frame
+    }
+
+    fn options(size: usize) -> FragmentOptions {
+        FragmentOptions { size, first_size: 0, overlap: 0, order: FragmentOrder::InOrder, duplicates: 0, seed: 1 }
+    }
+
+    fn offset_and_mf(frame: &[u8]) -> (usize, bool) {
+        let word = u16::from_be_bytes([frame[20], frame[21]]);
+        (((word & 0x1FFF) as usize) * 8, word & 0x2000 != 0)
+    }
+
+    #[test]
+    fn splits_on_eight_byte_boundaries() {
+        let frame = datagram(50, &[]);
+        let frags = fragment_ipv4(&frame, 14, &options(24)).unwrap();
+        assert_eq!(frags.len(), 3);
+        assert_eq!(frags.iter().map(|f| offset_and_mf(f)).collect::<Vec<_>>(), vec![(0, true), (24, true), (48, false)]);
+        assert_eq!(frags[2].len(), 14 + 20 + 2);
+        for f in &frags {
+            assert_eq!(ipv4_checksum(&f[14..34]), 0);
+            assert_eq!(u16::from_be_bytes([f[16], f[17]]) as usize, f.len() - 14);
+        }
+        // 重组后的数据与原数据一致
+        let data: Vec<u8> = frags.iter().flat_map(|f| f[34..].to_vec()).collect();
+        assert_eq!(data, frame[34..]);
+    }
+
+    #[test]
+    fn tiny_first_and_overlap() {
+        let frame = datagram(40, &[]);
+        let mut opts = options(16);
+        opts.first_size = 8;
+        let frags = fragment_ipv4(&frame, 14, &opts).unwrap();
+        let offsets: Vec<usize> = frags.iter().map(|f| offset_and_mf(f).0).collect();
+        assert_eq!(offsets, vec![0, 8, 24]);
+
+        opts.first_size = 16;
+        opts.overlap = 8;
+        let frags = fragment_ipv4(&frame, 14, &opts).unwrap();
+        let offsets: Vec<usize> = frags.iter().map(|f| offset_and_mf(f).0).collect();
+        assert_eq!(offsets, vec![0, 8, 16, 24]);
+        assert_eq!(&frags[1][34..], &frame[34 + 8..34 + 24]);
+    }
+
+    #[test]
+    fn order_and_duplicates() {
+        let frame = datagram(32, &[]);
+        let mut opts = options(8);
+        opts.order = FragmentOrder::LastFirst;
+        opts.duplicates = 1;
+        let frags = fragment_ipv4(&frame, 14, &opts).unwrap();
+        let offsets: Vec<usize> = frags.iter().map(|f| offset_and_mf(f).0).collect();
+        assert_eq!(offsets, vec![24, 24, 0, 0, 8, 8, 16, 16]);
+
+        opts.order = FragmentOrder::Random;
+        opts.duplicates = 0;
+        let mut shuffled: Vec<usize> = fragment_ipv4(&frame, 14, &opts).unwrap().iter().map(|f| offset_and_mf(f).0).collect();
+        shuffled.sort();
+        assert_eq!(shuffled, vec![0, 8, 16, 24]);
+    }
+
+    #[test]
+    fn only_copied_options_follow_first_fragment() {
+        // 记录路由（不复制）+ 安全选项样例（复制位置位）
+        let opts_bytes = [0x07, 7, 4, 0, 0, 0, 0, 0x82, 4, 0xAA, 0xBB, 0];
+        let frame = datagram(16, &opts_bytes);
+        let frags = fragment_ipv4(&frame, 14, &options(8)).unwrap();
+        assert_eq!(frags[0][14] & 0x0F, 8);
+        assert_eq!(frags[1][14] & 0x0F, 6);
+        assert_eq!(&frags[1][34..38], &[0x82, 4, 0xAA, 0xBB]);
+    }
+
+    #[test]
+    fn rejects_unaligned_sizes() {
+        let frame = datagram(16, &[]);
+        assert!(fragment_ipv4(&frame, 14, &options(12)).is_err());
+        assert!(fragment_ipv4(&frame[..20], 14, &options(8)).is_err());
+    }
+
+    #[test]
+    fn rejects_overlap_not_smaller_than_first_fragment() {
+        let frame = datagram(64, &[]);
+        let mut opts = options(24);
+        opts.first_size = 8;
+        opts.overlap = 16;
+        assert!(fragment_ipv4(&frame, 14, &opts).is_err());
+        opts.overlap = 8;
+        assert!(fragment_ipv4(&frame, 14, &opts).is_err());
+    }
+}
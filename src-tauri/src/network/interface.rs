@@ -210,6 +210,74 @@ impl NetworkInterface {
         Err(anyhow!("未找到网络接口: {}", name))
     }
 
+    // 接口自身的 MAC 和第一个 IPv4 地址，用作主动探测报文的源地址
+    pub fn local_addresses(name: &str) -> (Option<String>, Option<String>) {
+        Self::list_interfaces()
+            .ok()
+            .and_then(|list| list.into_iter().find(|i| i.name == name))
+            .map(|info| {
+                let ip = info.addresses.iter().find(|a| a.parse::<std::net::Ipv4Addr>().is_ok()).cloned();
+                (info.mac, ip)
+            })
+            .unwrap_or((None, None))
+    }
+
+    // 接口的 IPv4 地址及掩码，用于判断目标是否在本网段
+    pub fn ipv4_networks(name: &str) -> Vec<(std::net::Ipv4Addr, std::net::Ipv4Addr)> {
+        Device::list()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|d| d.name == name)
+            .flat_map(|d| d.addresses)
+            .filter_map(|a| match (a.addr, a.netmask) {
+                (std::net::IpAddr::V4(addr), Some(std::net::IpAddr::V4(mask))) => Some((addr, mask)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // 阻塞读取、带超时的即时模式捕获句柄，用于持续接收高速流量；读取超时返回 TimeoutExpired
+    pub fn open_receive_capture(name: &str, filter: &str, buffer_size: i32, timeout_ms: i32) -> Result<pcap::Capture<pcap::Active>> {
+        let device = Device::list()
+            .map_err(|e| anyhow!("获取网络设备列表失败: {}", e))?
+            .into_iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| anyhow!("未找到网络接口: {}", name))?;
+        let mut capture = pcap::Capture::from_device(device)
+            .map_err(|e| anyhow!("创建捕获实例失败: {}", e))?
+            .promisc(true)
+            .immediate_mode(true)
+            .buffer_size(buffer_size)
+            .timeout(timeout_ms)
+            .open()
+            .map_err(|e| anyhow!("打开网络捕获失败: {}", e))?;
+        capture
+            .filter(filter, true)
+            .map_err(|e| anyhow!("设置BPF过滤器失败: {} (过滤器: {})", e, filter))?;
+        Ok(capture)
+    }
+
+    // 非阻塞、即时模式的捕获句柄，主动探测时在同一句柄上边发送边读取应答
+    pub fn open_probe_capture(name: &str, filter: &str) -> Result<pcap::Capture<pcap::Active>> {
+        let device = Device::list()
+            .map_err(|e| anyhow!("获取网络设备列表失败: {}", e))?
+            .into_iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| anyhow!("未找到网络接口: {}", name))?;
+        let mut capture = pcap::Capture::from_device(device)
+            .map_err(|e| anyhow!("创建捕获实例失败: {}", e))?
+            .promisc(false)
+            .immediate_mode(true)
+            .open()
+            .map_err(|e| anyhow!("打开网络捕获失败: {}", e))?
+            .setnonblock()
+            .map_err(|e| anyhow!("设置非阻塞模式失败: {}", e))?;
+        capture
+            .filter(filter, true)
+            .map_err(|e| anyhow!("设置BPF过滤器失败: {} (过滤器: {})", e, filter))?;
+        Ok(capture)
+    }
+
     pub fn get_default() -> Result<Self> {
         let devices = Device::list()?;
         
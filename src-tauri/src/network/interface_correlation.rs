@@ -0,0 +1,257 @@
+use serde::{Serialize, Deserialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use crate::network::packet_sniffer::CapturedPacket;
+
+// 未指定时认为相隔 100ms 以上的相同报文不是同一次转发
+pub const DEFAULT_MAX_LATENCY_US: u64 = 100_000;
+
+// 同一报文在两个接口上被捕获的记录
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ForwardedFrame {
+    pub ingress_packet_id: String,
+    pub egress_packet_id: String,
+    pub ingress_interface: String,
+    pub egress_interface: String,
+    pub ingress_timestamp_us: u64,
+    pub latency_us: u64,
+    pub protocol: String,
+    pub info: String,
+}
+
+// 按入接口→出接口方向汇总的转发时延
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PathLatency {
+    pub ingress_interface: String,
+    pub egress_interface: String,
+    pub frames: u64,
+    pub min_us: u64,
+    pub avg_us: f64,
+    pub max_us: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct CorrelationReport {
+    pub paths: Vec<PathLatency>,
+    pub frames: Vec<ForwardedFrame>,
+    // 没有在其他接口上找到对应报文的数量
+    pub unmatched: usize,
+}
+
+// 报文指纹：IPv4 报文跳过 VLAN 标签，并排除转发时会变化的 TTL 和头部校验和，
+// 其余报文按以太网类型之后的内容计算
+pub fn fingerprint(raw: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut offset = 12;
+    while let Some(ethertype) = raw.get(offset..offset + 2) {
+        let ethertype = u16::from_be_bytes([ethertype[0], ethertype[1]]);
+        if ethertype == 0x8100 || ethertype == 0x88a8 {
+            offset += 4;
+            continue;
+        }
+        let l3 = &raw[offset + 2..];
+        let header_len = l3.first().map(|b| ((b & 0x0f) as usize * 4).max(20)).unwrap_or(20);
+        // 头部长度超出帧长度的畸形报文按原始内容计算
+        if ethertype == 0x0800 && header_len <= l3.len() {
+            let total_len = (u16::from_be_bytes([l3[2], l3[3]]) as usize).clamp(header_len, l3.len());
+            l3[..8].hash(&mut hasher);
+            l3[9].hash(&mut hasher);
+            l3[12..total_len].hash(&mut hasher);
+        } else {
+            ethertype.hash(&mut hasher);
+            l3.hash(&mut hasher);
+        }
+        return hasher.finish();
+    }
+    raw.hash(&mut hasher);
+    hasher.finish()
+}
+
+// 在按时间排序的多接口报文中匹配相同报文：同一指纹在另一个接口上再次出现时记为一次转发，
+// 多跳时逐段匹配；同一接口上的重复报文视为重传，从最新一次开始计算
+pub fn correlate(packets: &[CapturedPacket], max_latency_us: u64) -> CorrelationReport {
+    let mut ordered: Vec<&CapturedPacket> = packets.iter().filter(|p| p.interface.is_some()).collect();
+    ordered.sort_by_key(|p| p.timestamp_us);
+
+    let mut last_seen: HashMap<u64, (&CapturedPacket, bool)> = HashMap::new();
+    let mut frames = Vec::new();
+    let mut unmatched = 0;
+
+    for packet in ordered {
+        let key = fingerprint(&packet.raw_data);
+        let mut matched = false;
+        if let Some((previous, previous_matched)) = last_seen.get_mut(&key) {
+            let latency = packet.timestamp_us.saturating_sub(previous.timestamp_us);
+            if previous.interface != packet.interface && latency <= max_latency_us {
+                frames.push(ForwardedFrame {
+                    ingress_packet_id: previous.id.clone(),
+                    egress_packet_id: packet.id.clone(),
+                    ingress_interface: previous.interface.clone().unwrap_or_default(),
+                    egress_interface: packet.interface.clone().unwrap_or_default(),
+                    ingress_timestamp_us: previous.timestamp_us,
+                    latency_us: latency,
+                    protocol: packet.protocol.clone(),
+                    info: packet.info.clone(),
+                });
+                *previous_matched = true;
+                matched = true;
+            }
+        }
+        if let Some((_, false)) = last_seen.insert(key, (packet, matched)) {
+            unmatched += 1;
+        }
+    }
+    unmatched += last_seen.values().filter(|(_, matched)| !matched).count();
+
+    let mut by_path: BTreeMap<(String, String), Vec<u64>> = BTreeMap::new();
+    for frame in &frames {
+        by_path
+            .entry((frame.ingress_interface.clone(), frame.egress_interface.clone()))
+            .or_default()
+            .push(frame.latency_us);
+    }
+    let paths = by_path
+        .into_iter()
+        .map(|((ingress_interface, egress_interface), latencies)| PathLatency {
+            ingress_interface,
+            egress_interface,
+            frames: latencies.len() as u64,
+            min_us: latencies.iter().copied().min().unwrap_or(0),
+            avg_us: latencies.iter().sum::<u64>() as f64 / latencies.len() as f64,
+            max_us: latencies.iter().copied().max().unwrap_or(0),
+        })
+        .collect();
+
+    CorrelationReport { paths, frames, unmatched }
+}
+
+// 多接口捕获的合并缓冲：报文暂存一个时间窗口，按 pcap 时间戳排序后输出，
+// 使各接口线程交错送达的报文在缓存中仍按时间先后排列
+pub struct TimelineMerger {
+    window_us: u64,
+    pending: Vec<CapturedPacket>,
+    latest_us: u64,
+}
+
+impl TimelineMerger {
+    pub fn new(window_us: u64) -> Self {
+        Self { window_us, pending: Vec::new(), latest_us: 0 }
+    }
+
+    // 加入一个报文，返回已超出合并窗口、可以按序输出的报文
+    pub fn push(&mut self, packet: CapturedPacket) -> Vec<CapturedPacket> {
+        self.latest_us = self.latest_us.max(packet.timestamp_us);
+        let index = self.pending.partition_point(|p| p.timestamp_us <= packet.timestamp_us);
+        self.pending.insert(index, packet);
+        let ready = self.pending.partition_point(|p| p.timestamp_us + self.window_us <= self.latest_us);
+        self.pending.drain(..ready).collect()
+    }
+
+    // 输出全部暂存的报文，用于空闲或停止时
+    pub fn flush(&mut self) -> Vec<CapturedPacket> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipv4_frame(ident: u8, ttl: u8, checksum: u16, vlan: Option<u16>) -> Vec<u8> {
+        let mut frame = vec![0xaa; 12];
+        if let Some(vid) = vlan {
+            frame.extend_from_slice(&[0x81, 0x00]);
+            frame.extend_from_slice(&vid.to_be_bytes());
+        }
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(&[0x45, 0, 0, 28, 0x12, ident, 0, 0, ttl, 17]);
+        frame.extend_from_slice(&checksum.to_be_bytes());
+        frame.extend_from_slice(&[10, 0, 0, 1, 10, 0, 1, 1]);
+        frame.extend_from_slice(&[0x30, 0x39, 0x00, 0x35, 0, 8, 0, 0]);
+        // 以太网填充不参与指纹
+        frame.extend_from_slice(&[0; 18]);
+        frame
+    }
+
+    fn packet(id: &str, interface: &str, timestamp_us: u64, raw_data: Vec<u8>) -> CapturedPacket {
+        CapturedPacket {
+            id: id.to_string(),
+            timestamp: timestamp_us / 1000,
+            protocol: "udp".to_string(),
+            timestamp_us,
+            interface: Some(interface.to_string()),
+            ..CapturedPacket::from_frame(raw_data)
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_ttl_checksum_vlan_and_padding() {
+        let base = fingerprint(&ipv4_frame(1, 64, 0x1111, None));
+        let mut padded = ipv4_frame(1, 63, 0x2222, Some(100));
+        padded.truncate(padded.len() - 10);
+        assert_eq!(base, fingerprint(&padded));
+
+        let mut other = ipv4_frame(1, 64, 0x1111, None);
+        other[19] = 0x35;
+        assert_ne!(base, fingerprint(&other));
+    }
+
+    #[test]
+    fn fingerprint_handles_truncated_header() {
+        let mut frame = ipv4_frame(1, 64, 0x1111, None);
+        // IHL=15 声称 60 字节头部，但 L3 只有 46 字节
+        frame[14] = 0x4f;
+        let truncated = fingerprint(&frame);
+        // 按原始内容计算，TTL 也参与指纹
+        frame[22] = 63;
+        assert_ne!(truncated, fingerprint(&frame));
+        fingerprint(&frame[..15]);
+    }
+
+    #[test]
+    fn correlates_forwarded_frames_per_path() {
+        let packets = vec![
+            packet("a1", "eth0", 1_000, ipv4_frame(1, 64, 1, None)),
+            packet("b1", "eth1", 1_250, ipv4_frame(1, 63, 2, None)),
+            packet("a2", "eth0", 2_000, ipv4_frame(2, 64, 1, None)),
+            // 经过两跳转发
+            packet("a3", "eth0", 3_000, ipv4_frame(3, 10, 1, None)),
+            packet("b3", "eth1", 3_150, ipv4_frame(3, 9, 2, None)),
+            packet("c3", "eth2", 3_400, ipv4_frame(3, 8, 3, Some(7))),
+            // 同一接口上的重复报文以及超过时延上限的报文都不匹配
+            packet("b1-again", "eth1", 5_000, ipv4_frame(1, 63, 2, None)),
+            packet("a2-late", "eth1", 900_000, ipv4_frame(2, 63, 2, None)),
+        ];
+        let report = correlate(&packets, DEFAULT_MAX_LATENCY_US);
+        let pairs: Vec<(&str, &str, u64)> = report
+            .frames
+            .iter()
+            .map(|f| (f.ingress_packet_id.as_str(), f.egress_packet_id.as_str(), f.latency_us))
+            .collect();
+        assert_eq!(pairs, vec![("a1", "b1", 250), ("a3", "b3", 150), ("b3", "c3", 250)]);
+        assert_eq!(report.paths.len(), 2);
+        assert_eq!(report.paths[0], PathLatency {
+            ingress_interface: "eth0".to_string(),
+            egress_interface: "eth1".to_string(),
+            frames: 2,
+            min_us: 150,
+            avg_us: 200.0,
+            max_us: 250,
+        });
+        assert_eq!(report.unmatched, 3);
+    }
+
+    #[test]
+    fn merger_orders_within_window() {
+        let mut merger = TimelineMerger::new(50);
+        let ids = |packets: Vec<CapturedPacket>| packets.into_iter().map(|p| p.id).collect::<Vec<_>>();
+        assert!(merger.push(packet("b", "eth1", 120, Vec::new())).is_empty());
+        assert!(merger.push(packet("a", "eth0", 100, Vec::new())).is_empty());
+        assert_eq!(ids(merger.push(packet("c", "eth0", 160, Vec::new()))), vec!["a"]);
+        assert_eq!(ids(merger.flush()), vec!["b", "c"]);
+
+        let mut single = TimelineMerger::new(0);
+        assert_eq!(ids(single.push(packet("x", "eth0", 5, Vec::new()))), vec!["x"]);
+    }
+}
@@ -1,20 +1,36 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use crate::network::netns;
+
+// 隔离中网卡的快照文件，位于应用数据目录
+const SNAPSHOT_FILE: &str = "isolation_snapshots.json";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpConfig {
     pub address: String,
+    // IPv6 地址只使用 prefix_len，此字段为空
     pub netmask: String,
     pub gateway: Option<String>,
+    #[serde(default)]
+    pub prefix_len: u8,
+    #[serde(default)]
+    pub ipv6: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouteConfig {
     pub destination: String,
+    // 直连路由为空
     pub gateway: String,
     pub interface: String,
+    #[serde(default)]
+    pub metric: Option<u32>,
+    #[serde(default)]
+    pub ipv6: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,18 +41,80 @@ pub struct InterfaceSnapshot {
     pub mac_address: Option<String>,
     pub is_up: bool,
     pub is_isolated: bool,
+    // 以命名空间方式隔离时网卡所在的命名空间
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub mtu: Option<u32>,
+    #[serde(default)]
+    pub promisc: bool,
+    #[serde(default)]
+    pub default_gateway: Option<String>,
+    #[serde(default)]
+    pub dns_servers: Vec<String>,
+    // 地址由 DHCP 分配（目前仅 Windows 可识别）
+    #[serde(default)]
+    pub dhcp: bool,
+}
+
+// 恢复后的配置与快照不一致的一项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+// 上次运行遗留的隔离网卡
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleIsolation {
+    pub snapshot: InterfaceSnapshot,
+    // 恢复时将改变的配置：expected 为快照中的值，actual 为网卡当前的值
+    pub pending: Vec<SnapshotDiff>,
+    // 无法读取网卡当前配置的原因，例如网卡仍在隔离命名空间中
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleRestoreResult {
+    pub interface_name: String,
+    // 恢复后仍与快照不一致的项
+    pub diffs: Vec<SnapshotDiff>,
+    pub error: Option<String>,
 }
 
-#[derive(Debug)]
+// 隔离方式：清空网卡地址，或把网卡移入独立的网络命名空间（仅 Linux）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IsolationMode {
+    #[default]
+    Flush,
+    Namespace,
+}
+
+#[derive(Debug, PartialEq)]
 pub enum Platform {
     MacOS,
     Linux,
     Windows,
 }
 
+// netsh interface ip show config 的解析结果
+#[derive(Debug, Default)]
+struct WindowsIpConfig {
+    addresses: Vec<IpConfig>,
+    default_gateway: Option<String>,
+    dns_servers: Vec<String>,
+    dhcp: bool,
+}
+
 pub struct InterfaceManager {
     snapshots: HashMap<String, InterfaceSnapshot>,
     platform: Platform,
+    // 隔离快照的持久化文件，未设置时快照只保存在内存中
+    snapshot_file: Option<PathBuf>,
+    // 启动时从快照文件加载的网卡
+    stale: Vec<String>,
 }
 
 impl InterfaceManager {
@@ -45,6 +123,8 @@ impl InterfaceManager {
         Ok(Self {
             snapshots: HashMap::new(),
             platform,
+            snapshot_file: None,
+            stale: Vec::new(),
         })
     }
 
@@ -67,7 +147,7 @@ impl InterfaceManager {
             println!("[开发模式] 模拟管理员权限检查通过");
             return Ok(true);
         }
-        
+
         match self.platform {
             Platform::MacOS | Platform::Linux => {
                 // 尝试执行一个需要权限的命令来测试
@@ -92,63 +172,70 @@ impl InterfaceManager {
 
     // 备份网卡配置
     pub fn backup_interface(&mut self, interface_name: &str) -> Result<()> {
-        let snapshot = match self.platform {
-            Platform::MacOS => self.backup_interface_macos(interface_name)?,
-            Platform::Linux => self.backup_interface_linux(interface_name)?,
-            Platform::Windows => self.backup_interface_windows(interface_name)?,
-        };
-
+        let snapshot = self.read_interface(interface_name)?;
         self.snapshots.insert(interface_name.to_string(), snapshot);
         Ok(())
     }
 
+    // 读取网卡当前配置
+    fn read_interface(&self, interface_name: &str) -> Result<InterfaceSnapshot> {
+        match self.platform {
+            Platform::MacOS => self.backup_interface_macos(interface_name),
+            Platform::Linux => self.backup_interface_linux(interface_name),
+            Platform::Windows => self.backup_interface_windows(interface_name),
+        }
+    }
+
     // macOS备份实现
     fn backup_interface_macos(&self, interface_name: &str) -> Result<InterfaceSnapshot> {
         // 获取接口信息
         let ifconfig_output = Command::new("ifconfig")
             .arg(interface_name)
             .output()?;
-        
+
         if !ifconfig_output.status.success() {
             return Err(anyhow!("无法获取网卡 {} 信息", interface_name));
         }
 
         let config_str = String::from_utf8(ifconfig_output.stdout)?;
-        
+
         // 解析IP地址
-        let mut ip_addresses = Vec::new();
-        for line in config_str.lines() {
-            if line.trim().starts_with("inet ") {
-                if let Some(ip_info) = self.parse_macos_inet_line(line) {
-                    ip_addresses.push(ip_info);
-                }
+        let ip_addresses = self.parse_macos_addresses(&config_str);
+
+        // 获取 IPv4 和 IPv6 默认路由，只保留经过本网卡的
+        let mut routes = Vec::new();
+        for family in [None, Some("-inet6")] {
+            let mut args = vec!["-n", "get"];
+            args.extend(family);
+            args.push("default");
+            let route_output = Command::new("route").args(&args).output()?;
+            if route_output.status.success() {
+                let mut parsed = self.parse_macos_routes(&String::from_utf8_lossy(&route_output.stdout), interface_name);
+                parsed.iter_mut().for_each(|r| r.ipv6 = family.is_some());
+                routes.extend(parsed);
             }
         }
 
-        // 获取路由信息
-        let route_output = Command::new("route")
-            .args(&["-n", "get", "default"])
-            .output()?;
-        
-        let routes = if route_output.status.success() {
-            self.parse_macos_routes(&String::from_utf8(route_output.stdout)?, interface_name)
-        } else {
-            Vec::new()
-        };
-
         // 获取MAC地址
         let mac_address = self.extract_mac_from_ifconfig(&config_str);
 
         // 检查接口状态
-        let is_up = config_str.contains("UP,");
+        let (is_up, promisc, mtu) = self.parse_link_state(&config_str);
 
+        // macOS 的 DNS 按网络服务而不是网卡配置，隔离不影响 DNS，不做记录
         Ok(InterfaceSnapshot {
             name: interface_name.to_string(),
+            default_gateway: self.default_gateway(&routes),
             ip_addresses,
             routes,
             mac_address,
             is_up,
             is_isolated: false,
+            namespace: None,
+            mtu,
+            promisc,
+            dns_servers: Vec::new(),
+            dhcp: false,
         })
     }
 
@@ -156,42 +243,57 @@ impl InterfaceManager {
     fn backup_interface_linux(&self, interface_name: &str) -> Result<InterfaceSnapshot> {
         // 获取IP地址配置
         let ip_output = Command::new("ip")
-            .args(&["addr", "show", interface_name])
+            .args(["addr", "show", "dev", interface_name])
             .output()?;
-        
+
         if !ip_output.status.success() {
             return Err(anyhow!("无法获取网卡 {} 信息", interface_name));
         }
 
         let config_str = String::from_utf8(ip_output.stdout)?;
-        
+
         // 解析IP地址
         let ip_addresses = self.parse_linux_ip_addresses(&config_str);
 
-        // 获取路由信息
-        let route_output = Command::new("ip")
-            .args(&["route", "show", "dev", interface_name])
-            .output()?;
-        
-        let routes = if route_output.status.success() {
-            self.parse_linux_routes(&String::from_utf8(route_output.stdout)?, interface_name)
-        } else {
-            Vec::new()
-        };
+        // 获取 IPv4 和 IPv6 路由信息
+        let mut routes = Vec::new();
+        for (family, ipv6) in [("-4", false), ("-6", true)] {
+            let route_output = Command::new("ip")
+                .args([family, "route", "show", "dev", interface_name])
+                .output()?;
+            if route_output.status.success() {
+                routes.extend(self.parse_linux_routes(&String::from_utf8_lossy(&route_output.stdout), interface_name, ipv6));
+            }
+        }
 
         // 获取MAC地址
         let mac_address = self.extract_mac_from_ip_output(&config_str);
 
-        // 检查接口状态
-        let is_up = config_str.contains("state UP");
+        // 检查接口状态（管理状态，而不是取决于链路的运行状态）
+        let (is_up, promisc, mtu) = self.parse_link_state(&config_str);
+
+        // 网卡级 DNS 由 systemd-resolved 管理，未使用 resolved 时为空
+        let dns_servers = Command::new("resolvectl")
+            .args(["dns", interface_name])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| self.parse_resolvectl_dns(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default();
 
         Ok(InterfaceSnapshot {
             name: interface_name.to_string(),
+            default_gateway: self.default_gateway(&routes),
             ip_addresses,
             routes,
             mac_address,
             is_up,
             is_isolated: false,
+            namespace: None,
+            mtu,
+            promisc,
+            dns_servers,
+            dhcp: false,
         })
     }
 
@@ -201,33 +303,68 @@ impl InterfaceManager {
         let output = Command::new("netsh")
             .args(&["interface", "ip", "show", "config", interface_name])
             .output()?;
-        
+
         if !output.status.success() {
             return Err(anyhow!("无法获取网卡 {} 信息", interface_name));
         }
 
         let config_str = String::from_utf8_lossy(&output.stdout);
-        
+
         // 解析配置信息
-        let ip_addresses = self.parse_windows_config(&config_str);
-        let routes = Vec::new(); // Windows路由解析较复杂，暂时留空
+        let config = self.parse_windows_config(&config_str);
+        let routes = config.default_gateway.iter()
+            .map(|gateway| RouteConfig {
+                destination: "0.0.0.0/0".to_string(),
+                gateway: gateway.clone(),
+                interface: interface_name.to_string(),
+                metric: None,
+                ipv6: false,
+            })
+            .collect();
+
+        let mtu = Command::new("netsh")
+            .args(["interface", "ipv4", "show", "subinterface", interface_name])
+            .output()
+            .ok()
+            .and_then(|output| self.parse_windows_mtu(&String::from_utf8_lossy(&output.stdout), interface_name));
+
+        let is_up = Command::new("netsh")
+            .args(["interface", "show", "interface", interface_name])
+            .output()
+            .ok()
+            .and_then(|output| self.parse_windows_admin_state(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or(true);
 
         Ok(InterfaceSnapshot {
             name: interface_name.to_string(),
-            ip_addresses,
+            ip_addresses: config.addresses,
             routes,
             mac_address: None,
-            is_up: true,
+            is_up,
             is_isolated: false,
+            namespace: None,
+            mtu,
+            promisc: false,
+            default_gateway: config.default_gateway,
+            dns_servers: config.dns_servers,
+            dhcp: config.dhcp,
         })
     }
 
     // 隔离网卡（移除IP配置）
     pub fn isolate_interface(&mut self, interface_name: &str) -> Result<()> {
+        self.isolate_interface_with_mode(interface_name, IsolationMode::Flush)
+    }
+
+    pub fn isolate_interface_with_mode(&mut self, interface_name: &str, mode: IsolationMode) -> Result<()> {
+        if mode == IsolationMode::Namespace && self.platform != Platform::Linux {
+            return Err(anyhow!("网络命名空间隔离仅支持 Linux"));
+        }
+
         // 开发模式：模拟网卡隔离
         if cfg!(debug_assertions) {
             println!("[开发模式] 模拟隔离网卡: {}", interface_name);
-            
+
             // 创建一个模拟的快照
             let mock_snapshot = InterfaceSnapshot {
                 name: interface_name.to_string(),
@@ -235,33 +372,66 @@ impl InterfaceManager {
                     address: "192.168.1.100".to_string(),
                     netmask: "255.255.255.0".to_string(),
                     gateway: Some("192.168.1.1".to_string()),
+                    prefix_len: 24,
+                    ipv6: false,
+                }],
+                routes: vec![RouteConfig {
+                    destination: "default".to_string(),
+                    gateway: "192.168.1.1".to_string(),
+                    interface: interface_name.to_string(),
+                    metric: Some(100),
+                    ipv6: false,
                 }],
-                routes: vec![],
                 mac_address: Some("00:11:22:33:44:55".to_string()),
                 is_up: true,
                 is_isolated: true,
+                namespace: None,
+                mtu: Some(1500),
+                promisc: false,
+                default_gateway: Some("192.168.1.1".to_string()),
+                dns_servers: vec!["192.168.1.1".to_string()],
+                dhcp: false,
             };
-            
+
             self.snapshots.insert(interface_name.to_string(), mock_snapshot);
             println!("[开发模式] 网卡 {} 隔离模拟完成", interface_name);
             return Ok(());
         }
-        
-        // 先备份当前配置
-        if !self.snapshots.contains_key(interface_name) {
-            self.backup_interface(interface_name)?;
-        }
 
-        // 执行隔离操作
-        match self.platform {
-            Platform::MacOS => self.isolate_interface_macos(interface_name)?,
-            Platform::Linux => self.isolate_interface_linux(interface_name)?,
-            Platform::Windows => self.isolate_interface_windows(interface_name)?,
+        // 先备份当前配置；已隔离时保留原快照，否则重新读取，避免沿用上次测试前的旧配置
+        if !self.is_isolated(interface_name) {
+            self.backup_interface(interface_name)?;
         }
 
-        // 更新隔离状态
+        // 隔离前先把快照写入磁盘，程序中途退出后下次启动仍可恢复
+        let previous = self.snapshots.get(interface_name)
+            .ok_or_else(|| anyhow!("未找到网卡备份信息"))?
+            .clone();
+        let namespace = (mode == IsolationMode::Namespace).then(|| netns::namespace_name(interface_name));
         if let Some(snapshot) = self.snapshots.get_mut(interface_name) {
             snapshot.is_isolated = true;
+            snapshot.namespace = namespace.clone();
+        }
+        if let Err(e) = self.persist() {
+            self.snapshots.insert(interface_name.to_string(), previous);
+            return Err(anyhow!("保存网卡快照失败: {}", e));
+        }
+
+        // 执行隔离操作
+        let result = match (&namespace, &self.platform) {
+            (Some(namespace), _) => netns::move_into_namespace(interface_name, namespace),
+            (None, Platform::MacOS) => self.isolate_interface_macos(interface_name),
+            (None, Platform::Linux) => self.isolate_interface_linux(interface_name),
+            (None, Platform::Windows) => self.isolate_interface_windows(interface_name),
+        };
+
+        // 隔离失败时回退到隔离前的状态
+        if let Err(e) = result {
+            self.snapshots.insert(interface_name.to_string(), previous);
+            if let Err(e) = self.persist() {
+                eprintln!("保存网卡快照失败: {}", e);
+            }
+            return Err(e);
         }
 
         Ok(())
@@ -273,13 +443,13 @@ impl InterfaceManager {
         let snapshot = self.snapshots.get(interface_name)
             .ok_or_else(|| anyhow!("未找到网卡备份信息"))?;
 
-        for ip_config in &snapshot.ip_addresses {
+        for ip_config in snapshot.ip_addresses.iter().filter(|ip| !ip.ipv6) {
             let output = Command::new("sudo")
                 .args(&["ifconfig", interface_name, "inet", &ip_config.address, "delete"])
                 .output()?;
-            
+
             if !output.status.success() {
-                eprintln!("删除IP {} 失败: {}", ip_config.address, 
+                eprintln!("删除IP {} 失败: {}", ip_config.address,
                          String::from_utf8_lossy(&output.stderr));
             }
         }
@@ -300,7 +470,7 @@ impl InterfaceManager {
             .output()?;
 
         if !output.status.success() {
-            return Err(anyhow!("清空网卡IP地址失败: {}", 
+            return Err(anyhow!("清空网卡IP地址失败: {}",
                              String::from_utf8_lossy(&output.stderr)));
         }
 
@@ -316,39 +486,60 @@ impl InterfaceManager {
     fn isolate_interface_windows(&self, interface_name: &str) -> Result<()> {
         // 设置为无效IP
         let output = Command::new("netsh")
-            .args(&["interface", "ip", "set", "address", interface_name, 
+            .args(&["interface", "ip", "set", "address", interface_name,
                    "static", "0.0.0.0", "255.255.255.255"])
             .output()?;
 
         if !output.status.success() {
-            return Err(anyhow!("设置网卡隔离失败: {}", 
+            return Err(anyhow!("设置网卡隔离失败: {}",
                              String::from_utf8_lossy(&output.stderr)));
         }
 
         Ok(())
     }
 
-    // 恢复网卡配置
+    // 恢复网卡配置，恢复后的配置与快照不一致时返回错误
     pub fn restore_interface(&mut self, interface_name: &str) -> Result<()> {
+        let diffs = self.restore_interface_verified(interface_name)?;
+        if !diffs.is_empty() {
+            return Err(anyhow!("恢复后的网卡配置与快照不一致: {}", describe_diffs(&diffs)));
+        }
+        Ok(())
+    }
+
+    // 恢复网卡配置并重新读取校验，返回与快照不一致的项；
+    // 存在差异时网卡仍标记为隔离状态，可以再次恢复
+    pub fn restore_interface_verified(&mut self, interface_name: &str) -> Result<Vec<SnapshotDiff>> {
         // 开发模式：模拟网卡恢复
         if cfg!(debug_assertions) {
             println!("[开发模式] 模拟恢复网卡: {}", interface_name);
-            
+
             if let Some(snapshot) = self.snapshots.get_mut(interface_name) {
                 snapshot.is_isolated = false;
                 println!("[开发模式] 网卡 {} 恢复模拟完成", interface_name);
             } else {
                 println!("[开发模式] 警告：未找到网卡 {} 的备份配置，但在开发模式下继续", interface_name);
             }
-            return Ok(());
+            return Ok(Vec::new());
         }
-        
+
         let snapshot = self.snapshots.get(interface_name)
             .ok_or_else(|| anyhow!("未找到网卡 {} 的备份配置", interface_name))?
             .clone();
 
         if !snapshot.is_isolated {
-            return Ok(()); // 已经是正常状态
+            return Ok(Vec::new()); // 已经是正常状态
+        }
+
+        // 先把网卡移回宿主机，移动时被内核删除的地址和路由随后按快照恢复；
+        // 重启后命名空间已不存在，网卡已回到宿主机
+        if let Some(namespace) = &snapshot.namespace {
+            if netns::namespace_exists(namespace) {
+                netns::move_back(interface_name, namespace)?;
+            }
+            if let Some(snapshot_mut) = self.snapshots.get_mut(interface_name) {
+                snapshot_mut.namespace = None;
+            }
         }
 
         match self.platform {
@@ -357,105 +548,205 @@ impl InterfaceManager {
             Platform::Windows => self.restore_interface_windows(&snapshot)?,
         }
 
+        // 重新读取配置并与快照比较
+        let current = self.read_interface(interface_name)?;
+        let diffs = diff_snapshots(&snapshot, &current);
+
         // 更新状态
-        if let Some(snapshot_mut) = self.snapshots.get_mut(interface_name) {
-            snapshot_mut.is_isolated = false;
+        if diffs.is_empty() {
+            if let Some(snapshot_mut) = self.snapshots.get_mut(interface_name) {
+                snapshot_mut.is_isolated = false;
+            }
+            self.stale.retain(|name| name != interface_name);
+        }
+        if let Err(e) = self.persist() {
+            eprintln!("保存网卡快照失败: {}", e);
         }
 
-        Ok(())
+        Ok(diffs)
+    }
+
+    // 执行一个恢复步骤，失败时只记录日志，遗漏的配置由恢复后的校验报告
+    fn run_restore_step(&self, description: &str, program: &str, args: &[&str]) {
+        match Command::new(program).args(args).output() {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => eprintln!("{}失败: {}", description, String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => eprintln!("{}失败: {}", description, e),
+        }
     }
 
     // macOS恢复实现
     fn restore_interface_macos(&self, snapshot: &InterfaceSnapshot) -> Result<()> {
-        // 恢复IP配置
-        for ip_config in &snapshot.ip_addresses {
-            let args = vec![
-                "ifconfig", &snapshot.name, "inet", 
-                &ip_config.address, "netmask", &ip_config.netmask
-            ];
+        let name = snapshot.name.as_str();
+        if let Some(mtu) = snapshot.mtu {
+            self.run_restore_step("恢复 MTU", "sudo", &["ifconfig", name, "mtu", &mtu.to_string()]);
+        }
+        self.run_restore_step("恢复网卡状态", "sudo", &["ifconfig", name, if snapshot.is_up { "up" } else { "down" }]);
 
-            let output = Command::new("sudo")
-                .args(&args)
-                .output()?;
+        // 恢复IP配置，第一个 IPv4 地址作为主地址，其余作为别名
+        for (index, ip_config) in snapshot.ip_addresses.iter().filter(|ip| !ip.ipv6).enumerate() {
+            let mut args = vec!["ifconfig", name, "inet", &ip_config.address, "netmask", &ip_config.netmask];
+            if index > 0 {
+                args.push("alias");
+            }
+            self.run_restore_step(&format!("恢复IP {} ", ip_config.address), "sudo", &args);
+        }
+        for ip_config in snapshot.ip_addresses.iter().filter(|ip| ip.ipv6) {
+            let prefix_len = ip_config.prefix_len.to_string();
+            self.run_restore_step(
+                &format!("恢复IP {} ", ip_config.address),
+                "sudo",
+                &["ifconfig", name, "inet6", &ip_config.address, "prefixlen", &prefix_len, "alias"],
+            );
+        }
 
-            if !output.status.success() {
-                eprintln!("恢复IP {} 失败: {}", ip_config.address, 
-                         String::from_utf8_lossy(&output.stderr));
+        // 删除地址时经过本网卡的默认路由随之删除
+        for route in &snapshot.routes {
+            let mut args = vec!["route", "-n", "add"];
+            if route.ipv6 {
+                args.push("-inet6");
+            }
+            if route.destination != "default" {
+                args.push("-net");
             }
+            args.push(&route.destination);
+            args.push(&route.gateway);
+            self.run_restore_step(&format!("恢复路由 {} ", route.destination), "sudo", &args);
         }
 
-        // 取消混杂模式
-        let _output = Command::new("sudo")
-            .args(&["ifconfig", &snapshot.name, "-promisc"])
-            .output()?;
+        self.run_restore_step("恢复混杂模式", "sudo", &["ifconfig", name, if snapshot.promisc { "promisc" } else { "-promisc" }]);
 
         Ok(())
     }
 
-    // Linux恢复实现
+    // Linux恢复实现，使用 replace 保证重复恢复时不会因已存在而失败
     fn restore_interface_linux(&self, snapshot: &InterfaceSnapshot) -> Result<()> {
+        let name = snapshot.name.as_str();
+        if let Some(mtu) = snapshot.mtu {
+            self.run_restore_step("恢复 MTU", "sudo", &["ip", "link", "set", "dev", name, "mtu", &mtu.to_string()]);
+        }
+        self.run_restore_step("恢复网卡状态", "sudo", &["ip", "link", "set", "dev", name, if snapshot.is_up { "up" } else { "down" }]);
+
         // 恢复IP配置
         for ip_config in &snapshot.ip_addresses {
-            let ip_with_mask = format!("{}/{}", ip_config.address, 
-                                     self.netmask_to_cidr(&ip_config.netmask));
-            
-            let output = Command::new("sudo")
-                .args(&["ip", "addr", "add", &ip_with_mask, "dev", &snapshot.name])
-                .output()?;
-
-            if !output.status.success() {
-                eprintln!("恢复IP {} 失败: {}", ip_config.address, 
-                         String::from_utf8_lossy(&output.stderr));
-            }
+            let ip_with_mask = format!("{}/{}", ip_config.address, ip_config.prefix_len);
+            self.run_restore_step(
+                &format!("恢复IP {} ", ip_config.address),
+                "sudo",
+                &["ip", "addr", "replace", &ip_with_mask, "dev", name],
+            );
         }
 
-        // 恢复路由
+        // 恢复路由，地址恢复后网关才可达
         for route in &snapshot.routes {
-            let output = Command::new("sudo")
-                .args(&["ip", "route", "add", &route.destination, 
-                       "via", &route.gateway, "dev", &snapshot.name])
-                .output()?;
-
-            if !output.status.success() {
-                eprintln!("恢复路由 {} 失败: {}", route.destination, 
-                         String::from_utf8_lossy(&output.stderr));
+            let metric = route.metric.map(|m| m.to_string());
+            let mut args = vec!["ip", if route.ipv6 { "-6" } else { "-4" }, "route", "replace", &route.destination];
+            if !route.gateway.is_empty() {
+                args.extend(["via", &route.gateway]);
             }
+            args.extend(["dev", name]);
+            if let Some(metric) = &metric {
+                args.extend(["metric", metric]);
+            }
+            self.run_restore_step(&format!("恢复路由 {} ", route.destination), "sudo", &args);
         }
 
-        // 取消混杂模式
-        let _output = Command::new("sudo")
-            .args(&["ip", "link", "set", &snapshot.name, "promisc", "off"])
-            .output()?;
+        self.run_restore_step("恢复混杂模式", "sudo", &["ip", "link", "set", "dev", name, "promisc", if snapshot.promisc { "on" } else { "off" }]);
+
+        if !snapshot.dns_servers.is_empty() {
+            let mut args = vec!["resolvectl", "dns", name];
+            args.extend(snapshot.dns_servers.iter().map(String::as_str));
+            self.run_restore_step("恢复 DNS ", "sudo", &args);
+        }
 
         Ok(())
     }
 
     // Windows恢复实现
     fn restore_interface_windows(&self, snapshot: &InterfaceSnapshot) -> Result<()> {
-        // 恢复第一个IP配置（Windows通常一个接口一个IP）
-        if let Some(ip_config) = snapshot.ip_addresses.first() {
-            let mut args = vec![
-                "interface", "ip", "set", "address", &snapshot.name,
-                "static", &ip_config.address, &ip_config.netmask
-            ];
-
-            if let Some(gateway) = &ip_config.gateway {
-                args.push(gateway);
-            }
-
+        let name = snapshot.name.as_str();
+        if snapshot.dhcp {
             let output = Command::new("netsh")
-                .args(&args)
+                .args(["interface", "ip", "set", "address", name, "dhcp"])
                 .output()?;
-
             if !output.status.success() {
-                return Err(anyhow!("恢复网卡配置失败: {}", 
-                                 String::from_utf8_lossy(&output.stderr)));
+                return Err(anyhow!("恢复网卡配置失败: {}",
+                                 String::from_utf8_lossy(&output.stdout)));
+            }
+            self.run_restore_step("恢复 DNS ", "netsh", &["interface", "ip", "set", "dns", name, "dhcp"]);
+        } else {
+            let mut addresses = snapshot.ip_addresses.iter().filter(|ip| !ip.ipv6);
+            // 第一个地址连同默认网关设置，覆盖隔离时的无效地址
+            if let Some(ip_config) = addresses.next() {
+                let mut args = vec![
+                    "interface", "ip", "set", "address", name,
+                    "static", &ip_config.address, &ip_config.netmask
+                ];
+
+                if let Some(gateway) = &snapshot.default_gateway {
+                    args.push(gateway);
+                }
+
+                let output = Command::new("netsh")
+                    .args(&args)
+                    .output()?;
+
+                if !output.status.success() {
+                    return Err(anyhow!("恢复网卡配置失败: {}",
+                                     String::from_utf8_lossy(&output.stdout)));
+                }
+            }
+            for ip_config in addresses {
+                self.run_restore_step(
+                    &format!("恢复IP {} ", ip_config.address),
+                    "netsh",
+                    &["interface", "ip", "add", "address", name, &ip_config.address, &ip_config.netmask],
+                );
+            }
+
+            match snapshot.dns_servers.split_first() {
+                Some((first, rest)) => {
+                    self.run_restore_step("恢复 DNS ", "netsh", &["interface", "ip", "set", "dns", name, "static", first]);
+                    for (index, server) in rest.iter().enumerate() {
+                        let index = format!("index={}", index + 2);
+                        self.run_restore_step("恢复 DNS ", "netsh", &["interface", "ip", "add", "dns", name, server, &index]);
+                    }
+                }
+                None => self.run_restore_step("恢复 DNS ", "netsh", &["interface", "ip", "set", "dns", name, "static", "none"]),
             }
         }
 
+        if let Some(mtu) = snapshot.mtu {
+            let mtu = format!("mtu={}", mtu);
+            self.run_restore_step("恢复 MTU", "netsh", &["interface", "ipv4", "set", "subinterface", name, &mtu, "store=active"]);
+        }
+
         Ok(())
     }
 
+    // 命名空间隔离中的网卡所在的命名空间，发送线程需在其中打开句柄
+    pub fn namespace_of(&self, interface_name: &str) -> Option<String> {
+        self.snapshots.get(interface_name)
+            .filter(|s| s.is_isolated)
+            .and_then(|s| s.namespace.clone())
+    }
+
+    // 所有处于命名空间隔离中的网卡及其命名空间，捕获按接口查找
+    pub fn namespaces(&self) -> HashMap<String, String> {
+        self.snapshots.keys()
+            .filter_map(|name| self.namespace_of(name).map(|namespace| (name.clone(), namespace)))
+            .collect()
+    }
+
+    // 响应监控、扫描和 ARP 工具依赖宿主机命名空间中的地址、路由和 ARP 解析，
+    // 不支持已移入命名空间的网卡
+    pub fn require_host_namespace(&self, interface_name: &str) -> Result<()> {
+        match self.namespace_of(interface_name) {
+            Some(namespace) => Err(anyhow!("网卡 {} 已隔离到网络命名空间 {} 中，此功能暂不支持命名空间隔离", interface_name, namespace)),
+            None => Ok(()),
+        }
+    }
+
     // 获取网卡隔离状态
     pub fn is_isolated(&self, interface_name: &str) -> bool {
         self.snapshots.get(interface_name)
@@ -463,64 +754,315 @@ impl InterfaceManager {
             .unwrap_or(false)
     }
 
+    // 获取网卡的备份快照
+    pub fn snapshot(&self, interface_name: &str) -> Option<&InterfaceSnapshot> {
+        self.snapshots.get(interface_name)
+    }
+
+    // 比较网卡当前配置与快照，不修改网卡
+    pub fn verify_interface(&self, interface_name: &str) -> Result<Vec<SnapshotDiff>> {
+        let snapshot = self.snapshots.get(interface_name)
+            .ok_or_else(|| anyhow!("未找到网卡 {} 的备份配置", interface_name))?;
+        // 开发模式下快照是模拟的，不与真实网卡比较
+        if cfg!(debug_assertions) {
+            return Ok(Vec::new());
+        }
+        Ok(diff_snapshots(snapshot, &self.read_interface(interface_name)?))
+    }
+
     // 清理指定网卡的备份
     pub fn cleanup_interface(&mut self, interface_name: &str) {
         self.snapshots.remove(interface_name);
+        self.stale.retain(|name| name != interface_name);
+        if let Err(e) = self.persist() {
+            eprintln!("保存网卡快照失败: {}", e);
+        }
+    }
+
+    // 启用快照持久化并加载上次运行遗留的隔离快照，返回遗留的网卡名；
+    // 程序正常结束时所有网卡都已恢复，文件中剩下的快照说明程序曾在隔离期间异常退出
+    pub fn load_persisted_snapshots(&mut self, dir: &Path) -> Result<Vec<String>> {
+        let path = dir.join(SNAPSHOT_FILE);
+        // 文件无法加载时持久化照常启用，之后的隔离仍然写盘
+        self.snapshot_file = Some(path.clone());
+        let loaded = match load_snapshots(&path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                // 无法解析的文件改名保留以便手动检查，不被之后的保存覆盖
+                let corrupt = path.with_extension("json.corrupt");
+                return Err(match std::fs::rename(&path, &corrupt) {
+                    Ok(()) => anyhow!("{}，已移至 {}", e, corrupt.display()),
+                    Err(rename_error) => anyhow!("{}，移走失败: {}", e, rename_error),
+                });
+            }
+        };
+        for snapshot in loaded {
+            if !self.stale.contains(&snapshot.name) {
+                self.stale.push(snapshot.name.clone());
+            }
+            self.snapshots.insert(snapshot.name.clone(), snapshot);
+        }
+        Ok(self.stale.clone())
+    }
+
+    // 列出遗留的隔离网卡以及恢复时将改变的配置
+    pub fn stale_isolations(&self) -> Vec<StaleIsolation> {
+        self.stale.iter()
+            .filter_map(|name| self.snapshots.get(name))
+            .filter(|snapshot| snapshot.is_isolated)
+            .map(|snapshot| {
+                let (pending, note) = match &snapshot.namespace {
+                    Some(namespace) if netns::namespace_exists(namespace) => {
+                        (Vec::new(), Some(format!("网卡位于网络命名空间 {} 中，恢复时移回宿主机", namespace)))
+                    }
+                    _ => match self.read_interface(&snapshot.name) {
+                        Ok(current) => (diff_snapshots(snapshot, &current), None),
+                        Err(e) => (Vec::new(), Some(e.to_string())),
+                    },
+                };
+                StaleIsolation { snapshot: snapshot.clone(), pending, note }
+            })
+            .collect()
+    }
+
+    // 恢复全部遗留的隔离网卡
+    pub fn restore_stale_interfaces(&mut self) -> Vec<StaleRestoreResult> {
+        let names = self.stale.clone();
+        names.into_iter()
+            .map(|interface_name| match self.restore_interface_verified(&interface_name) {
+                Ok(diffs) => StaleRestoreResult { interface_name, diffs, error: None },
+                Err(e) => StaleRestoreResult { interface_name, diffs: Vec::new(), error: Some(e.to_string()) },
+            })
+            .collect()
+    }
+
+    // 把所有隔离中的快照写入文件，没有隔离中的网卡时删除文件
+    fn persist(&self) -> Result<()> {
+        let Some(path) = &self.snapshot_file else {
+            return Ok(());
+        };
+        let mut isolated: Vec<&InterfaceSnapshot> = self.snapshots.values().filter(|s| s.is_isolated).collect();
+        isolated.sort_by(|a, b| a.name.cmp(&b.name));
+        save_snapshots(path, &isolated)
     }
 
     // 辅助方法：解析配置信息
+    // 首行 <FLAGS> 中的 UP、PROMISC 以及 mtu，Linux 的 ip addr 与 macOS 的 ifconfig 格式相同
+    fn parse_link_state(&self, output: &str) -> (bool, bool, Option<u32>) {
+        let first_line = output.lines().next().unwrap_or_default();
+        let flags: Vec<&str> = first_line
+            .split_once('<')
+            .and_then(|(_, rest)| rest.split_once('>'))
+            .map(|(flags, _)| flags.split(',').collect())
+            .unwrap_or_default();
+        let parts: Vec<&str> = first_line.split_whitespace().collect();
+        let mtu = parts.iter()
+            .position(|part| *part == "mtu")
+            .and_then(|i| parts.get(i + 1))
+            .and_then(|mtu| mtu.parse().ok());
+        (flags.contains(&"UP"), flags.contains(&"PROMISC"), mtu)
+    }
+
+    fn parse_macos_addresses(&self, config_str: &str) -> Vec<IpConfig> {
+        config_str.lines().filter_map(|line| self.parse_macos_inet_line(line)).collect()
+    }
+
     fn parse_macos_inet_line(&self, line: &str) -> Option<IpConfig> {
         let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 4 && parts[0] == "inet" {
-            Some(IpConfig {
-                address: parts[1].to_string(),
-                netmask: parts[3].to_string(),
-                gateway: None,
-            })
-        } else {
-            None
+        let value_after = |key: &str| parts.iter().position(|part| *part == key).and_then(|i| parts.get(i + 1));
+        match parts.first() {
+            Some(&"inet") if parts.len() >= 4 => {
+                let prefix_len = self.netmask_to_cidr(value_after("netmask")?);
+                Some(IpConfig {
+                    address: parts[1].to_string(),
+                    netmask: self.cidr_to_netmask(prefix_len),
+                    gateway: None,
+                    prefix_len,
+                    ipv6: false,
+                })
+            }
+            // 链路本地地址由系统生成，临时地址会定期更换，都不需要恢复
+            Some(&"inet6") if parts.len() >= 4 => {
+                if parts[1].starts_with("fe80") || parts[1].contains('%') || parts.contains(&"temporary") {
+                    return None;
+                }
+                Some(IpConfig {
+                    address: parts[1].to_string(),
+                    netmask: String::new(),
+                    gateway: None,
+                    prefix_len: value_after("prefixlen")?.parse().ok()?,
+                    ipv6: true,
+                })
+            }
+            _ => None,
         }
     }
 
-    fn parse_macos_routes(&self, _route_output: &str, _interface_name: &str) -> Vec<RouteConfig> {
-        // macOS路由解析实现
-        Vec::new() // 简化实现
+    // 解析 route -n get default 的输出，默认路由不经过本网卡时返回空
+    fn parse_macos_routes(&self, route_output: &str, interface_name: &str) -> Vec<RouteConfig> {
+        let mut gateway = None;
+        let mut interface = None;
+        for line in route_output.lines() {
+            match line.trim().split_once(':') {
+                Some(("gateway", value)) => gateway = Some(value.trim().to_string()),
+                Some(("interface", value)) => interface = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        match (gateway, interface) {
+            (Some(gateway), Some(interface)) if interface == interface_name => vec![RouteConfig {
+                destination: "default".to_string(),
+                gateway,
+                interface,
+                metric: None,
+                ipv6: false,
+            }],
+            _ => Vec::new(),
+        }
     }
 
     fn parse_linux_ip_addresses(&self, output: &str) -> Vec<IpConfig> {
         let mut ip_addresses = Vec::new();
-        
+
         for line in output.lines() {
-            if line.trim().starts_with("inet ") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    let ip_with_cidr = parts[1];
-                    if let Some(slash_pos) = ip_with_cidr.find('/') {
-                        let ip = &ip_with_cidr[..slash_pos];
-                        let cidr = &ip_with_cidr[slash_pos + 1..];
-                        if let Ok(cidr_num) = cidr.parse::<u8>() {
-                            ip_addresses.push(IpConfig {
-                                address: ip.to_string(),
-                                netmask: self.cidr_to_netmask(cidr_num),
-                                gateway: None,
-                            });
-                        }
-                    }
-                }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let ipv6 = match parts.first() {
+                Some(&"inet") => false,
+                Some(&"inet6") => true,
+                _ => continue,
+            };
+            // 链路本地地址由内核生成，临时地址会定期更换，都不需要恢复
+            if ipv6 && (line.contains("scope link") || parts.contains(&"temporary")) {
+                continue;
+            }
+            let Some((ip, cidr)) = parts.get(1).and_then(|p| p.split_once('/')) else {
+                continue;
+            };
+            if let Ok(cidr_num) = cidr.parse::<u8>() {
+                ip_addresses.push(IpConfig {
+                    address: ip.to_string(),
+                    netmask: if ipv6 { String::new() } else { self.cidr_to_netmask(cidr_num) },
+                    gateway: None,
+                    prefix_len: cidr_num,
+                    ipv6,
+                });
             }
         }
-        
+
         ip_addresses
     }
 
-    fn parse_linux_routes(&self, _route_output: &str, _interface_name: &str) -> Vec<RouteConfig> {
-        // Linux路由解析实现
-        Vec::new() // 简化实现
+    // 解析 ip route show dev 的输出；内核根据地址自动生成的路由随地址恢复，不单独记录
+    fn parse_linux_routes(&self, route_output: &str, interface_name: &str, ipv6: bool) -> Vec<RouteConfig> {
+        const SPECIAL_TYPES: [&str; 7] = ["unreachable", "prohibit", "blackhole", "local", "broadcast", "multicast", "anycast"];
+        let mut routes = Vec::new();
+
+        for line in route_output.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let Some(destination) = parts.first() else {
+                continue;
+            };
+            if SPECIAL_TYPES.contains(destination) || parts.windows(2).any(|w| w == ["proto", "kernel"]) {
+                continue;
+            }
+            let value_after = |key: &str| parts.iter().position(|part| *part == key).and_then(|i| parts.get(i + 1));
+            routes.push(RouteConfig {
+                destination: destination.to_string(),
+                gateway: value_after("via").map(|g| g.to_string()).unwrap_or_default(),
+                interface: interface_name.to_string(),
+                metric: value_after("metric").and_then(|m| m.parse().ok()),
+                ipv6,
+            });
+        }
+
+        routes
+    }
+
+    // 解析 resolvectl dns <网卡> 的输出，例如 "Link 2 (eth0): 192.168.1.1 8.8.8.8"
+    fn parse_resolvectl_dns(&self, output: &str) -> Vec<String> {
+        output.lines()
+            .filter_map(|line| line.split_once("):"))
+            .flat_map(|(_, servers)| servers.split_whitespace().map(|s| s.to_string()))
+            .collect()
+    }
+
+    // 解析 netsh interface ip show config 的输出，兼容英文和中文系统
+    fn parse_windows_config(&self, config_str: &str) -> WindowsIpConfig {
+        let mut config = WindowsIpConfig::default();
+        let mut in_dns = false;
+
+        for line in config_str.lines() {
+            let line = line.trim();
+            // DNS 服务器的续行只有地址
+            if line.parse::<std::net::IpAddr>().is_ok() {
+                if in_dns {
+                    config.dns_servers.push(line.to_string());
+                }
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            in_dns = false;
+
+            if key.contains("DNS") {
+                in_dns = true;
+                if value.parse::<std::net::IpAddr>().is_ok() {
+                    config.dns_servers.push(value.to_string());
+                }
+            } else if key.contains("DHCP") {
+                config.dhcp = value == "Yes" || value == "是";
+            } else if key.contains("IP Address") || key.contains("IP 地址") {
+                config.addresses.push(IpConfig {
+                    address: value.to_string(),
+                    netmask: String::new(),
+                    gateway: None,
+                    prefix_len: 0,
+                    ipv6: false,
+                });
+            } else if key.contains("Subnet Prefix") || key.contains("子网前缀") {
+                // 例如 "192.168.1.0/24 (mask 255.255.255.0)"，对应上一个地址
+                let prefix_len = value.split_whitespace().next()
+                    .and_then(|prefix| prefix.split_once('/'))
+                    .and_then(|(_, len)| len.parse::<u8>().ok());
+                if let (Some(address), Some(prefix_len)) = (config.addresses.last_mut(), prefix_len) {
+                    address.prefix_len = prefix_len;
+                    address.netmask = self.cidr_to_netmask(prefix_len);
+                }
+            } else if (key.contains("Default Gateway") || key.contains("默认网关"))
+                && value.parse::<std::net::IpAddr>().is_ok() {
+                config.default_gateway = Some(value.to_string());
+            }
+        }
+
+        if let (Some(address), Some(gateway)) = (config.addresses.first_mut(), &config.default_gateway) {
+            address.gateway = Some(gateway.clone());
+        }
+        config
+    }
+
+    // 解析 netsh interface ipv4 show subinterface 的输出，首列为 MTU，末列为网卡名
+    fn parse_windows_mtu(&self, output: &str, interface_name: &str) -> Option<u32> {
+        output.lines()
+            .filter(|line| line.trim_end().ends_with(interface_name))
+            .find_map(|line| line.split_whitespace().next()?.parse().ok())
     }
 
-    fn parse_windows_config(&self, _config_str: &str) -> Vec<IpConfig> {
-        // Windows配置解析实现
-        Vec::new() // 简化实现
+    // 解析 netsh interface show interface 的管理状态
+    fn parse_windows_admin_state(&self, output: &str) -> Option<bool> {
+        output.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.contains("Administrative state") || key.contains("管理状态"))
+            .map(|(_, value)| matches!(value.trim(), "Enabled" | "已启用"))
+    }
+
+    // 优先取 IPv4 默认路由的网关
+    fn default_gateway(&self, routes: &[RouteConfig]) -> Option<String> {
+        routes.iter()
+            .filter(|r| matches!(r.destination.as_str(), "default" | "0.0.0.0/0" | "::/0") && !r.gateway.is_empty())
+            .min_by_key(|r| r.ipv6)
+            .map(|r| r.gateway.clone())
     }
 
     fn extract_mac_from_ifconfig(&self, config_str: &str) -> Option<String> {
@@ -551,25 +1093,371 @@ impl InterfaceManager {
         None
     }
 
+    // 支持点分十进制和 macOS ifconfig 的十六进制掩码（如 0xffffff00）
     fn netmask_to_cidr(&self, netmask: &str) -> u8 {
-        // 简化实现：将子网掩码转换为CIDR表示法
-        match netmask {
-            "255.255.255.0" => 24,
-            "255.255.0.0" => 16,
-            "255.0.0.0" => 8,
-            "255.255.255.255" => 32,
-            _ => 24, // 默认值
-        }
+        let bits = match netmask.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => netmask.parse::<Ipv4Addr>().ok().map(u32::from),
+        };
+        bits.map(|b| b.leading_ones() as u8).unwrap_or(24)
     }
 
     fn cidr_to_netmask(&self, cidr: u8) -> String {
-        // 简化实现：将CIDR转换为子网掩码
-        match cidr {
-            24 => "255.255.255.0".to_string(),
-            16 => "255.255.0.0".to_string(),
-            8 => "255.0.0.0".to_string(),
-            32 => "255.255.255.255".to_string(),
-            _ => "255.255.255.0".to_string(), // 默认值
+        let bits = u32::MAX.checked_shl(32 - cidr.min(32) as u32).unwrap_or(0);
+        Ipv4Addr::from(bits).to_string()
+    }
+}
+
+// 比较快照与当前配置。DHCP 分配的地址、网关和 DNS 由服务器决定，只比较是否启用 DHCP
+pub fn diff_snapshots(expected: &InterfaceSnapshot, actual: &InterfaceSnapshot) -> Vec<SnapshotDiff> {
+    fn sorted(mut items: Vec<String>) -> String {
+        items.sort();
+        items.dedup();
+        items.join(", ")
+    }
+    fn addresses(snapshot: &InterfaceSnapshot) -> String {
+        sorted(snapshot.ip_addresses.iter().map(|ip| format!("{}/{}", ip.address, ip.prefix_len)).collect())
+    }
+    fn routes(snapshot: &InterfaceSnapshot) -> String {
+        sorted(snapshot.routes.iter().map(|r| {
+            let mut route = r.destination.clone();
+            if !r.gateway.is_empty() {
+                route.push_str(&format!(" via {}", r.gateway));
+            }
+            if let Some(metric) = r.metric {
+                route.push_str(&format!(" metric {}", metric));
+            }
+            route
+        }).collect())
+    }
+    fn optional<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "无".to_string())
+    }
+
+    let mut fields = vec![
+        ("dhcp", expected.dhcp.to_string(), actual.dhcp.to_string()),
+        ("is_up", expected.is_up.to_string(), actual.is_up.to_string()),
+        ("mtu", optional(&expected.mtu), optional(&actual.mtu)),
+        ("promisc", expected.promisc.to_string(), actual.promisc.to_string()),
+        ("mac_address", optional(&expected.mac_address), optional(&actual.mac_address)),
+    ];
+    if !expected.dhcp {
+        fields.extend([
+            ("ip_addresses", addresses(expected), addresses(actual)),
+            ("routes", routes(expected), routes(actual)),
+            ("default_gateway", optional(&expected.default_gateway), optional(&actual.default_gateway)),
+            ("dns_servers", expected.dns_servers.join(", "), actual.dns_servers.join(", ")),
+        ]);
+    }
+
+    fields.into_iter()
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(field, expected, actual)| SnapshotDiff { field: field.to_string(), expected, actual })
+        .collect()
+}
+
+fn load_snapshots(path: &Path) -> Result<Vec<InterfaceSnapshot>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("读取快照文件 {} 失败: {}", path.display(), e))?;
+    let snapshots: Vec<InterfaceSnapshot> = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("解析快照文件 {} 失败: {}", path.display(), e))?;
+    Ok(snapshots.into_iter().filter(|s| s.is_isolated).collect())
+}
+
+// 先写临时文件再重命名，避免写入中途退出留下不完整的文件
+fn save_snapshots(path: &Path, snapshots: &[&InterfaceSnapshot]) -> Result<()> {
+    if snapshots.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
         }
+        return Ok(());
     }
-}
\ No newline at end of file
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, serde_json::to_string_pretty(snapshots)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+fn describe_diffs(diffs: &[SnapshotDiff]) -> String {
+    diffs.iter()
+        .map(|d| format!("{} 应为 [{}]，实际为 [{}]", d.field, d.expected, d.actual))
+        .collect::<Vec<_>>()
+        .join("；")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> InterfaceManager {
+        InterfaceManager::new().unwrap()
+    }
+
+    #[test]
+    fn parses_linux_addresses_routes_and_link_state() {
+        let manager = manager();
+        let addr = "\
+2: eth0: <BROADCAST,MULTICAST,PROMISC,UP,LOWER_UP> mtu 9000 qdisc fq_codel state UP group default qlen 1000
+    link/ether 52:54:00:12:34:56 brd ff:ff:ff:ff:ff:ff
+    inet 192.168.1.100/24 brd 192.168.1.255 scope global dynamic eth0
+       valid_lft 86000sec preferred_lft 86000sec
+    inet 10.1.0.5/20 scope global secondary eth0
+    inet6 2001:db8::100/64 scope global dynamic mngtmpaddr
+    inet6 2001:db8::beef/64 scope global temporary dynamic
+    inet6 fe80::5054:ff:fe12:3456/64 scope link
+";
+        assert_eq!(manager.parse_link_state(addr), (true, true, Some(9000)));
+        let addresses = manager.parse_linux_ip_addresses(addr);
+        let summary: Vec<(&str, &str, u8, bool)> = addresses.iter()
+            .map(|ip| (ip.address.as_str(), ip.netmask.as_str(), ip.prefix_len, ip.ipv6))
+            .collect();
+        assert_eq!(summary, vec![
+            ("192.168.1.100", "255.255.255.0", 24, false),
+            ("10.1.0.5", "255.255.240.0", 20, false),
+            ("2001:db8::100", "", 64, true),
+        ]);
+
+        let v4 = "\
+default via 192.168.1.1 proto dhcp src 192.168.1.100 metric 100
+10.20.0.0/16 via 10.1.0.1
+192.168.1.0/24 proto kernel scope link src 192.168.1.100 metric 100
+";
+        let v6 = "\
+2001:db8::/64 proto kernel metric 256 pref medium
+fe80::/64 proto kernel metric 256 pref medium
+default via fe80::1 proto ra metric 1024 expires 1798sec pref medium
+";
+        let mut routes = manager.parse_linux_routes(v4, "eth0", false);
+        routes.extend(manager.parse_linux_routes(v6, "eth0", true));
+        let summary: Vec<(&str, &str, Option<u32>, bool)> = routes.iter()
+            .map(|r| (r.destination.as_str(), r.gateway.as_str(), r.metric, r.ipv6))
+            .collect();
+        assert_eq!(summary, vec![
+            ("default", "192.168.1.1", Some(100), false),
+            ("10.20.0.0/16", "10.1.0.1", None, false),
+            ("default", "fe80::1", Some(1024), true),
+        ]);
+        assert_eq!(manager.default_gateway(&routes), Some("192.168.1.1".to_string()));
+
+        assert_eq!(manager.parse_resolvectl_dns("Link 2 (eth0): 192.168.1.1 2001:db8::53\n"), vec!["192.168.1.1", "2001:db8::53"]);
+        assert!(manager.parse_resolvectl_dns("Link 2 (eth0):\n").is_empty());
+    }
+
+    #[test]
+    fn parses_macos_ifconfig_and_default_route() {
+        let manager = manager();
+        let ifconfig = "\
+en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+\tether a4:83:e7:12:34:56
+\tinet6 fe80::1c2b:3d4e:5f60:7182%en0 prefixlen 64 secured scopeid 0x6
+\tinet 192.168.1.23 netmask 0xfffffe00 broadcast 192.168.1.255
+\tinet6 2001:db8::1234 prefixlen 64 autoconf secured
+\tinet6 2001:db8::abcd prefixlen 64 autoconf temporary
+\tstatus: active
+";
+        assert_eq!(manager.parse_link_state(ifconfig), (true, false, Some(1500)));
+        let summary: Vec<(String, String, u8, bool)> = manager.parse_macos_addresses(ifconfig).into_iter()
+            .map(|ip| (ip.address, ip.netmask, ip.prefix_len, ip.ipv6))
+            .collect();
+        assert_eq!(summary, vec![
+            ("192.168.1.23".to_string(), "255.255.254.0".to_string(), 23, false),
+            ("2001:db8::1234".to_string(), String::new(), 64, true),
+        ]);
+
+        let route = "\
+   route to: default
+destination: default
+       mask: default
+    gateway: 192.168.1.1
+  interface: en0
+      flags: <UP,GATEWAY,DONE,STATIC,PRCLONING>
+";
+        let routes = manager.parse_macos_routes(route, "en0");
+        assert_eq!(routes.len(), 1);
+        assert_eq!((routes[0].destination.as_str(), routes[0].gateway.as_str()), ("default", "192.168.1.1"));
+        assert!(manager.parse_macos_routes(route, "en1").is_empty());
+    }
+
+    #[test]
+    fn parses_windows_netsh_output() {
+        let manager = manager();
+        let config = "\
+Configuration for interface \"Ethernet 2\"
+    DHCP enabled:                         No
+    IP Address:                           192.168.1.100
+    Subnet Prefix:                        192.168.1.0/24 (mask 255.255.255.0)
+    IP Address:                           10.0.0.8
+    Subnet Prefix:                        10.0.0.0/8 (mask 255.0.0.0)
+    Default Gateway:                      192.168.1.1
+    Gateway Metric:                       0
+    InterfaceMetric:                      25
+    Statically Configured DNS Servers:    8.8.8.8
+                                          1.1.1.1
+    Register with which suffix:           Primary only
+    Statically Configured WINS Servers:   None
+";
+        let parsed = manager.parse_windows_config(config);
+        assert!(!parsed.dhcp);
+        let summary: Vec<(&str, &str, u8)> = parsed.addresses.iter()
+            .map(|ip| (ip.address.as_str(), ip.netmask.as_str(), ip.prefix_len))
+            .collect();
+        assert_eq!(summary, vec![("192.168.1.100", "255.255.255.0", 24), ("10.0.0.8", "255.0.0.0", 8)]);
+        assert_eq!(parsed.addresses[0].gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(parsed.default_gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(parsed.dns_servers, vec!["8.8.8.8", "1.1.1.1"]);
+
+        let dhcp = "\
+    DHCP 已启用:                          是
+    IP 地址:                              192.168.3.7
+    子网前缀:                             192.168.3.0/24 (掩码 255.255.255.0)
+    默认网关:                             192.168.3.1
+    通过 DHCP 配置的 DNS 服务器:          192.168.3.1
+";
+        let parsed = manager.parse_windows_config(dhcp);
+        assert!(parsed.dhcp);
+        assert_eq!(parsed.addresses[0].prefix_len, 24);
+        assert_eq!(parsed.dns_servers, vec!["192.168.3.1"]);
+
+        let subinterface = "\
+   MTU  MediaSenseState   Bytes In  Bytes Out  Interface
+------  ---------------  ---------  ---------  -------------
+  1400                1  123456789   12345678  Ethernet 2
+";
+        assert_eq!(manager.parse_windows_mtu(subinterface, "Ethernet 2"), Some(1400));
+        assert_eq!(manager.parse_windows_admin_state("Ethernet 2\n   Administrative state: Disabled\n"), Some(false));
+    }
+
+    #[test]
+    fn netmask_conversions_cover_all_prefixes() {
+        let manager = manager();
+        for cidr in 0..=32 {
+            assert_eq!(manager.netmask_to_cidr(&manager.cidr_to_netmask(cidr)), cidr);
+        }
+        assert_eq!(manager.cidr_to_netmask(20), "255.255.240.0");
+        assert_eq!(manager.netmask_to_cidr("0xffffff80"), 25);
+    }
+
+    #[test]
+    fn persisted_snapshots_are_loaded_as_stale() {
+        let dir = std::env::temp_dir().join(format!("bitsender-snapshots-{}", uuid::Uuid::new_v4()));
+        let mut isolated = sample_snapshot();
+        isolated.namespace = Some("bitsender-eth0".to_string());
+        let mut restored = sample_snapshot();
+        restored.name = "eth1".to_string();
+        restored.is_isolated = false;
+        save_snapshots(&dir.join(SNAPSHOT_FILE), &[&isolated, &restored]).unwrap();
+
+        let mut manager = manager();
+        assert_eq!(manager.load_persisted_snapshots(&dir).unwrap(), vec!["eth0"]);
+        assert!(manager.is_isolated("eth0"));
+        assert_eq!(manager.snapshot("eth0").unwrap().namespace.as_deref(), Some("bitsender-eth0"));
+        assert!(manager.snapshot("eth1").is_none());
+
+        // 清理后文件随之删除，下次启动不再有遗留网卡
+        manager.cleanup_interface("eth0");
+        assert!(!dir.join(SNAPSHOT_FILE).exists());
+        assert!(manager.stale_isolations().is_empty());
+        assert!(InterfaceManager::new().unwrap().load_persisted_snapshots(&dir).unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn namespace_lookup_only_covers_isolated_namespaces() {
+        let mut manager = manager();
+        let mut moved = sample_snapshot();
+        moved.namespace = Some("bitsender-eth0".to_string());
+        let mut cleared = sample_snapshot();
+        cleared.name = "eth1".to_string();
+        let mut restored = sample_snapshot();
+        restored.name = "eth2".to_string();
+        restored.namespace = Some("bitsender-eth2".to_string());
+        restored.is_isolated = false;
+        for snapshot in [moved, cleared, restored] {
+            manager.snapshots.insert(snapshot.name.clone(), snapshot);
+        }
+
+        assert_eq!(manager.namespace_of("eth0").as_deref(), Some("bitsender-eth0"));
+        assert_eq!(manager.namespaces(), HashMap::from([("eth0".to_string(), "bitsender-eth0".to_string())]));
+        assert!(manager.require_host_namespace("eth0").is_err());
+        for name in ["eth1", "eth2", "eth9"] {
+            assert!(manager.namespace_of(name).is_none());
+            assert!(manager.require_host_namespace(name).is_ok());
+        }
+    }
+
+    #[test]
+    fn corrupt_snapshot_file_is_moved_aside_and_persistence_stays_enabled() {
+        let dir = std::env::temp_dir().join(format!("bitsender-snapshots-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SNAPSHOT_FILE), "{ not json").unwrap();
+
+        let mut manager = manager();
+        assert!(manager.load_persisted_snapshots(&dir).is_err());
+        assert_eq!(std::fs::read_to_string(dir.join("isolation_snapshots.json.corrupt")).unwrap(), "{ not json");
+
+        let snapshot = sample_snapshot();
+        manager.snapshots.insert(snapshot.name.clone(), snapshot);
+        manager.persist().unwrap();
+        assert_eq!(InterfaceManager::new().unwrap().load_persisted_snapshots(&dir).unwrap(), vec!["eth0"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn sample_snapshot() -> InterfaceSnapshot {
+        InterfaceSnapshot {
+            name: "eth0".to_string(),
+            ip_addresses: vec![IpConfig {
+                address: "192.168.1.100".to_string(),
+                netmask: "255.255.255.0".to_string(),
+                gateway: None,
+                prefix_len: 24,
+                ipv6: false,
+            }],
+            routes: vec![RouteConfig {
+                destination: "default".to_string(),
+                gateway: "192.168.1.1".to_string(),
+                interface: "eth0".to_string(),
+                metric: Some(100),
+                ipv6: false,
+            }],
+            mac_address: Some("52:54:00:12:34:56".to_string()),
+            is_up: true,
+            is_isolated: true,
+            namespace: None,
+            mtu: Some(1500),
+            promisc: false,
+            default_gateway: Some("192.168.1.1".to_string()),
+            dns_servers: vec!["192.168.1.1".to_string()],
+            dhcp: false,
+        }
+    }
+
+    #[test]
+    fn diff_reports_mismatched_fields() {
+        let expected = sample_snapshot();
+        let mut actual = expected.clone();
+        actual.is_isolated = false;
+        assert!(diff_snapshots(&expected, &actual).is_empty());
+
+        actual.routes.clear();
+        actual.default_gateway = None;
+        actual.promisc = true;
+        let diffs = diff_snapshots(&expected, &actual);
+        let fields: Vec<&str> = diffs.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, vec!["promisc", "routes", "default_gateway"]);
+        assert_eq!(diffs[1].expected, "default via 192.168.1.1 metric 100");
+        assert_eq!(diffs[2].actual, "无");
+
+        // DHCP 网卡不比较服务器分配的配置
+        let mut dhcp = expected.clone();
+        dhcp.dhcp = true;
+        actual.dhcp = true;
+        actual.promisc = false;
+        assert!(diff_snapshots(&dhcp, &actual).is_empty());
+    }
+}
@@ -4,12 +4,50 @@ pub mod packet_sniffer;
 pub mod response_monitor;
 pub mod interface_manager;
 pub mod sequence_sender;
+pub mod stream_reassembler;
+pub mod conversation_stats;
+pub mod rate_series;
+pub mod pcapng_writer;
+pub mod packet_validation;
+pub mod packet_schema;
+pub mod fragmenter;
+pub mod tcp_options;
+pub mod dns;
+pub mod dhcp;
+pub mod ntp;
+pub mod arp;
+pub mod scanner;
+pub mod rtt_stats;
+pub mod capture_sessions;
+pub mod interface_correlation;
+pub mod signature;
+pub mod benchmark;
+pub mod netns;
 
 pub use packet_builder::PacketBuilder;
 pub use interface::NetworkInterface;
 pub use packet_sniffer::{SnifferManager, CapturedPacket, PacketStatistics, CaptureFilters};
-pub use response_monitor::{MonitorManager, TestConfig, TestResult, MonitoringStatistics};
-pub use interface_manager::{InterfaceManager, InterfaceSnapshot};
+pub use response_monitor::{MonitorManager, TestConfig, TestResult, MonitoringStatistics, TracerouteConfig, TracerouteHop};
+pub use interface_manager::{InterfaceManager, InterfaceSnapshot, IsolationMode, SnapshotDiff, StaleIsolation, StaleRestoreResult};
+pub use stream_reassembler::{FlowTable, StreamConversation, FollowedStream};
+pub use conversation_stats::{TrafficStatistics, EndpointStats, ConversationStats};
+pub use rate_series::{RateHistory, RateSeriesSnapshot};
+pub use pcapng_writer::{PcapngRingWriter, RingBufferConfig, RingBufferStatus};
+pub use packet_validation::FieldError;
+pub use packet_schema::{ProtocolSchema, FieldSchema};
+pub use fragmenter::{FragmentOptions, FragmentOrder};
+pub use tcp_options::TcpOption;
+pub use dns::DnsMessage;
+pub use dhcp::DhcpMessage;
+pub use ntp::NtpPacket;
+pub use arp::{ArpInfo, ArpEntry, ArpHost, ArpScanConfig, GratuitousKind};
+pub use scanner::{ScanConfig, ScanResult, ScanTaskStatus, ScanTaskHandle};
+pub use rtt_stats::{RttSummary, HistogramBucket};
+pub use capture_sessions::{CaptureSessions, CaptureSessionInfo};
+pub use interface_correlation::{CorrelationReport, ForwardedFrame, PathLatency};
+pub use signature::{SignatureReport, SignatureStream, SignatureTracker};
+pub use benchmark::{BenchmarkConfig, BenchmarkStatus, BenchmarkTaskHandle, FrameSizeResult};
+pub use netns::VethPair;
 
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
@@ -17,7 +55,7 @@ use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use tokio::sync::oneshot;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PacketData {
     pub protocol: String,
     pub fields: std::collections::HashMap<String, String>,
@@ -70,22 +108,44 @@ pub struct SequenceTaskHandle {
     pub isolate_interface: bool,
 }
 
-pub async fn send_packet(packet_data: PacketData, interface_name: Option<String>) -> Result<SendResult> {
-    let mut interface = if let Some(name) = interface_name {
-        NetworkInterface::find_by_name(&name)?
-    } else {
-        NetworkInterface::get_default()?
-    };
+// namespace 为网卡所在的隔离命名空间，此时在命名空间中打开发送句柄
+pub async fn send_packet(packet_data: PacketData, interface_name: Option<String>, namespace: Option<String>) -> Result<SendResult> {
+    let (name, fragments) = match (interface_name, namespace) {
+        (Some(name), Some(namespace)) => {
+            let frames = PacketBuilder::new(packet_data).build_frames()?;
+            let mut sender = netns::open_sender(&name, Some(&namespace))?;
+            for frame in &frames {
+                sender.send(frame)?;
+            }
+            (name, frames.len())
+        }
+        (interface_name, _) => {
+            let mut interface = if let Some(name) = interface_name {
+                NetworkInterface::find_by_name(&name)?
+            } else {
+                NetworkInterface::get_default()?
+            };
 
-    let packet = PacketBuilder::new(packet_data)
-        .build()?;
+            let frames = PacketBuilder::new(packet_data)
+                .build_frames()?;
+
+            for frame in &frames {
+                interface.send_packet(frame)?;
+            }
+            (interface.name().to_string(), frames.len())
+        }
+    };
 
-    interface.send_packet(&packet)?;
+    let message = if fragments > 1 {
+        format!("报文已分为 {} 个分片通过接口 {} 发送成功", fragments, name)
+    } else {
+        format!("报文已通过接口 {} 发送成功", name)
+    };
 
     Ok(SendResult {
         success: true,
-        message: format!("报文已通过接口 {} 发送成功", interface.name()),
-        interface: Some(interface.name().to_string()),
+        message,
+        interface: Some(name),
     })
 }
 
@@ -103,10 +163,25 @@ pub struct BatchTaskHandle {
     pub stop_tx: Option<oneshot::Sender<()>>,
     pub interface_name: Option<String>,
     pub isolate_interface: bool,
+    // 发送速率时间序列
+    pub rates: Arc<Mutex<RateHistory>>,
+    // 启用签名匹配时捕获侧的匹配状态
+    pub signature: Option<Arc<Mutex<SignatureTracker>>>,
+}
+
+// 已完成的后台任务保留的时间，启动新任务时清理过期任务
+pub const FINISHED_TASK_RETENTION_SECS: u64 = 600;
+
+// 清理完成超过 FINISHED_TASK_RETENTION_SECS 的任务；completed_at 取任务的完成时间，
+// 未完成的任务返回 None，时间均为 Unix 秒
+pub fn prune_finished<H>(tasks: &mut HashMap<String, H>, now: u64, completed_at: impl Fn(&H) -> Option<u64>) {
+    tasks.retain(|_, handle| completed_at(handle).is_none_or(|at| now < at + FINISHED_TASK_RETENTION_SECS));
 }
 
 pub type TaskMap = Arc<Mutex<HashMap<String, BatchTaskHandle>>>;
 pub type SequenceTaskMap = Arc<Mutex<HashMap<String, SequenceTaskHandle>>>;
-pub type SnifferState = Arc<Mutex<SnifferManager>>;
+pub type ScanTaskMap = Arc<Mutex<HashMap<String, ScanTaskHandle>>>;
+pub type BenchmarkTaskMap = Arc<Mutex<HashMap<String, BenchmarkTaskHandle>>>;
+pub type SnifferState = Arc<Mutex<CaptureSessions>>;
 pub type MonitorState = Arc<Mutex<MonitorManager>>;
 pub type InterfaceManagerState = Arc<Mutex<InterfaceManager>>; 
\ No newline at end of file
@@ -0,0 +1,154 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use crate::network::interface::NetworkSender;
+
+// Linux 网络命名空间隔离：测试期间把网卡移入独立的命名空间，宿主机的协议栈看不到该网卡，
+// 不会对测试流量作出应答，也不需要清空宿主机上的地址
+const NAMESPACE_PREFIX: &str = "bitsender-";
+// 网卡名最长 15 字节
+const IFNAMSIZ: usize = 15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VethPair {
+    pub name: String,
+    pub peer: String,
+}
+
+pub fn namespace_name(interface_name: &str) -> String {
+    let sanitized: String = interface_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}{}", NAMESPACE_PREFIX, sanitized)
+}
+
+fn ip(args: &[&str]) -> Result<()> {
+    let output = Command::new("sudo").arg("ip").args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("执行 ip {} 失败: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+// 创建命名空间并把网卡移入，移入后网卡在命名空间内启用；
+// 网卡移出宿主机命名空间时内核会删除它的地址和路由，恢复时需按快照重新配置
+pub fn move_into_namespace(interface_name: &str, namespace: &str) -> Result<()> {
+    ip(&["netns", "add", namespace])?;
+    let moved = ip(&["link", "set", "dev", interface_name, "netns", namespace])
+        .and_then(|_| ip(&["-n", namespace, "link", "set", "dev", interface_name, "up"]));
+    if let Err(e) = moved {
+        let _ = ip(&["-n", namespace, "link", "set", "dev", interface_name, "netns", "1"]);
+        let _ = ip(&["netns", "del", namespace]);
+        return Err(e);
+    }
+    Ok(())
+}
+
+// 把网卡移回宿主机（1 号进程所在的命名空间）并删除命名空间
+pub fn move_back(interface_name: &str, namespace: &str) -> Result<()> {
+    ip(&["-n", namespace, "link", "set", "dev", interface_name, "netns", "1"])?;
+    ip(&["netns", "del", namespace])
+}
+
+pub fn namespace_exists(namespace: &str) -> bool {
+    std::path::Path::new("/var/run/netns").join(namespace).exists()
+}
+
+// 当前线程切换到指定命名空间，需要 CAP_SYS_ADMIN
+#[cfg(target_os = "linux")]
+fn enter_namespace(namespace: &str) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let path = format!("/var/run/netns/{}", namespace);
+    let file = std::fs::File::open(&path).map_err(|e| anyhow!("打开网络命名空间 {} 失败: {}", namespace, e))?;
+    if unsafe { libc::setns(file.as_raw_fd(), libc::CLONE_NEWNET) } != 0 {
+        return Err(anyhow!("切换到网络命名空间 {} 失败: {}", namespace, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enter_namespace(_namespace: &str) -> Result<()> {
+    Err(anyhow!("网络命名空间仅支持 Linux"))
+}
+
+// 在网卡所在的命名空间中打开发送或捕获句柄；指定命名空间时在临时线程中切换命名空间后打开，
+// 句柄创建后始终属于该命名空间，调用线程（可能是运行时的线程池）不受影响
+pub fn open_in<T: Send + 'static>(namespace: Option<&str>, open: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    let Some(namespace) = namespace else {
+        return open();
+    };
+    let namespace = namespace.to_string();
+    std::thread::spawn(move || {
+        enter_namespace(&namespace)?;
+        open()
+    })
+    .join()
+    .map_err(|_| anyhow!("打开句柄的线程异常退出"))?
+}
+
+pub fn open_sender(interface_name: &str, namespace: Option<&str>) -> Result<NetworkSender> {
+    let interface_name = interface_name.to_string();
+    open_in(namespace, move || NetworkSender::open(&interface_name))
+}
+
+pub fn veth_names(base: &str) -> Result<VethPair> {
+    let base = base.trim();
+    if base.is_empty() || base.len() + 1 > IFNAMSIZ || !base.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!("无效的 veth 名称: {}", base));
+    }
+    Ok(VethPair { name: format!("{}a", base), peer: format!("{}b", base) })
+}
+
+// 创建用于环回测试的 veth 对：两端都在宿主机命名空间中启用，不配置地址并关闭 IPv6，
+// 从一端发出的报文在另一端捕获，不改动任何物理网卡
+pub fn create_veth_pair(base: &str) -> Result<VethPair> {
+    let pair = veth_names(base)?;
+    ip(&["link", "add", &pair.name, "type", "veth", "peer", "name", &pair.peer])?;
+    // IPv6 未关闭时宿主机会在启用的接口上发送邻居发现等报文，混入测试流量
+    for end in [&pair.name, &pair.peer] {
+        if let Err(e) = disable_ipv6(end).and_then(|_| ip(&["link", "set", "dev", end, "up"])) {
+            let _ = ip(&["link", "del", &pair.name]);
+            return Err(e);
+        }
+    }
+    Ok(pair)
+}
+
+fn disable_ipv6(interface_name: &str) -> Result<()> {
+    let setting = format!("net.ipv6.conf.{}.disable_ipv6=1", interface_name);
+    let output = Command::new("sudo").args(["sysctl", "-q", "-w", &setting]).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("关闭 {} 的 IPv6 失败: {}", interface_name, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+// 删除 veth 的任意一端时另一端随之删除
+pub fn delete_veth_pair(name: &str) -> Result<()> {
+    ip(&["link", "del", name])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_valid_interface_names() {
+        assert_eq!(namespace_name("eth0"), "bitsender-eth0");
+        assert_eq!(namespace_name("en0.100/x"), "bitsender-en0_100_x");
+
+        let pair = veth_names("bs1234").unwrap();
+        assert_eq!((pair.name.as_str(), pair.peer.as_str()), ("bs1234a", "bs1234b"));
+        assert!(veth_names("").is_err());
+        assert!(veth_names("averylonginterface").is_err());
+        assert!(veth_names("bad name").is_err());
+    }
+
+    #[test]
+    fn open_in_host_namespace_runs_on_the_calling_thread() {
+        let caller = std::thread::current().id();
+        assert!(open_in(None, move || Ok(std::thread::current().id() == caller)).unwrap());
+        assert!(open_in(Some("bitsender-missing-namespace"), || Ok(())).is_err());
+    }
+}
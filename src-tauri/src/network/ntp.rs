@@ -0,0 +1,137 @@
+use serde::{Serialize, Deserialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 1900-01-01 到 1970-01-01 的秒数
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+pub const PACKET_LEN: usize = 48;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NtpPacket {
+    pub leap: u8,
+    pub version: u8,
+    pub mode: u8,
+    pub stratum: u8,
+    pub poll: i8,
+    pub precision: i8,
+    pub root_delay: u32,
+    pub root_dispersion: u32,
+    pub reference_id: [u8; 4],
+    // 64 位 NTP 时间戳：高 32 位为秒，低 32 位为秒的小数部分
+    pub reference_ts: u64,
+    pub origin_ts: u64,
+    pub receive_ts: u64,
+    pub transmit_ts: u64,
+}
+
+impl NtpPacket {
+    // 客户端请求：其余字段为 0，发送时间戳可由调用方填入
+    pub fn client_request(version: u8) -> Self {
+        Self {
+            leap: 0,
+            version,
+            mode: 3,
+            stratum: 0,
+            poll: 0,
+            precision: 0,
+            root_delay: 0,
+            root_dispersion: 0,
+            reference_id: [0; 4],
+            reference_ts: 0,
+            origin_ts: 0,
+            receive_ts: 0,
+            transmit_ts: 0,
+        }
+    }
+}
+
+pub fn now_timestamp() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let fraction = ((now.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    ((now.as_secs() + NTP_UNIX_OFFSET) << 32) | fraction
+}
+
+pub fn encode(packet: &NtpPacket) -> Vec<u8> {
+    let mut out = Vec::with_capacity(PACKET_LEN);
+    out.push((packet.leap & 0x03) << 6 | (packet.version & 0x07) << 3 | (packet.mode & 0x07));
+    out.push(packet.stratum);
+    out.push(packet.poll as u8);
+    out.push(packet.precision as u8);
+    out.extend_from_slice(&packet.root_delay.to_be_bytes());
+    out.extend_from_slice(&packet.root_dispersion.to_be_bytes());
+    out.extend_from_slice(&packet.reference_id);
+    for ts in [packet.reference_ts, packet.origin_ts, packet.receive_ts, packet.transmit_ts] {
+        out.extend_from_slice(&ts.to_be_bytes());
+    }
+    out
+}
+
+// 只解析 48 字节的基本头部，扩展字段和认证信息忽略
+pub fn decode(data: &[u8]) -> Option<NtpPacket> {
+    let data = data.get(..PACKET_LEN)?;
+    let u32_at = |i: usize| u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    let u64_at = |i: usize| ((u32_at(i) as u64) << 32) | u32_at(i + 4) as u64;
+    let version = (data[0] >> 3) & 0x07;
+    if version == 0 {
+        return None;
+    }
+    Some(NtpPacket {
+        leap: data[0] >> 6,
+        version,
+        mode: data[0] & 0x07,
+        stratum: data[1],
+        poll: data[2] as i8,
+        precision: data[3] as i8,
+        root_delay: u32_at(4),
+        root_dispersion: u32_at(8),
+        reference_id: [data[12], data[13], data[14], data[15]],
+        reference_ts: u64_at(16),
+        origin_ts: u64_at(24),
+        receive_ts: u64_at(32),
+        transmit_ts: u64_at(40),
+    })
+}
+
+pub fn mode_name(mode: u8) -> &'static str {
+    match mode {
+        1 => "symmetric active",
+        2 => "symmetric passive",
+        3 => "client",
+        4 => "server",
+        5 => "broadcast",
+        6 => "control",
+        7 => "private",
+        _ => "reserved",
+    }
+}
+
+pub fn describe(packet: &NtpPacket) -> String {
+    let mut text = format!("NTP Version {}, {}", packet.version, mode_name(packet.mode));
+    if packet.mode == 4 || packet.mode == 5 {
+        text.push_str(&format!(", stratum {}", packet.stratum));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_request_round_trip() {
+        let mut request = NtpPacket::client_request(4);
+        request.transmit_ts = now_timestamp();
+        let bytes = encode(&request);
+        assert_eq!(bytes.len(), PACKET_LEN);
+        assert_eq!(bytes[0], 0x23);
+        assert_eq!(decode(&bytes).unwrap(), request);
+        assert_eq!(describe(&request), "NTP Version 4, client");
+        // 2020 年之后的时间戳
+        assert!(request.transmit_ts >> 32 > 3_786_825_600);
+    }
+
+    #[test]
+    fn rejects_short_or_versionless() {
+        assert!(decode(&[0x23; 20]).is_none());
+        assert!(decode(&[0u8; PACKET_LEN]).is_none());
+    }
+}
@@ -1,27 +1,344 @@
 use crate::network::PacketData;
+use crate::network::packet_validation::{self, FieldError};
+use crate::network::packet_schema::{self, ProtocolSchema};
+use crate::network::fragmenter::{self, FragmentOptions, FragmentOrder};
+use crate::network::{tcp_options, dns, dhcp, ntp};
 use anyhow::{Result, anyhow};
 
 pub struct PacketBuilder {
     data: PacketData,
 }
 
+// 由上层协议决定的 IPv4 头部默认值
+struct Ipv4Defaults {
+    protocol: u8,
+    identification: u16,
+    dont_fragment: bool,
+}
+
+// 由应用层协议决定的 UDP 报文默认值，与各协议的字段定义一致
+struct UdpDefaults {
+    dst_mac: &'static str,
+    src_ip: &'static str,
+    dst_ip: &'static str,
+    src_port: u16,
+    dst_port: u16,
+}
+
+const PLAIN_UDP: UdpDefaults = UdpDefaults {
+    dst_mac: "00:00:00:00:00:00",
+    src_ip: "192.168.1.1",
+    dst_ip: "192.168.1.2",
+    src_port: 12345,
+    dst_port: 53,
+};
+
+// 基于 IPv4 的协议，可设置 IP 选项并分片
+fn is_ipv4_based(protocol: &str) -> bool {
+    matches!(protocol, "ip" | "ipv4" | "tcp" | "udp" | "icmp" | "dns" | "dhcp" | "bootp" | "ntp")
+}
+
 impl PacketBuilder {
     pub fn new(data: PacketData) -> Self {
         Self { data }
     }
 
     pub fn build(&self) -> Result<Vec<u8>> {
+        if packet_schema::protocol_fields(&self.data.protocol).is_none() {
+            return Err(anyhow!("不支持的协议类型: {}", self.data.protocol));
+        }
+
+        // 任一字段无效都拒绝构建，避免静默回退到默认值发出与预期不符的报文
+        let errors = self.validate();
+        if !errors.is_empty() {
+            let details: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            return Err(anyhow!("字段校验失败: {}", details.join("; ")));
+        }
+
         match self.data.protocol.to_lowercase().as_str() {
             "ethernet" => self.build_ethernet_packet(),
             "ip" | "ipv4" => self.build_ipv4_packet(),
             "tcp" => self.build_tcp_packet(),
-            "udp" => self.build_udp_packet(),
+            "udp" => self.build_udp_packet(PLAIN_UDP, Vec::new()),
+            "dns" => self.build_udp_packet(PLAIN_UDP, self.build_dns_payload()?),
+            "dhcp" | "bootp" => {
+                // 服务器发出的 BOOTREPLY 从 67 端口发往客户端的 68 端口
+                let (src_port, dst_port) = if self.dhcp_op()? == 2 { (67, 68) } else { (68, 67) };
+                self.build_udp_packet(
+                    UdpDefaults {
+                        dst_mac: "ff:ff:ff:ff:ff:ff",
+                        src_ip: "0.0.0.0",
+                        dst_ip: "255.255.255.255",
+                        src_port,
+                        dst_port,
+                    },
+                    self.build_dhcp_payload()?,
+                )
+            }
+            "ntp" => self.build_udp_packet(UdpDefaults { dst_port: 123, ..PLAIN_UDP }, self.build_ntp_payload()?),
             "arp" => self.build_arp_packet(),
             "icmp" => self.build_icmp_packet(),
+            "raw" | "eth" => self.build_raw_packet(),
             _ => Err(anyhow!("不支持的协议类型: {}", self.data.protocol)),
         }
     }
 
+    // 构建并按 frag_* 字段分片；未分片时只有一个帧
+    pub fn build_frames(&self) -> Result<Vec<Vec<u8>>> {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+        self.fragment_frames(self.build()?, seed)
+    }
+
+    // 将已构建的报文按 frag_* 字段分片，seed 决定随机顺序；
+    // 连续发送时每个报文使用不同的 seed，各报文的分片顺序各不相同
+    pub fn fragment_frames(&self, packet: Vec<u8>, seed: u64) -> Result<Vec<Vec<u8>>> {
+        let size: usize = self.get_dec("frag_size", 0)?;
+        if size == 0 || !is_ipv4_based(&self.data.protocol.to_lowercase()) {
+            return Ok(vec![packet]);
+        }
+
+        let order = self.get_field("frag_order", "in_order")?;
+        let options = FragmentOptions {
+            size,
+            first_size: self.get_dec("frag_first_size", 0)?,
+            overlap: self.get_dec("frag_overlap", 0)?,
+            order: FragmentOrder::parse(&order).ok_or_else(|| anyhow!("字段 frag_order 的值无效: {}", order))?,
+            duplicates: self.get_dec("frag_duplicates", 0)?,
+            seed,
+        };
+        // IPv4 头部紧跟以太网头部
+        let ip_offset = 14;
+        let mut frames = fragmenter::fragment_ipv4(&packet, ip_offset, &options)?;
+
+        // 分片重新计算了校验和，fixed/corrupt 模式需要再次应用
+        for frame in frames.iter_mut() {
+            let ip = &mut frame[ip_offset..];
+            let header_len = ((ip[0] & 0x0F) as usize) * 4;
+            self.write_ip_checksum(ip, header_len)?;
+        }
+        Ok(frames)
+    }
+
+    // 协议的字段定义（名称、别名、类型、位宽、默认值、范围、说明），供前端生成表单
+    pub fn schema(protocol: &str) -> Option<ProtocolSchema> {
+        packet_schema::protocol_schema(protocol)
+    }
+
+    pub fn schemas() -> Vec<ProtocolSchema> {
+        packet_schema::all_schemas()
+    }
+
+    // 校验全部字段和载荷，一次返回所有错误；空值视为使用默认值
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = packet_validation::validate_fields(&self.data.protocol, &self.data.fields);
+        let protocol = self.data.protocol.to_lowercase();
+        if packet_schema::protocol_fields(&protocol).is_none() {
+            return errors;
+        }
+
+        // 各选项字段单独合法时，合计长度仍可能超出头部上限
+        let options_valid = !errors.iter().any(|e| e.field.starts_with("ip_option"));
+        if is_ipv4_based(&protocol) && options_valid {
+            if let Err(e) = self.build_ip_options() {
+                errors.push(FieldError {
+                    field: "ip_options".to_string(),
+                    value: self.get_field("ip_options", "").unwrap_or_default(),
+                    expected: format!("与记录路由、时间戳选项合计不超过 40 字节（{}）", e),
+                });
+            }
+        }
+
+        // 分片偏移以 8 字节为单位
+        if is_ipv4_based(&protocol) {
+            for key in ["frag_size", "frag_first_size", "frag_overlap"] {
+                if let Ok(n) = self.get_dec::<usize>(key, 0) {
+                    if !n.is_multiple_of(8) {
+                        errors.push(FieldError { field: key.to_string(), value: n.to_string(), expected: "8 的倍数".to_string() });
+                    }
+                }
+            }
+            // 重叠须小于首分片和后续分片的大小
+            if let (Ok(size), Ok(first_size), Ok(overlap)) = (
+                self.get_dec::<usize>("frag_size", 0),
+                self.get_dec::<usize>("frag_first_size", 0),
+                self.get_dec::<usize>("frag_overlap", 0),
+            ) {
+                let first_size = if first_size > 0 { first_size } else { size };
+                if size > 0 && (overlap >= size || overlap >= first_size) {
+                    errors.push(FieldError {
+                        field: "frag_overlap".to_string(),
+                        value: overlap.to_string(),
+                        expected: format!("小于首分片大小 {} 和分片大小 {}", first_size, size),
+                    });
+                }
+            }
+        }
+
+        let payload = self.data.payload.as_deref().unwrap_or("").trim();
+        let payload_error = if matches!(protocol.as_str(), "raw" | "eth") {
+            // 载荷格式本身无效时已单独报错
+            let format = self.get_field_multi(&["payload_format", "format"], "").unwrap_or_default();
+            if packet_validation::check_value(packet_validation::FieldKind::PayloadFormat, &format).is_err() {
+                None
+            } else {
+                match self.decode_raw_payload(payload, format.trim()) {
+                    Ok(bytes) if bytes.is_empty() => Some("非空的以太网帧".to_string()),
+                    Ok(_) => None,
+                    Err(e) => Some(e.to_string()),
+                }
+            }
+        } else if !payload.is_empty() && self.parse_hex(payload).is_err() {
+            Some("十六进制字节串".to_string())
+        } else {
+            None
+        };
+
+        if let Some(expected) = payload_error {
+            errors.push(FieldError {
+                field: "payload".to_string(),
+                value: payload.chars().take(64).collect(),
+                expected,
+            });
+        }
+        errors
+    }
+
+    // 原始帧：payload 即完整的以太网帧，按原样发送
+    // payload 支持十六进制（默认）、base64 或文件引用，可通过 payload_format 字段
+    // 或 "base64:" / "file:" 前缀指定；fixup 为真时自动修正 IPv4/L4 长度与校验和
+    fn build_raw_packet(&self) -> Result<Vec<u8>> {
+        let payload = self.data.payload.as_deref().unwrap_or("").trim();
+        let format = self.get_field_multi(&["payload_format", "format"], "")?;
+        let mut packet = self.decode_raw_payload(payload, format.trim())?;
+
+        if packet.is_empty() {
+            return Err(anyhow!("原始帧内容为空"));
+        }
+
+        let fixup = self.get_field_multi(&["fixup", "auto_fixup"], "false")?;
+        if self.parse_bool_flag(&fixup) {
+            // 以太网填充保留在帧尾，但不计入 IPv4 和 UDP 长度
+            let end = ipv4_frame_end(&packet);
+            self.fixup_ipv4_frame(&mut packet[..end])?;
+        }
+
+        Ok(packet)
+    }
+
+    fn decode_raw_payload(&self, payload: &str, format: &str) -> Result<Vec<u8>> {
+        let (format, content) = match format.to_lowercase().as_str() {
+            "" => {
+                if let Some(rest) = payload.strip_prefix("base64:") {
+                    ("base64", rest)
+                } else if let Some(rest) = payload.strip_prefix("file:") {
+                    ("file", rest)
+                } else {
+                    ("hex", payload)
+                }
+            }
+            "hex" => ("hex", payload),
+            "base64" => ("base64", payload.strip_prefix("base64:").unwrap_or(payload)),
+            "file" => ("file", payload.strip_prefix("file:").unwrap_or(payload)),
+            other => return Err(anyhow!("不支持的载荷格式: {}", other)),
+        };
+
+        match format {
+            "base64" => self.parse_base64(content),
+            "file" => {
+                let path = content.trim();
+                std::fs::read(path).map_err(|e| anyhow!("读取帧文件 {} 失败: {}", path, e))
+            }
+            _ => self.parse_hex(content),
+        }
+    }
+
+    // 修正以太网帧中的 IPv4 总长度、头部校验和，以及 TCP/UDP/ICMP 的长度与校验和；
+    // 帧尾的全部字节都计入数据报，带以太网填充的帧应先用 ipv4_frame_end 截取
+    pub(crate) fn fixup_ipv4_frame(&self, frame: &mut [u8]) -> Result<()> {
+        if frame.len() < 14 {
+            return Err(anyhow!("帧长度不足以太网头部: {} 字节", frame.len()));
+        }
+
+        // 跳过 802.1Q / 802.1ad VLAN 标签
+        let mut offset = 12;
+        let mut ether_type = u16::from_be_bytes([frame[offset], frame[offset + 1]]);
+        while (ether_type == 0x8100 || ether_type == 0x88a8) && frame.len() >= offset + 6 {
+            offset += 4;
+            ether_type = u16::from_be_bytes([frame[offset], frame[offset + 1]]);
+        }
+        let ip_start = offset + 2;
+
+        if ether_type != 0x0800 {
+            return Ok(()); // 非 IPv4 帧，无需修正
+        }
+
+        if frame.len() < ip_start + 20 {
+            return Err(anyhow!("IPv4 头部不完整"));
+        }
+
+        let ihl = ((frame[ip_start] & 0x0f) as usize) * 4;
+        if ihl < 20 || frame.len() < ip_start + ihl {
+            return Err(anyhow!("无效的 IPv4 头部长度: {}", ihl));
+        }
+
+        // IPv4 总长度与头部校验和
+        let ip_length = frame.len() - ip_start;
+        if ip_length > u16::MAX as usize {
+            return Err(anyhow!("IPv4 数据报过长: {} 字节", ip_length));
+        }
+        frame[ip_start + 2..ip_start + 4].copy_from_slice(&(ip_length as u16).to_be_bytes());
+        frame[ip_start + 10] = 0;
+        frame[ip_start + 11] = 0;
+        let ip_checksum = self.calculate_ip_checksum(&frame[ip_start..ip_start + ihl]);
+        frame[ip_start + 10..ip_start + 12].copy_from_slice(&ip_checksum.to_be_bytes());
+
+        // 分片报文的传输层头部不完整，不做修正
+        let flags_fragment = u16::from_be_bytes([frame[ip_start + 6], frame[ip_start + 7]]);
+        if flags_fragment & 0x3fff != 0 {
+            return Ok(());
+        }
+
+        let protocol = frame[ip_start + 9];
+        let l4_start = ip_start + ihl;
+        let mut src_ip = [0u8; 4];
+        let mut dst_ip = [0u8; 4];
+        src_ip.copy_from_slice(&frame[ip_start + 12..ip_start + 16]);
+        dst_ip.copy_from_slice(&frame[ip_start + 16..ip_start + 20]);
+        let segment_len = frame.len() - l4_start;
+
+        match protocol {
+            6 if segment_len >= 20 => {
+                frame[l4_start + 16] = 0;
+                frame[l4_start + 17] = 0;
+                let checksum = self.calculate_l4_checksum(&src_ip, &dst_ip, protocol, &frame[l4_start..]);
+                frame[l4_start + 16..l4_start + 18].copy_from_slice(&checksum.to_be_bytes());
+            }
+            17 if segment_len >= 8 => {
+                frame[l4_start + 4..l4_start + 6].copy_from_slice(&(segment_len as u16).to_be_bytes());
+                frame[l4_start + 6] = 0;
+                frame[l4_start + 7] = 0;
+                let mut checksum = self.calculate_l4_checksum(&src_ip, &dst_ip, protocol, &frame[l4_start..]);
+                // UDP 中 0 表示未计算校验和，计算结果为 0 时应写入 0xFFFF
+                if checksum == 0 {
+                    checksum = 0xFFFF;
+                }
+                frame[l4_start + 6..l4_start + 8].copy_from_slice(&checksum.to_be_bytes());
+            }
+            1 if segment_len >= 4 => {
+                frame[l4_start + 2] = 0;
+                frame[l4_start + 3] = 0;
+                let checksum = self.calculate_icmp_checksum(&frame[l4_start..]);
+                frame[l4_start + 2..l4_start + 4].copy_from_slice(&checksum.to_be_bytes());
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     fn build_ethernet_packet(&self) -> Result<Vec<u8>> {
         let mut packet = Vec::new();
         
@@ -34,8 +351,7 @@ impl PacketBuilder {
         packet.extend_from_slice(&self.parse_mac(&src_mac)?);
         
         // 以太网类型 (2 bytes) - 默认 IPv4
-        let ether_type = self.get_field("ether_type", "0800")?;
-        packet.extend_from_slice(&self.parse_hex(&ether_type)?);
+        packet.extend_from_slice(&self.get_hex_bytes(&["ether_type"], "0800", 2)?);
         
         // Payload
         if let Some(payload) = &self.data.payload {
@@ -60,116 +376,124 @@ impl PacketBuilder {
         let src_mac = self.get_field("src_mac", "00:00:00:00:00:00")?;
         packet.extend_from_slice(&self.parse_mac(&src_mac)?);
         
-        let ether_type = self.get_field("ether_type", "0800")?;
-        packet.extend_from_slice(&self.parse_hex(&ether_type)?);
-        
-        // IPv4头部 (20 bytes)
-        let version = self.get_field("version", "4")?.parse::<u8>().unwrap_or(4);
-        let ihl = self.get_field("ihl", "5")?.parse::<u8>().unwrap_or(5);
-        packet.push((version << 4) | ihl);
-        
-        let tos = self.get_field("tos", "0")?.parse::<u8>().unwrap_or(0);
-        packet.push(tos);
-        
-        // 总长度 (稍后计算)
-        let total_length_pos = packet.len();
-        packet.extend_from_slice(&[0x00, 0x00]);
-        
-        let identification = self.get_field("identification", "0")?.parse::<u16>().unwrap_or(0);
-        packet.extend_from_slice(&identification.to_be_bytes());
+        packet.extend_from_slice(&self.get_hex_bytes(&["ether_type"], "0800", 2)?);
         
-        let flags = self.get_field("flags", "2")?.parse::<u8>().unwrap_or(2);
-        let fragment_offset = self.get_field("fragment_offset", "0")?.parse::<u16>().unwrap_or(0);
-        packet.extend_from_slice(&(((flags as u16) << 13) | fragment_offset).to_be_bytes());
-        
-        let ttl = self.get_field("ttl", "64")?.parse::<u8>().unwrap_or(64);
-        packet.push(ttl);
-        
-        let protocol = self.get_field("protocol", "6")?.parse::<u8>().unwrap_or(6);
-        packet.push(protocol);
-        
-        // 头部校验和 (稍后计算)
-        let checksum_pos = packet.len();
-        packet.extend_from_slice(&[0x00, 0x00]);
-        
-        let src_ip = self.get_field("srcIp", "192.168.1.1")?;
-        packet.extend_from_slice(&self.parse_ip(&src_ip)?);
-        
-        let dst_ip = self.get_field("dstIp", "192.168.1.2")?;
-        packet.extend_from_slice(&self.parse_ip(&dst_ip)?);
+        let src_ip = self.parse_ip(&self.get_field("srcIp", "192.168.1.1")?)?;
+        let dst_ip = self.parse_ip(&self.get_field("dstIp", "192.168.1.2")?)?;
+        let protocol: u8 = self.get_dec("protocol", 6)?;
+        let header = self.build_ipv4_header(
+            Ipv4Defaults { protocol, identification: 0, dont_fragment: true },
+            &src_ip,
+            &dst_ip,
+        )?;
+        let header_len = header.len();
+        packet.extend_from_slice(&header);
         
         // Payload
         if let Some(payload) = &self.data.payload {
             packet.extend_from_slice(&self.parse_hex(payload)?);
         }
         
-        // 计算并填充总长度 (IPv4头部 + payload)
-        let ip_length = packet.len() - 14; // 减去以太网头部
-        packet[total_length_pos] = (ip_length >> 8) as u8;
-        packet[total_length_pos + 1] = ip_length as u8;
-        
-        // 计算并填充IPv4头部校验和
-        let checksum = self.calculate_ip_checksum(&packet[14..14 + 20]);
-        packet[checksum_pos] = (checksum >> 8) as u8;
-        packet[checksum_pos + 1] = checksum as u8;
+        self.finish_ipv4_header(&mut packet[14..], header_len)?;
         
         Ok(packet)
     }
 
-    fn build_ip_packet(&self) -> Result<Vec<u8>> {
-        let mut packet = Vec::new();
+    // IPv4 头部（含选项）；总长度和校验和留空，追加完上层数据后由 finish_ipv4_header 填写
+    fn build_ipv4_header(&self, defaults: Ipv4Defaults, src_ip: &[u8], dst_ip: &[u8]) -> Result<Vec<u8>> {
+        let options = self.build_ip_options()?;
+        let mut header = Vec::with_capacity(20 + options.len());
         
-        // 版本和头部长度 (1 byte)
-        packet.push(0x45); // IPv4, 头部长度 5*4=20 bytes
+        // 版本和头部长度，IHL 默认按选项长度计算，允许填写不一致的值
+        let version: u8 = self.get_dec("version", 4)?;
+        let ihl: u8 = self.get_dec("ihl", (5 + options.len() / 4) as u8)?;
+        header.push(((version & 0x0F) << 4) | (ihl & 0x0F));
         
-        // 服务类型 (1 byte)
-        packet.push(0x00);
+        // 服务类型：DSCP(6) | ECN(2)，tos 填写时整体覆盖
+        let dscp: u8 = self.get_dec("dscp", 0)?;
+        let ecn: u8 = self.get_dec("ecn", 0)?;
+        header.push(self.get_dec("tos", ((dscp & 0x3F) << 2) | (ecn & 0x03))?);
         
-        // 总长度 (2 bytes) - 稍后填充
-        packet.extend_from_slice(&[0x00, 0x00]);
+        // 总长度 - 稍后填充
+        header.extend_from_slice(&[0x00, 0x00]);
         
-        // 标识 (2 bytes)
-        let id = self.get_field("id", "0000")?;
-        packet.extend_from_slice(&self.parse_hex(&id)?);
+        let identification: u16 = self.get_dec("identification", defaults.identification)?;
+        header.extend_from_slice(&identification.to_be_bytes());
         
-        // 标志和片偏移 (2 bytes)
-        packet.extend_from_slice(&[0x00, 0x00]);
+        // 标志：保留位 | DF | MF，flags 填写时整体覆盖
+        let df: u16 = self.get_dec("df", defaults.dont_fragment as u16)?;
+        let mf: u16 = self.get_dec("mf", 0)?;
+        let flags: u16 = self.get_dec("flags", ((df & 0x01) << 1) | (mf & 0x01))?;
+        let fragment_offset: u16 = self.get_dec("fragment_offset", 0)?;
+        header.extend_from_slice(&(((flags & 0x07) << 13) | (fragment_offset & 0x1FFF)).to_be_bytes());
         
-        // TTL (1 byte)
-        let ttl = self.get_field("ttl", "64")?;
-        packet.push(ttl.parse::<u8>().unwrap_or(64));
+        header.push(self.get_dec("ttl", 64)?);
+        header.push(self.get_dec("ip_protocol", defaults.protocol)?);
         
-        // 协议 (1 byte) - 默认 TCP
-        let protocol = self.get_field("protocol", "06")?;
-        packet.push(u8::from_str_radix(&protocol, 16).unwrap_or(6));
+        // 校验和 - 稍后计算
+        header.extend_from_slice(&[0x00, 0x00]);
         
-        // 校验和 (2 bytes) - 稍后计算
-        packet.extend_from_slice(&[0x00, 0x00]);
+        header.extend_from_slice(src_ip);
+        header.extend_from_slice(dst_ip);
+        header.extend_from_slice(&options);
         
-        // 源 IP (4 bytes)
-        let src_ip = self.get_field("src_ip", "192.168.1.1")?;
-        packet.extend_from_slice(&self.parse_ip(&src_ip)?);
+        Ok(header)
+    }
+
+    // 记录路由、时间戳、原始选项依次排列，末尾以 0（选项结束）补齐到 4 字节
+    fn build_ip_options(&self) -> Result<Vec<u8>> {
+        let mut options = Vec::new();
         
-        // 目标 IP (4 bytes)
-        let dst_ip = self.get_field("dst_ip", "192.168.1.2")?;
-        packet.extend_from_slice(&self.parse_ip(&dst_ip)?);
+        let rr_slots: u8 = self.get_dec("ip_option_rr", 0)?;
+        if rr_slots > 0 {
+            options.extend_from_slice(&[0x07, 3 + rr_slots * 4, 4]);
+            options.resize(options.len() + rr_slots as usize * 4, 0);
+        }
         
-        // Payload
-        if let Some(payload) = &self.data.payload {
-            packet.extend_from_slice(&self.parse_hex(payload)?);
+        // 时间戳选项，标志 0 表示只记录时间戳
+        let ts_slots: u8 = self.get_dec("ip_option_ts", 0)?;
+        if ts_slots > 0 {
+            options.extend_from_slice(&[0x44, 4 + ts_slots * 4, 5, 0x00]);
+            options.resize(options.len() + ts_slots as usize * 4, 0);
         }
         
-        // 计算并填充总长度
-        let total_length = packet.len() as u16;
-        packet[2] = (total_length >> 8) as u8;
-        packet[3] = total_length as u8;
+        let raw = self.get_field("ip_options", "")?;
+        if !raw.is_empty() {
+            let bytes = packet_validation::parse_hex_data(&raw)
+                .ok_or_else(|| anyhow!("字段 ip_options 的值无效: {}", raw))?;
+            options.extend_from_slice(&bytes);
+        }
         
-        // 计算并填充校验和
-        let checksum = self.calculate_ip_checksum(&packet[0..20]);
-        packet[10] = (checksum >> 8) as u8;
-        packet[11] = checksum as u8;
+        while !options.len().is_multiple_of(4) {
+            options.push(0);
+        }
+        if options.len() > 40 {
+            return Err(anyhow!("IP 选项共 {} 字节，超过 40 字节上限", options.len()));
+        }
+        Ok(options)
+    }
+
+    // ip 从 IPv4 头部开始到报文末尾
+    fn finish_ipv4_header(&self, ip: &mut [u8], header_len: usize) -> Result<()> {
+        let total_length = u16::try_from(ip.len())
+            .map_err(|_| anyhow!("IPv4 总长度 {} 超过 65535", ip.len()))?;
+        ip[2..4].copy_from_slice(&total_length.to_be_bytes());
         
-        Ok(packet)
+        self.write_ip_checksum(ip, header_len)
+    }
+
+    // 按 ip_checksum_mode 写入头部校验和
+    fn write_ip_checksum(&self, ip: &mut [u8], header_len: usize) -> Result<()> {
+        ip[10..12].copy_from_slice(&[0x00, 0x00]);
+        let computed = self.calculate_ip_checksum(&ip[..header_len]);
+        let checksum = match self.get_field("ip_checksum_mode", "auto")?.trim().to_lowercase().as_str() {
+            "fixed" => self.get_checksum("ip_checksum")?,
+            // 翻转最低位，反码运算下也不会与正确值等价
+            "corrupt" => computed ^ 0x0001,
+            _ => computed,
+        };
+        ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+        Ok(())
     }
 
     fn build_tcp_packet(&self) -> Result<Vec<u8>> {
@@ -182,92 +506,47 @@ impl PacketBuilder {
         let src_mac = self.get_field("src_mac", "00:00:00:00:00:00")?;
         packet.extend_from_slice(&self.parse_mac(&src_mac)?);
         
-        let ether_type = self.get_field("ether_type", "0800")?;
-        packet.extend_from_slice(&self.parse_hex(&ether_type)?);
-        
-        // IPv4头部 (20 bytes) - 简化版本
-        packet.push(0x45); // Version 4, IHL 5
-        packet.push(0x00); // TOS
-        
-        // 总长度 (稍后计算)
-        let total_length_pos = packet.len();
-        packet.extend_from_slice(&[0x00, 0x00]);
-        
-        packet.extend_from_slice(&[0x00, 0x00]); // ID
-        packet.extend_from_slice(&[0x40, 0x00]); // Flags, Fragment offset
-        packet.push(64); // TTL
-        packet.push(6); // Protocol (TCP)
-        
-        // IP头部校验和 (稍后计算)
-        let ip_checksum_pos = packet.len();
-        packet.extend_from_slice(&[0x00, 0x00]);
-        
-        let src_ip = self.get_field("srcIp", "192.168.1.1")?;
-        packet.extend_from_slice(&self.parse_ip(&src_ip)?);
+        packet.extend_from_slice(&self.get_hex_bytes(&["ether_type"], "0800", 2)?);
         
-        let dst_ip = self.get_field("dstIp", "192.168.1.2")?;
-        packet.extend_from_slice(&self.parse_ip(&dst_ip)?);
+        // IPv4头部
+        let src_ip = self.parse_ip(&self.get_field("srcIp", "192.168.1.1")?)?;
+        let dst_ip = self.parse_ip(&self.get_field("dstIp", "192.168.1.2")?)?;
+        let header = self.build_ipv4_header(
+            Ipv4Defaults { protocol: 6, identification: 0, dont_fragment: true },
+            &src_ip,
+            &dst_ip,
+        )?;
+        let ip_header_len = header.len();
+        packet.extend_from_slice(&header);
         
         // TCP 头部 (20 bytes)
-        let src_port = self.get_field("srcPort", "12345")?;
-        let src_port = src_port.parse::<u16>().unwrap_or(12345);
+        let src_port: u16 = self.get_dec("srcPort", 12345)?;
         packet.extend_from_slice(&src_port.to_be_bytes());
         
-        let dst_port = self.get_field("dstPort", "80")?;
-        let dst_port = dst_port.parse::<u16>().unwrap_or(80);
+        let dst_port: u16 = self.get_dec("dstPort", 80)?;
         packet.extend_from_slice(&dst_port.to_be_bytes());
         
         // 序列号 (4 bytes)
-        let seq = self.get_field("seq", "0")?;
-        let seq = seq.parse::<u32>().unwrap_or(0);
+        let seq: u32 = self.get_dec("seq", 0)?;
         packet.extend_from_slice(&seq.to_be_bytes());
         
         // 确认号 (4 bytes)
-        let ack = self.get_field("ack", "0")?;
-        let ack = ack.parse::<u32>().unwrap_or(0);
+        let ack: u32 = self.get_dec("ack", 0)?;
         packet.extend_from_slice(&ack.to_be_bytes());
         
+        // 选项在窗口大小、校验和、紧急指针之后写入，数据偏移默认按选项长度计算
+        let options_spec = self.get_field("tcp_options", "")?;
+        let tcp_options = tcp_options::encode_options(&tcp_options::parse_options_spec(&options_spec)?)?;
+        
         // 数据偏移与标志 (2 bytes)
-        let data_offset = self
-            .get_field("data_offset", "5")?
-            .parse::<u8>()
-            .unwrap_or(5)
-            .clamp(5, 15);
-        let reserved = self
-            .get_field("reserved", "0")?
-            .parse::<u8>()
-            .unwrap_or(0)
-            & 0x07;
-        let flag_urg = self
-            .get_field("flag_urg", "0")?
-            .parse::<u8>()
-            .unwrap_or(0)
-            & 0x01;
-        let flag_ack = self
-            .get_field("flag_ack", "0")?
-            .parse::<u8>()
-            .unwrap_or(0)
-            & 0x01;
-        let flag_psh = self
-            .get_field("flag_psh", "0")?
-            .parse::<u8>()
-            .unwrap_or(0)
-            & 0x01;
-        let flag_rst = self
-            .get_field("flag_rst", "0")?
-            .parse::<u8>()
-            .unwrap_or(0)
-            & 0x01;
-        let flag_syn = self
-            .get_field("flag_syn", "1")?
-            .parse::<u8>()
-            .unwrap_or(1)
-            & 0x01;
-        let flag_fin = self
-            .get_field("flag_fin", "0")?
-            .parse::<u8>()
-            .unwrap_or(0)
-            & 0x01;
+        let data_offset = self.get_dec::<u8>("data_offset", (5 + tcp_options.len() / 4) as u8)?.clamp(5, 15);
+        let reserved = self.get_dec::<u8>("reserved", 0)? & 0x07;
+        let flag_urg = self.get_dec::<u8>("flag_urg", 0)? & 0x01;
+        let flag_ack = self.get_dec::<u8>("flag_ack", 0)? & 0x01;
+        let flag_psh = self.get_dec::<u8>("flag_psh", 0)? & 0x01;
+        let flag_rst = self.get_dec::<u8>("flag_rst", 0)? & 0x01;
+        let flag_syn = self.get_dec::<u8>("flag_syn", 1)? & 0x01;
+        let flag_fin = self.get_dec::<u8>("flag_fin", 0)? & 0x01;
 
         // 第一个字节: data_offset(4) | reserved(3) | ns(1=0)
         let offset_reserved_ns = ((data_offset & 0x0F) << 4) | ((reserved & 0x07) << 1);
@@ -281,8 +560,7 @@ impl PacketBuilder {
         packet.extend_from_slice(&[offset_reserved_ns, flags_byte]);
         
         // 窗口大小 (2 bytes)
-        let window_size = self.get_field("window_size", "8192")?;
-        let window_size = window_size.parse::<u16>().unwrap_or(8192);
+        let window_size: u16 = self.get_dec("window_size", 8192)?;
         packet.extend_from_slice(&window_size.to_be_bytes());
         
         // 校验和 (2 bytes) - 默认0；若用户提供则填入
@@ -290,90 +568,60 @@ impl PacketBuilder {
         packet.extend_from_slice(&[0x00, 0x00]);
         
         // 紧急指针 (2 bytes)
-        let urgent_pointer = self.get_field("urgent_pointer", "0")?;
-        let urgent_pointer = urgent_pointer.parse::<u16>().unwrap_or(0);
+        let urgent_pointer: u16 = self.get_dec("urgent_pointer", 0)?;
         packet.extend_from_slice(&urgent_pointer.to_be_bytes());
         
+        // 选项 (0-40 bytes)
+        packet.extend_from_slice(&tcp_options);
+        
         // Payload
         if let Some(payload) = &self.data.payload {
             packet.extend_from_slice(&self.parse_hex(payload)?);
         }
         
-        // 计算并填充总长度 (IPv4头部 + TCP头部 + payload)
-        let ip_length = packet.len() - 14; // 减去以太网头部
-        packet[total_length_pos] = (ip_length >> 8) as u8;
-        packet[total_length_pos + 1] = ip_length as u8;
-        
-        // 计算并填充IP头部校验和
-        let checksum = self.calculate_ip_checksum(&packet[14..14 + 20]);
-        packet[ip_checksum_pos] = (checksum >> 8) as u8;
-        packet[ip_checksum_pos + 1] = checksum as u8;
+        // 填充总长度和IP头部校验和 (IPv4头部 + TCP头部 + payload)
+        self.finish_ipv4_header(&mut packet[14..], ip_header_len)?;
 
         // 如果用户提供了 TCP 校验和，使用用户值覆盖（十六进制或十进制）
-        if let Ok(cs_str) = self.get_field("checksum", "0") {
-            let cs_trim = cs_str.trim();
-            if !cs_trim.is_empty() {
-                let cs_val = if cs_trim.starts_with("0x") || cs_trim.starts_with("0X") {
-                    u16::from_str_radix(&cs_trim[2..], 16).unwrap_or(0)
-                } else if let Ok(dec) = cs_trim.parse::<u16>() {
-                    dec
-                } else {
-                    u16::from_str_radix(cs_trim, 16).unwrap_or(0)
-                };
-                if cs_val != 0 {
-                    let bytes = cs_val.to_be_bytes();
-                    packet[tcp_checksum_pos] = bytes[0];
-                    packet[tcp_checksum_pos + 1] = bytes[1];
-                }
-            }
+        let cs_val = self.get_checksum("checksum")?;
+        if cs_val != 0 {
+            let bytes = cs_val.to_be_bytes();
+            packet[tcp_checksum_pos] = bytes[0];
+            packet[tcp_checksum_pos + 1] = bytes[1];
         }
         
         Ok(packet)
     }
 
-    fn build_udp_packet(&self) -> Result<Vec<u8>> {
+    // app_data 为应用层协议生成的数据，放在 payload 之前
+    fn build_udp_packet(&self, defaults: UdpDefaults, app_data: Vec<u8>) -> Result<Vec<u8>> {
         let mut packet = Vec::new();
         
         // 以太网头部 (14 bytes)
-        let dst_mac = self.get_field("dst_mac", "00:00:00:00:00:00")?;
+        let dst_mac = self.get_field("dst_mac", defaults.dst_mac)?;
         packet.extend_from_slice(&self.parse_mac(&dst_mac)?);
         
         let src_mac = self.get_field("src_mac", "00:00:00:00:00:00")?;
         packet.extend_from_slice(&self.parse_mac(&src_mac)?);
         
-        let ether_type = self.get_field("ether_type", "0800")?;
-        packet.extend_from_slice(&self.parse_hex(&ether_type)?);
-        
-        // IPv4头部 (20 bytes) - 简化版本
-        packet.push(0x45); // Version 4, IHL 5
-        packet.push(0x00); // TOS
-        
-        // 总长度 (稍后计算)
-        let total_length_pos = packet.len();
-        packet.extend_from_slice(&[0x00, 0x00]);
-        
-        packet.extend_from_slice(&[0x00, 0x00]); // ID
-        packet.extend_from_slice(&[0x40, 0x00]); // Flags, Fragment offset
-        packet.push(64); // TTL
-        packet.push(17); // Protocol (UDP)
-        
-        // IP头部校验和 (稍后计算)
-        let ip_checksum_pos = packet.len();
-        packet.extend_from_slice(&[0x00, 0x00]);
-        
-        let src_ip = self.get_field("srcIp", "192.168.1.1")?;
-        packet.extend_from_slice(&self.parse_ip(&src_ip)?);
+        packet.extend_from_slice(&self.get_hex_bytes(&["ether_type"], "0800", 2)?);
         
-        let dst_ip = self.get_field("dstIp", "192.168.1.2")?;
-        packet.extend_from_slice(&self.parse_ip(&dst_ip)?);
+        // IPv4头部
+        let src_ip = self.parse_ip(&self.get_field("srcIp", defaults.src_ip)?)?;
+        let dst_ip = self.parse_ip(&self.get_field("dstIp", defaults.dst_ip)?)?;
+        let header = self.build_ipv4_header(
+            Ipv4Defaults { protocol: 17, identification: 0, dont_fragment: true },
+            &src_ip,
+            &dst_ip,
+        )?;
+        let ip_header_len = header.len();
+        packet.extend_from_slice(&header);
         
         // UDP 头部 (8 bytes)
-        let src_port = self.get_field("srcPort", "12345")?;
-        let src_port = src_port.parse::<u16>().unwrap_or(12345);
+        let src_port: u16 = self.get_dec("srcPort", defaults.src_port)?;
         packet.extend_from_slice(&src_port.to_be_bytes());
         
-        let dst_port = self.get_field("dstPort", "53")?;
-        let dst_port = dst_port.parse::<u16>().unwrap_or(53);
+        let dst_port: u16 = self.get_dec("dstPort", defaults.dst_port)?;
         packet.extend_from_slice(&dst_port.to_be_bytes());
         
         // UDP长度 (2 bytes) - 稍后填充
@@ -385,90 +633,180 @@ impl PacketBuilder {
         packet.extend_from_slice(&[0x00, 0x00]);
         
         // Payload
+        packet.extend_from_slice(&app_data);
         if let Some(payload) = &self.data.payload {
             packet.extend_from_slice(&self.parse_hex(payload)?);
         }
         
-        // 计算并填充总长度 (IPv4头部 + UDP头部 + payload)
-        let ip_length = packet.len() - 14; // 减去以太网头部
-        packet[total_length_pos] = (ip_length >> 8) as u8;
-        packet[total_length_pos + 1] = ip_length as u8;
-        
         // 计算并填充UDP长度 (UDP头部 + payload)，允许前端覆盖
-        let computed_udp_length = (packet.len() - 14 - 20) as u16; // 减去以太网头部和IP头部
-        let udp_len_field = self.get_field("length", "0")?;
-        let udp_len_override = udp_len_field.parse::<u16>().unwrap_or(0);
+        let computed_udp_length = (packet.len() - 14 - ip_header_len) as u16; // 减去以太网头部和IP头部
+        let udp_len_override: u16 = self.get_dec("length", 0)?;
         let udp_length = if udp_len_override > 0 { udp_len_override } else { computed_udp_length };
         packet[udp_length_pos] = (udp_length >> 8) as u8;
         packet[udp_length_pos + 1] = udp_length as u8;
         
-        // 计算并填充IP头部校验和
-        let checksum = self.calculate_ip_checksum(&packet[14..14 + 20]);
-        packet[ip_checksum_pos] = (checksum >> 8) as u8;
-        packet[ip_checksum_pos + 1] = checksum as u8;
+        // 填充总长度和IP头部校验和 (IPv4头部 + UDP头部 + payload)
+        self.finish_ipv4_header(&mut packet[14..], ip_header_len)?;
 
         // 覆盖UDP校验和（如果前端提供了非0值；未计算伪首部校验和）
-        if let Ok(cs_str) = self.get_field("checksum", "0") {
-            let cs_trim = cs_str.trim();
-            if !cs_trim.is_empty() {
-                let cs_val = if cs_trim.starts_with("0x") || cs_trim.starts_with("0X") {
-                    u16::from_str_radix(&cs_trim[2..], 16).unwrap_or(0)
-                } else if let Ok(dec) = cs_trim.parse::<u16>() {
-                    dec
-                } else {
-                    u16::from_str_radix(cs_trim, 16).unwrap_or(0)
-                };
-                if cs_val != 0 {
-                    let bytes = cs_val.to_be_bytes();
-                    packet[udp_checksum_pos] = bytes[0];
-                    packet[udp_checksum_pos + 1] = bytes[1];
-                }
-            }
+        let cs_val = self.get_checksum("checksum")?;
+        if cs_val != 0 {
+            let bytes = cs_val.to_be_bytes();
+            packet[udp_checksum_pos] = bytes[0];
+            packet[udp_checksum_pos + 1] = bytes[1];
         }
         
         Ok(packet)
     }
 
-    fn build_arp_packet(&self) -> Result<Vec<u8>> {
-        let mut packet = Vec::new();
+    fn build_dns_payload(&self) -> Result<Vec<u8>> {
+        let mut flags = (self.get_dec::<u16>("dns_qr", 0)? << 15) | (self.get_dec::<u16>("dns_opcode", 0)? << 11);
+        for (key, bit, default) in [("dns_aa", 10, 0), ("dns_tc", 9, 0), ("dns_rd", 8, 1), ("dns_ra", 7, 0)] {
+            flags |= self.get_dec::<u16>(key, default)? << bit;
+        }
+        flags |= self.get_dec::<u16>("dns_rcode", 0)?;
+
+        let message = dns::DnsMessage {
+            id: self.get_dec("dns_id", 0)?,
+            flags,
+            questions: dns::parse_questions(&self.get_field("dns_questions", "example.com A")?)?,
+            answers: dns::parse_records(&self.get_field("dns_answers", "")?)?,
+            authorities: dns::parse_records(&self.get_field("dns_authorities", "")?)?,
+            additionals: dns::parse_records(&self.get_field("dns_additionals", "")?)?,
+        };
+        dns::encode_message(&message)
+    }
 
-        // Ethernet header
-        let dst_mac = self.get_field_multi(&["dst_mac", "dstMac", "target_mac", "targetMac"], "ff:ff:ff:ff:ff:ff")?;
-        packet.extend_from_slice(&self.parse_mac(&dst_mac)?);
+    fn dhcp_message_type(&self) -> Result<u8> {
+        let message_type = self.get_field("dhcp_message_type", "discover")?;
+        dhcp::message_type_code(&message_type)
+            .ok_or_else(|| anyhow!("字段 dhcp_message_type 的值无效: {}", message_type))
+    }
 
-        let src_mac = self.get_field_multi(&["src_mac", "srcMac", "sender_mac", "senderMac"], "00:00:00:00:00:00")?;
-        packet.extend_from_slice(&self.parse_mac(&src_mac)?);
+    // 未填写 dhcp_op 时由消息类型决定
+    fn dhcp_op(&self) -> Result<u8> {
+        self.get_dec("dhcp_op", dhcp::op_for_message_type(self.dhcp_message_type()?))
+    }
 
-        let ether_type = self.get_field_multi(&["ether_type", "etherType"], "0806")?;
-        packet.extend_from_slice(&self.parse_hex(&ether_type)?);
+    fn build_dhcp_payload(&self) -> Result<Vec<u8>> {
+        let type_code = self.dhcp_message_type()?;
+        let op = self.dhcp_op()?;
+        let xid = self.get_field("dhcp_xid", "0")?;
+        let chaddr = self.get_field_multi(&["dhcp_client_mac", "src_mac"], "00:00:00:00:00:00")?;
+        let addr = |key: &str| -> Result<[u8; 4]> {
+            let ip = self.parse_ip(&self.get_field(key, "0.0.0.0")?)?;
+            Ok([ip[0], ip[1], ip[2], ip[3]])
+        };
 
-        // ARP header
-        let hw_type = self.get_field_multi(&["hwType", "hardware_type"], "1")?;
-        packet.extend_from_slice(&self.parse_u16_value(&hw_type, 1)?);
+        // 选项按消息类型、服务器标识、请求地址、租期、网络参数、主机名、参数列表的常见顺序排列
+        let mut options = vec![(dhcp::OPTION_MESSAGE_TYPE, vec![type_code])];
+        for (key, code) in [
+            ("dhcp_server_id", dhcp::OPTION_SERVER_ID),
+            ("dhcp_requested_ip", dhcp::OPTION_REQUESTED_IP),
+        ] {
+            let value = self.get_field(key, "")?;
+            if !value.is_empty() {
+                options.push((code, self.parse_ip(&value)?));
+            }
+        }
+        if !self.get_field("dhcp_lease_time", "")?.is_empty() {
+            options.push((dhcp::OPTION_LEASE_TIME, self.get_dec::<u32>("dhcp_lease_time", 0)?.to_be_bytes().to_vec()));
+        }
+        let mask = self.get_field("dhcp_subnet_mask", "")?;
+        if !mask.is_empty() {
+            options.push((dhcp::OPTION_SUBNET_MASK, self.parse_ip(&mask)?));
+        }
+        for (key, code) in [("dhcp_router", dhcp::OPTION_ROUTER), ("dhcp_dns", dhcp::OPTION_DNS_SERVERS)] {
+            let value = self.get_field(key, "")?;
+            let list = packet_validation::parse_ipv4_list(&value).ok_or_else(|| anyhow!("字段 {} 的值无效: {}", key, value))?;
+            if !list.is_empty() {
+                options.push((code, list.into_iter().flat_map(|(ip, _)| ip).collect()));
+            }
+        }
+        let hostname = self.get_field("dhcp_hostname", "")?;
+        if !hostname.is_empty() {
+            options.push((dhcp::OPTION_HOSTNAME, hostname.trim().as_bytes().to_vec()));
+        }
+        if op == 1 {
+            let params = self.get_field("dhcp_param_list", "1,3,6,15")?;
+            let params = packet_validation::parse_byte_list(&params)
+                .ok_or_else(|| anyhow!("字段 dhcp_param_list 的值无效: {}", params))?;
+            if !params.is_empty() {
+                options.push((dhcp::OPTION_PARAMETER_LIST, params));
+            }
+        }
+        options.extend(dhcp::parse_raw_options(&self.get_field("dhcp_options", "")?)?);
+
+        let message = dhcp::DhcpMessage {
+            op,
+            hops: self.get_dec("dhcp_hops", 0)?,
+            xid: packet_validation::parse_hex_number(&xid)
+                .and_then(|n| u32::try_from(n).ok())
+                .ok_or_else(|| anyhow!("字段 dhcp_xid 的值无效: {}", xid))?,
+            secs: self.get_dec("dhcp_secs", 0)?,
+            broadcast: self.get_dec::<u8>("dhcp_broadcast", 1)? == 1,
+            ciaddr: addr("dhcp_ciaddr")?,
+            yiaddr: addr("dhcp_yiaddr")?,
+            siaddr: addr("dhcp_siaddr")?,
+            giaddr: addr("dhcp_giaddr")?,
+            chaddr: self.parse_mac(&chaddr)?.try_into().map_err(|_| anyhow!("无效的客户端 MAC: {}", chaddr))?,
+            options,
+        };
+        dhcp::encode(&message)
+    }
 
-        let proto_type = self.get_field_multi(&["protoType", "protocol_type"], "0x0800")?;
-        packet.extend_from_slice(&self.parse_u16_value(&proto_type, 0x0800)?);
+    fn build_ntp_payload(&self) -> Result<Vec<u8>> {
+        let reference_id = self.get_hex_bytes(&["ntp_reference_id"], "00000000", 4)?;
+        let packet = ntp::NtpPacket {
+            leap: self.get_dec("ntp_leap", 0)?,
+            version: self.get_dec("ntp_version", 4)?,
+            mode: self.get_dec("ntp_mode", 3)?,
+            stratum: self.get_dec("ntp_stratum", 0)?,
+            poll: self.get_dec::<u8>("ntp_poll", 0)? as i8,
+            precision: self.get_dec::<u8>("ntp_precision", 0)? as i8,
+            root_delay: self.get_dec("ntp_root_delay", 0)?,
+            root_dispersion: self.get_dec("ntp_root_dispersion", 0)?,
+            reference_id: [reference_id[0], reference_id[1], reference_id[2], reference_id[3]],
+            reference_ts: self.get_dec("ntp_reference_ts", 0)?,
+            origin_ts: self.get_dec("ntp_origin_ts", 0)?,
+            receive_ts: self.get_dec("ntp_receive_ts", 0)?,
+            transmit_ts: self.get_dec("ntp_transmit_ts", ntp::now_timestamp())?,
+        };
+        Ok(ntp::encode(&packet))
+    }
+
+    fn build_arp_packet(&self) -> Result<Vec<u8>> {
+        let mut packet = Vec::new();
 
-        let hw_size = self.get_field_multi(&["hwSize", "hw_len", "hardware_size"], "6")?;
-        packet.push(self.parse_u8_value(&hw_size, 6)?);
+        // 键名、别名和默认值均取自 ARP 字段定义
+        // Ethernet header：未填写时取 ARP 报文中的对应地址
+        let dst_mac = self.schema_value("dst_mac")
+            .or_else(|| self.schema_value("dstMac"))
+            .unwrap_or_else(|| self.schema_field("dst_mac"));
+        packet.extend_from_slice(&self.parse_mac(&dst_mac)?);
 
-        let proto_size = self.get_field_multi(&["protoSize", "proto_len", "protocol_size"], "4")?;
-        packet.push(self.parse_u8_value(&proto_size, 4)?);
+        let src_mac = self.schema_value("src_mac")
+            .or_else(|| self.schema_value("srcMac"))
+            .unwrap_or_else(|| self.schema_field("src_mac"));
+        packet.extend_from_slice(&self.parse_mac(&src_mac)?);
 
-        let opcode = self.get_field_multi(&["opcode", "op", "operation"], "1")?;
-        packet.extend_from_slice(&self.parse_u16_value(&opcode, 1)?);
+        packet.extend_from_slice(&self.get_hex_bytes(&self.schema_keys("ether_type"), &self.schema_field("ether_type"), 2)?);
 
-        let sender_mac = self.get_field_multi(&["srcMac", "sender_mac", "senderMac"], &src_mac)?;
+        // ARP header
+        packet.extend_from_slice(&self.parse_u16_value(&self.schema_field("hwType"), 1)?);
+        packet.extend_from_slice(&self.parse_u16_value(&self.schema_field("protoType"), 0x0800)?);
+        packet.push(self.parse_u8_value(&self.schema_field("hwSize"), 6)?);
+        packet.push(self.parse_u8_value(&self.schema_field("protoSize"), 4)?);
+        packet.extend_from_slice(&self.parse_u16_value(&self.schema_field("opcode"), 1)?);
+
+        // 发送方和目标 MAC 未填写时与以太网地址相同
+        let sender_mac = self.schema_value("srcMac").unwrap_or_else(|| src_mac.clone());
         packet.extend_from_slice(&self.parse_mac(&sender_mac)?);
+        packet.extend_from_slice(&self.parse_ip(&self.schema_field("srcIp"))?);
 
-        let sender_ip = self.get_field_multi(&["srcIp", "sender_ip", "senderIp"], "0.0.0.0")?;
-        packet.extend_from_slice(&self.parse_ip(&sender_ip)?);
-
-        let target_mac = self.get_field_multi(&["dstMac", "target_mac", "targetMac"], &dst_mac)?;
+        let target_mac = self.schema_value("dstMac").unwrap_or_else(|| dst_mac.clone());
         packet.extend_from_slice(&self.parse_mac(&target_mac)?);
-
-        let target_ip = self.get_field_multi(&["dstIp", "target_ip", "targetIp"], "0.0.0.0")?;
-        packet.extend_from_slice(&self.parse_ip(&target_ip)?);
+        packet.extend_from_slice(&self.parse_ip(&self.schema_field("dstIp"))?);
 
         // Optional ARP payload
         if let Some(payload) = &self.data.payload {
@@ -485,26 +823,37 @@ impl PacketBuilder {
     }
 
     fn build_icmp_packet(&self) -> Result<Vec<u8>> {
-        // 先构建 IP 头部
-        let mut packet = self.build_ip_packet()?;
+        let mut packet = Vec::new();
         
-        // ICMP 头部 (8 bytes)
-        let icmp_type = self.get_field("icmp_type", "08")?; // Echo Request
-        packet.push(u8::from_str_radix(&icmp_type, 16).unwrap_or(8));
+        // 以太网头部 (14 bytes)
+        let dst_mac = self.get_field("dst_mac", "00:00:00:00:00:00")?;
+        packet.extend_from_slice(&self.parse_mac(&dst_mac)?);
         
-        let icmp_code = self.get_field("icmp_code", "00")?;
-        packet.push(u8::from_str_radix(&icmp_code, 16).unwrap_or(0));
+        let src_mac = self.get_field("src_mac", "00:00:00:00:00:00")?;
+        packet.extend_from_slice(&self.parse_mac(&src_mac)?);
         
-        // 校验和 (2 bytes) - 稍后计算
+        packet.extend_from_slice(&self.get_hex_bytes(&["ether_type"], "0800", 2)?);
+        
+        // IPv4头部，沿用十六进制的 id/protocol 字段
+        let src_ip = self.parse_ip(&self.get_field_multi(&["srcIp", "src_ip"], "192.168.1.1")?)?;
+        let dst_ip = self.parse_ip(&self.get_field_multi(&["dstIp", "dst_ip"], "192.168.1.2")?)?;
+        let id = self.get_hex_bytes(&["id"], "0000", 2)?;
+        let protocol = self.get_hex_u8("protocol", 0x01)?;
+        let header = self.build_ipv4_header(
+            Ipv4Defaults { protocol, identification: u16::from_be_bytes([id[0], id[1]]), dont_fragment: false },
+            &src_ip,
+            &dst_ip,
+        )?;
+        let ip_header_len = header.len();
+        packet.extend_from_slice(&header);
+        
+        // ICMP 头部：类型、代码、校验和（稍后计算），其后按类型排布
+        let icmp_start = packet.len();
+        let icmp_type = self.get_hex_u8("icmp_type", 0x08)?; // Echo Request
+        packet.push(icmp_type);
+        packet.push(self.get_hex_u8("icmp_code", 0x00)?);
         packet.extend_from_slice(&[0x00, 0x00]);
-        
-        // 标识符 (2 bytes)
-        let identifier = self.get_field("identifier", "0000")?;
-        packet.extend_from_slice(&self.parse_hex(&identifier)?);
-        
-        // 序列号 (2 bytes)
-        let sequence = self.get_field("sequence", "0000")?;
-        packet.extend_from_slice(&self.parse_hex(&sequence)?);
+        packet.extend_from_slice(&self.build_icmp_body(icmp_type, &src_ip, &dst_ip)?);
         
         // Payload
         if let Some(payload) = &self.data.payload {
@@ -512,16 +861,109 @@ impl PacketBuilder {
         }
         
         // 计算 ICMP 校验和
-        let icmp_checksum = self.calculate_icmp_checksum(&packet[20..]);
-        packet[22] = (icmp_checksum >> 8) as u8;
-        packet[23] = icmp_checksum as u8;
+        let icmp_checksum = self.calculate_icmp_checksum(&packet[icmp_start..]);
+        packet[icmp_start + 2..icmp_start + 4].copy_from_slice(&icmp_checksum.to_be_bytes());
+        
+        self.finish_ipv4_header(&mut packet[14..], ip_header_len)?;
         
         Ok(packet)
     }
 
-    // 辅助方法
+    // ICMP 校验和之后的部分：4 字节的其余头部及各类型特有的数据
+    fn build_icmp_body(&self, icmp_type: u8, src_ip: &[u8], dst_ip: &[u8]) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        match icmp_type {
+            // 目的不可达：未用(2) + 下一跳 MTU(2) + 原始数据报
+            3 => {
+                body.extend_from_slice(&[0x00, 0x00]);
+                body.extend_from_slice(&self.get_dec::<u16>("icmp_mtu", 0)?.to_be_bytes());
+                body.extend_from_slice(&self.build_icmp_original(src_ip, dst_ip)?);
+            }
+            // 源抑制、超时：未用(4) + 原始数据报
+            4 | 11 => {
+                body.extend_from_slice(&[0x00; 4]);
+                body.extend_from_slice(&self.build_icmp_original(src_ip, dst_ip)?);
+            }
+            // 重定向：网关地址(4) + 原始数据报
+            5 => {
+                body.extend_from_slice(&self.parse_ip(&self.get_field("icmp_gateway", "0.0.0.0")?)?);
+                body.extend_from_slice(&self.build_icmp_original(src_ip, dst_ip)?);
+            }
+            // 参数问题：指针(1) + 未用(3) + 原始数据报
+            12 => {
+                body.push(self.get_dec("icmp_pointer", 0)?);
+                body.extend_from_slice(&[0x00; 3]);
+                body.extend_from_slice(&self.build_icmp_original(src_ip, dst_ip)?);
+            }
+            // 路由器通告：地址数(1) + 地址项大小(1，单位 4 字节) + 生存期(2) + 地址/优先级列表
+            9 => {
+                let value = self.get_field("icmp_routers", "")?;
+                let routers = packet_validation::parse_ipv4_list(&value)
+                    .ok_or_else(|| anyhow!("字段 icmp_routers 的值无效: {}", value))?;
+                let count = u8::try_from(routers.len()).map_err(|_| anyhow!("路由器通告最多 255 个地址"))?;
+                body.extend_from_slice(&[count, 2]);
+                body.extend_from_slice(&self.get_dec::<u16>("icmp_lifetime", 1800)?.to_be_bytes());
+                for (addr, preference) in routers {
+                    body.extend_from_slice(&addr);
+                    body.extend_from_slice(&preference.to_be_bytes());
+                }
+            }
+            // 路由器请求：保留(4)
+            10 => body.extend_from_slice(&[0x00; 4]),
+            // 时间戳请求/应答：标识符 + 序列号 + 发起/接收/发送时间戳
+            13 | 14 => {
+                body.extend_from_slice(&self.get_hex_bytes(&["identifier"], "0000", 2)?);
+                body.extend_from_slice(&self.get_hex_bytes(&["sequence"], "0000", 2)?);
+                for key in ["icmp_originate", "icmp_receive", "icmp_transmit"] {
+                    body.extend_from_slice(&self.get_dec::<u32>(key, 0)?.to_be_bytes());
+                }
+            }
+            // 回显及其他类型：标识符 + 序列号
+            _ => {
+                body.extend_from_slice(&self.get_hex_bytes(&["identifier"], "0000", 2)?);
+                body.extend_from_slice(&self.get_hex_bytes(&["sequence"], "0000", 2)?);
+            }
+        }
+        Ok(body)
+    }
+
+    // 差错报文携带的原始数据报：IP 头部 + 传输层前 8 字节。
+    // 原始数据报由本报文的目的主机发出，目的地址默认为本报文的源地址
+    fn build_icmp_original(&self, src_ip: &[u8], dst_ip: &[u8]) -> Result<Vec<u8>> {
+        let raw = self.get_field("icmp_original", "")?;
+        if !raw.is_empty() {
+            return packet_validation::parse_hex_data(&raw)
+                .ok_or_else(|| anyhow!("字段 icmp_original 的值无效: {}", raw));
+        }
+
+        let orig_dst = match self.get_field("orig_dst_ip", "")? {
+            v if v.is_empty() => src_ip.to_vec(),
+            v => self.parse_ip(&v)?,
+        };
+        let protocol: u8 = self.get_dec("orig_protocol", 17)?;
+
+        let mut original = vec![0x45, 0x00, 0x00, 28, 0x00, 0x00, 0x40, 0x00, 1, protocol, 0x00, 0x00];
+        original.extend_from_slice(dst_ip);
+        original.extend_from_slice(&orig_dst);
+        let checksum = self.calculate_ip_checksum(&original);
+        original[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+        original.extend_from_slice(&self.get_dec::<u16>("orig_src_port", 12345)?.to_be_bytes());
+        original.extend_from_slice(&self.get_dec::<u16>("orig_dst_port", 33434)?.to_be_bytes());
+        if protocol == 17 {
+            // UDP 长度 8，校验和 0
+            original.extend_from_slice(&[0x00, 0x08, 0x00, 0x00]);
+        } else {
+            // TCP 序列号等其余字段填 0
+            original.extend_from_slice(&[0x00; 4]);
+        }
+        Ok(original)
+    }
+
+    // 辅助方法（空值视为未填写，使用默认值）
     fn get_field(&self, key: &str, default: &str) -> Result<String> {
         Ok(self.data.fields.get(key)
+            .filter(|s| !s.trim().is_empty())
             .map(|s| s.to_string())
             .unwrap_or_else(|| default.to_string()))
     }
@@ -529,12 +971,69 @@ impl PacketBuilder {
     fn get_field_multi(&self, keys: &[&str], default: &str) -> Result<String> {
         for key in keys {
             if let Some(v) = self.data.fields.get(*key) {
-                return Ok(v.clone());
+                if !v.trim().is_empty() {
+                    return Ok(v.clone());
+                }
             }
         }
         Ok(default.to_string())
     }
 
+    // 当前协议字段定义中的主键名及别名，未定义时只有 name 本身
+    fn schema_keys(&self, name: &'static str) -> Vec<&'static str> {
+        packet_schema::field_spec(&self.data.protocol, name)
+            .map(|spec| spec.keys().collect())
+            .unwrap_or_else(|| vec![name])
+    }
+
+    // 依次查找字段的主键名和别名，均未填写时返回 None
+    fn schema_value(&self, name: &'static str) -> Option<String> {
+        self.schema_keys(name)
+            .into_iter()
+            .find_map(|key| self.data.fields.get(key).filter(|v| !v.trim().is_empty()).cloned())
+    }
+
+    // 未填写时使用字段定义中的默认值
+    fn schema_field(&self, name: &'static str) -> String {
+        self.schema_value(name).unwrap_or_else(|| {
+            packet_schema::field_spec(&self.data.protocol, name).map(|spec| spec.default).unwrap_or_default().to_string()
+        })
+    }
+
+    // 十进制字段，超出目标类型范围时报错
+    fn get_dec<T: TryFrom<u64>>(&self, key: &str, default: T) -> Result<T> {
+        let value = match self.data.fields.get(key).map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            Some(v) => v,
+            None => return Ok(default),
+        };
+        packet_validation::parse_dec_value(value)
+            .and_then(|n| T::try_from(n).ok())
+            .ok_or_else(|| anyhow!("字段 {} 的值无效: {}", key, value))
+    }
+
+    fn get_hex_u8(&self, key: &str, default: u8) -> Result<u8> {
+        let value = self.get_field(key, "")?;
+        if value.is_empty() {
+            return Ok(default);
+        }
+        packet_validation::parse_hex_number(&value)
+            .and_then(|n| u8::try_from(n).ok())
+            .ok_or_else(|| anyhow!("字段 {} 的值无效: {}", key, value))
+    }
+
+    // 固定宽度的十六进制字段，不足时左侧补零
+    fn get_hex_bytes(&self, keys: &[&str], default: &str, bytes: usize) -> Result<Vec<u8>> {
+        let value = self.get_field_multi(keys, default)?;
+        packet_validation::parse_hex_bytes_value(&value, bytes)
+            .ok_or_else(|| anyhow!("字段 {} 的值无效: {}", keys[0], value))
+    }
+
+    fn get_checksum(&self, key: &str) -> Result<u16> {
+        let value = self.get_field(key, "0")?;
+        packet_validation::parse_checksum_value(&value)
+            .ok_or_else(|| anyhow!("字段 {} 的值无效: {}", key, value))
+    }
+
     fn parse_u16_value(&self, value: &str, default: u16) -> Result<[u8; 2]> {
         let trimmed = value.trim();
         if trimmed.is_empty() {
@@ -624,6 +1123,40 @@ impl PacketBuilder {
         Ok(result)
     }
 
+    // 解析 base64（兼容标准与 URL 安全字母表，忽略空白与填充）
+    fn parse_base64(&self, input: &str) -> Result<Vec<u8>> {
+        let mut result = Vec::with_capacity(input.len() * 3 / 4);
+        let mut buffer = 0u32;
+        let mut bits = 0u32;
+
+        for c in input.chars() {
+            let value = match c {
+                'A'..='Z' => c as u32 - 'A' as u32,
+                'a'..='z' => c as u32 - 'a' as u32 + 26,
+                '0'..='9' => c as u32 - '0' as u32 + 52,
+                '+' | '-' => 62,
+                '/' | '_' => 63,
+                '=' => break,
+                c if c.is_whitespace() => continue,
+                _ => return Err(anyhow!("无效的 base64 字符: {}", c)),
+            };
+
+            buffer = (buffer << 6) | value;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                result.push((buffer >> bits) as u8);
+                buffer &= (1 << bits) - 1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn parse_bool_flag(&self, value: &str) -> bool {
+        matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+    }
+
     fn calculate_ip_checksum(&self, data: &[u8]) -> u16 {
         let mut sum = 0u32;
         for i in (0..data.len()).step_by(2) {
@@ -641,24 +1174,29 @@ impl PacketBuilder {
         !sum as u16
     }
 
-    #[allow(dead_code)]
-    fn calculate_tcp_checksum(&self, packet: &[u8]) -> u16 {
-        // 简化的 TCP 校验和计算
+    // TCP/UDP 校验和：覆盖 IPv4 伪首部 + 传输层头部与数据
+    fn calculate_l4_checksum(&self, src_ip: &[u8], dst_ip: &[u8], protocol: u8, segment: &[u8]) -> u16 {
         let mut sum = 0u32;
-        let tcp_start = 20;
-        
-        for i in (tcp_start..packet.len()).step_by(2) {
-            if i + 1 < packet.len() {
-                sum += ((packet[i] as u32) << 8) + packet[i + 1] as u32;
+
+        // 伪首部：源IP、目标IP、协议号、传输层长度
+        for pair in src_ip.chunks(2).chain(dst_ip.chunks(2)) {
+            sum += ((pair[0] as u32) << 8) + pair[1] as u32;
+        }
+        sum += protocol as u32;
+        sum += segment.len() as u32;
+
+        for i in (0..segment.len()).step_by(2) {
+            if i + 1 < segment.len() {
+                sum += ((segment[i] as u32) << 8) + segment[i + 1] as u32;
             } else {
-                sum += (packet[i] as u32) << 8;
+                sum += (segment[i] as u32) << 8;
             }
         }
-        
+
         while sum >> 16 != 0 {
             sum = (sum & 0xFFFF) + (sum >> 16);
         }
-        
+
         !sum as u16
     }
 
@@ -680,6 +1218,40 @@ impl PacketBuilder {
     }
 }
 
+// IPv4 数据报在帧中的结束位置：不足 60 字节而补齐的最小帧中，
+// IPv4 总长度之后的字节是以太网填充；其余情况返回帧长
+pub(crate) fn ipv4_frame_end(frame: &[u8]) -> usize {
+    if frame.len() > 60 {
+        return frame.len();
+    }
+    let mut offset = 12;
+    while frame.len() >= offset + 6 && matches!(frame[offset..offset + 2], [0x81, 0x00] | [0x88, 0xa8]) {
+        offset += 4;
+    }
+    let ip_start = offset + 2;
+    if frame.get(offset..ip_start) != Some(&[0x08, 0x00][..]) || frame.len() < ip_start + 20 {
+        return frame.len();
+    }
+    let ihl = ((frame[ip_start] & 0x0f) as usize) * 4;
+    let total_len = u16::from_be_bytes([frame[ip_start + 2], frame[ip_start + 3]]) as usize;
+    if total_len < ihl.max(20) || ip_start + total_len >= frame.len() {
+        return frame.len();
+    }
+    ip_start + total_len
+}
+
+// 测试用：按字段构建指定源、目的地址的 IPv4 帧并补齐校验和，供各模块构造应答报文
+#[cfg(test)]
+pub(crate) fn test_frame(protocol: &str, src_ip: &str, dst_ip: &str, fields: Vec<(&str, &str)>) -> Vec<u8> {
+    let mut map: std::collections::HashMap<String, String> = fields.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    map.insert("srcIp".to_string(), src_ip.to_string());
+    map.insert("dstIp".to_string(), dst_ip.to_string());
+    let builder = PacketBuilder::new(PacketData { protocol: protocol.to_string(), fields: map, payload: None });
+    let mut frame = builder.build().unwrap();
+    builder.fixup_ipv4_frame(&mut frame).unwrap();
+    frame
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -871,6 +1443,20 @@ mod tests {
         assert!(packet.len() >= 60, "ARP frame must be >= 60 bytes, got {}", packet.len());
     }
 
+    #[test]
+    fn build_arp_resolves_schema_aliases() {
+        let primary = make_packet("arp", vec![
+            ("srcMac", "00:11:22:33:44:55"), ("srcIp", "10.0.0.1"), ("dstIp", "10.0.0.2"), ("opcode", "2"),
+        ], None).build().unwrap();
+        let aliased = make_packet("arp", vec![
+            ("sender_mac", "00:11:22:33:44:55"), ("senderIp", "10.0.0.1"), ("target_ip", "10.0.0.2"), ("operation", "2"),
+        ], None).build().unwrap();
+        assert_eq!(primary, aliased);
+        // 以太网源 MAC 未填写时取发送方 MAC
+        assert_eq!(&aliased[6..12], &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(&aliased[20..22], &[0x00, 0x02]);
+    }
+
     // ── IP checksum ───────────────────────────────────────────
 
     #[test]
@@ -909,4 +1495,497 @@ mod tests {
         }
         assert_eq!(sum as u16, 0xFFFF, "IP header checksum should validate to 0xFFFF");
     }
+
+    // ── build_raw_packet ──────────────────────────────────────
+
+    fn ones_complement_sum(data: &[u8]) -> u16 {
+        let mut sum = 0u32;
+        for i in (0..data.len()).step_by(2) {
+            let hi = (data[i] as u32) << 8;
+            let lo = if i + 1 < data.len() { data[i + 1] as u32 } else { 0 };
+            sum += hi + lo;
+        }
+        while sum >> 16 != 0 {
+            sum = (sum & 0xFFFF) + (sum >> 16);
+        }
+        sum as u16
+    }
+
+    #[test]
+    fn build_raw_sends_exact_bytes() {
+        let frame = "ffffffffffff001122334455080600010001";
+        let pb = make_packet("raw", vec![], Some(frame));
+        let packet = pb.build().unwrap();
+        assert_eq!(packet, pb.parse_hex(frame).unwrap());
+    }
+
+    #[test]
+    fn build_eth_alias_is_raw() {
+        let pb = make_packet("eth", vec![("dst_mac", "ff:ff:ff:ff:ff:ff")], Some("aabbccddeeff"));
+        assert_eq!(pb.build().unwrap(), vec![0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+    }
+
+    #[test]
+    fn build_raw_base64_prefix_and_field() {
+        let pb = make_packet("raw", vec![], Some("base64:qrvM3e7/"));
+        assert_eq!(pb.build().unwrap(), vec![0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+
+        let pb = make_packet("raw", vec![("payload_format", "base64")], Some("3q2+7w=="));
+        assert_eq!(pb.build().unwrap(), vec![0xDE, 0xAD, 0xBE, 0xEF]);
+    }
+
+    #[test]
+    fn build_raw_from_file() {
+        let path = std::env::temp_dir().join(format!("bitsender_raw_{}.bin", uuid::Uuid::new_v4()));
+        std::fs::write(&path, [0x01, 0x02, 0x03]).unwrap();
+        let pb = make_packet("raw", vec![], Some(&format!("file:{}", path.display())));
+        let packet = pb.build();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(packet.unwrap(), vec![0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn build_raw_empty_errors() {
+        assert!(make_packet("raw", vec![], None).build().is_err());
+        assert!(make_packet("raw", vec![("payload_format", "yaml")], Some("00")).build().is_err());
+    }
+
+    #[test]
+    fn build_raw_fixup_udp() {
+        // IPv4/UDP 帧，长度与校验和全部置零，并附加 2 字节数据
+        let frame = concat!(
+            "aabbccddeeff001122334455", "0800",
+            "45000000000040004011", "0000", "0a000001", "0a000002",
+            "00350035", "0000", "0000", "abcd"
+        );
+        let pb = make_packet("raw", vec![("fixup", "true")], Some(frame));
+        let packet = pb.build().unwrap();
+
+        // IPv4 总长度 = 20 + 8 + 2
+        assert_eq!(&packet[16..18], &[0x00, 30]);
+        assert_eq!(ones_complement_sum(&packet[14..34]), 0xFFFF);
+        // UDP 长度与伪首部校验和
+        assert_eq!(&packet[38..40], &[0x00, 10]);
+        let mut pseudo = Vec::new();
+        pseudo.extend_from_slice(&packet[26..34]);
+        pseudo.extend_from_slice(&[0x00, 17, 0x00, 10]);
+        pseudo.extend_from_slice(&packet[34..]);
+        assert_eq!(ones_complement_sum(&pseudo), 0xFFFF);
+    }
+
+    #[test]
+    fn build_raw_fixup_excludes_ethernet_padding() {
+        // 30 字节的 IPv4/UDP 数据报补齐到 60 字节
+        let frame = format!("{}{}", concat!(
+            "aabbccddeeff001122334455", "0800",
+            "4500001e000040004011", "0000", "0a000001", "0a000002",
+            "00350035", "0000", "0000", "abcd"
+        ), "00".repeat(16));
+        let packet = make_packet("raw", vec![("fixup", "true")], Some(&frame)).build().unwrap();
+        assert_eq!(packet.len(), 60);
+        assert_eq!(&packet[16..18], &[0x00, 30]);
+        assert_eq!(&packet[38..40], &[0x00, 10]);
+        assert_eq!(ipv4_frame_end(&packet), 44);
+
+        // 超过最小帧长的帧，IPv4 总长度之后的字节仍视为数据
+        let mut long = packet.clone();
+        long.resize(80, 0);
+        assert_eq!(ipv4_frame_end(&long), 80);
+    }
+
+    #[test]
+    fn build_raw_without_fixup_keeps_bytes() {
+        let frame = "aabbccddeeff0011223344550800450000000000400040110000";
+        let pb = make_packet("raw", vec![], Some(frame));
+        assert_eq!(pb.build().unwrap(), pb.parse_hex(frame).unwrap());
+    }
+
+    // ── validate ──────────────────────────────────────────────
+
+    #[test]
+    fn build_rejects_invalid_fields_instead_of_defaulting() {
+        let pb = make_packet("tcp", vec![("dstPort", "8O"), ("srcPort", "99999")], None);
+        let errors = pb.validate();
+        assert_eq!(errors.len(), 2);
+        let err = pb.build().unwrap_err().to_string();
+        assert!(err.contains("dstPort") && err.contains("srcPort"));
+    }
+
+    #[test]
+    fn build_treats_empty_fields_as_default() {
+        let pb = make_packet("udp", vec![("srcPort", ""), ("dst_mac", ""), ("ether_type", " ")], None);
+        let packet = pb.build().unwrap();
+        assert_eq!(&packet[0..6], &[0u8; 6]);
+        assert_eq!(&packet[12..14], &[0x08, 0x00]);
+        assert_eq!(&packet[34..36], &12345u16.to_be_bytes());
+    }
+
+    #[test]
+    fn build_pads_short_ether_type() {
+        let pb = make_packet("ethernet", vec![("ether_type", "800")], None);
+        assert_eq!(&pb.build().unwrap()[12..14], &[0x08, 0x00]);
+    }
+
+    #[test]
+    fn validate_reports_bad_payload() {
+        let errors = make_packet("udp", vec![], Some("zz")).validate();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "payload");
+
+        let errors = make_packet("raw", vec![("payload_format", "yaml")], Some("00")).validate();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "payload_format");
+    }
+
+    #[test]
+    fn icmp_type_is_parsed_strictly() {
+        assert!(make_packet("icmp", vec![("icmp_type", "0g")], None).build().is_err());
+        let packet = make_packet("icmp", vec![("icmp_type", "0")], None).build().unwrap();
+        assert_eq!(packet[34], 0);
+    }
+
+    // ── ipv4 header ──────────────────────────────────────────
+
+    #[test]
+    fn tcp_honours_ipv4_header_fields() {
+        let packet = make_packet("tcp", vec![
+            ("dscp", "46"), ("ecn", "1"), ("identification", "4660"), ("df", "0"), ("mf", "1"),
+            ("fragment_offset", "185"), ("ttl", "3"), ("ip_protocol", "253"),
+        ], None).build().unwrap();
+        let ip = &packet[14..34];
+        assert_eq!(ip[1], (46 << 2) | 1);
+        assert_eq!(&ip[4..6], &[0x12, 0x34]);
+        assert_eq!(&ip[6..8], &(0x2000u16 | 185).to_be_bytes());
+        assert_eq!((ip[8], ip[9]), (3, 253));
+        assert_eq!(ones_complement_sum(ip), 0xFFFF);
+    }
+
+    #[test]
+    fn tos_and_flags_override_their_parts() {
+        let packet = make_packet("udp", vec![("dscp", "10"), ("tos", "255"), ("df", "1"), ("flags", "4")], None)
+            .build()
+            .unwrap();
+        assert_eq!(packet[15], 0xFF);
+        assert_eq!(packet[20], 0x80);
+    }
+
+    #[test]
+    fn ip_options_extend_header_and_ihl() {
+        let packet = make_packet("udp", vec![("ip_option_rr", "2"), ("ip_options", "01")], Some("AABB"))
+            .build()
+            .unwrap();
+        // 记录路由 3+8 字节，加 1 字节 NOP，补齐到 12 字节
+        assert_eq!(packet[14], 0x48);
+        let header = &packet[14..46];
+        assert_eq!(&header[20..24], &[0x07, 11, 4, 0]);
+        assert_eq!(&header[31..32], &[0x01]);
+        assert_eq!(ones_complement_sum(header), 0xFFFF);
+        assert_eq!(u16::from_be_bytes([packet[16], packet[17]]), 32 + 8 + 2);
+        assert_eq!(u16::from_be_bytes([packet[50], packet[51]]), 8 + 2);
+    }
+
+    #[test]
+    fn ip_options_over_40_bytes_are_rejected() {
+        let builder = make_packet("tcp", vec![("ip_option_rr", "9"), ("ip_option_ts", "1")], None);
+        assert!(builder.build().is_err());
+        assert!(builder.validate().iter().any(|e| e.field == "ip_options"));
+        assert!(make_packet("tcp", vec![("ip_option_ts", "9")], None).build().is_ok());
+    }
+
+    #[test]
+    fn ip_checksum_modes() {
+        let fixed = make_packet("ipv4", vec![("ip_checksum_mode", "fixed"), ("ip_checksum", "0xBEEF")], None)
+            .build()
+            .unwrap();
+        assert_eq!(&fixed[24..26], &[0xBE, 0xEF]);
+
+        let corrupt = make_packet("ipv4", vec![("ip_checksum_mode", "corrupt")], None).build().unwrap();
+        assert_ne!(ones_complement_sum(&corrupt[14..34]), 0xFFFF);
+        assert!(make_packet("ipv4", vec![("ip_checksum_mode", "wrong")], None).build().is_err());
+    }
+
+    #[test]
+    fn icmp_total_length_covers_icmp_header_once() {
+        let packet = make_packet("icmp", vec![], Some("DEADBEEF")).build().unwrap();
+        assert_eq!(packet.len(), 14 + 20 + 8 + 4);
+        assert_eq!(u16::from_be_bytes([packet[16], packet[17]]), 32);
+        assert_eq!(ones_complement_sum(&packet[14..34]), 0xFFFF);
+        assert_eq!(ones_complement_sum(&packet[34..]), 0xFFFF);
+    }
+
+    // ── tcp options ──────────────────────────────────────────
+
+    #[test]
+    fn tcp_options_set_data_offset() {
+        let packet = make_packet("tcp", vec![("tcp_options", "mss:1460,nop,ws:7,sack_perm")], Some("AA"))
+            .build()
+            .unwrap();
+        let tcp = &packet[34..];
+        assert_eq!(tcp[12] >> 4, 8);
+        assert_eq!(&tcp[20..32], &[2, 4, 0x05, 0xB4, 1, 3, 3, 7, 4, 2, 0, 0]);
+        assert_eq!(tcp[32], 0xAA);
+        assert_eq!(
+            tcp_options::decode_options(&tcp[20..24]),
+            vec![tcp_options::TcpOption::Mss { value: 1460 }]
+        );
+        assert_eq!(u16::from_be_bytes([packet[16], packet[17]]), 20 + 32 + 1);
+    }
+
+    #[test]
+    fn invalid_tcp_options_are_field_errors() {
+        let builder = make_packet("tcp", vec![("tcp_options", "mss:1460,bogus")], None);
+        assert_eq!(builder.validate()[0].field, "tcp_options");
+        assert!(builder.build().is_err());
+        // 显式填写的数据偏移仍然生效
+        let packet = make_packet("tcp", vec![("data_offset", "6")], None).build().unwrap();
+        assert_eq!(packet[46] >> 4, 6);
+    }
+
+    // ── icmp ─────────────────────────────────────────────────
+
+    #[test]
+    fn icmp_ip_protocol_defaults_to_icmp() {
+        // 未填写 protocol 时 IP 协议号为 1（ICMP），此前默认为 6
+        let packet = make_packet("icmp", vec![], None).build().unwrap();
+        assert_eq!(packet[23], 0x01);
+        assert_eq!(PacketBuilder::schema("icmp").unwrap().fields.iter().find(|f| f.name == "protocol").unwrap().default, "01");
+        // 显式填写时仍按填写的十六进制值
+        let packet = make_packet("icmp", vec![("protocol", "06")], None).build().unwrap();
+        assert_eq!(packet[23], 0x06);
+    }
+
+    #[test]
+    fn icmp_is_ethernet_framed_and_accepts_both_address_keys() {
+        let packet = make_packet("icmp", vec![
+            ("dst_mac", "aa:bb:cc:dd:ee:ff"), ("dstIp", "10.0.0.9"), ("src_ip", "10.0.0.1"),
+        ], None).build().unwrap();
+        assert_eq!(&packet[0..6], &[0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+        assert_eq!(&packet[12..14], &[0x08, 0x00]);
+        assert_eq!(packet[23], 1);
+        assert_eq!(&packet[26..34], &[10, 0, 0, 1, 10, 0, 0, 9]);
+    }
+
+    #[test]
+    fn icmp_unreachable_embeds_original_datagram() {
+        let packet = make_packet("icmp", vec![
+            ("icmp_type", "03"), ("icmp_code", "04"), ("icmp_mtu", "1400"),
+            ("srcIp", "10.0.0.1"), ("dstIp", "10.0.0.9"), ("orig_dst_port", "53"),
+        ], None).build().unwrap();
+        let icmp = &packet[34..];
+        assert_eq!(icmp.len(), 8 + 28);
+        assert_eq!(&icmp[6..8], &1400u16.to_be_bytes());
+        let original = &icmp[8..];
+        assert_eq!(ones_complement_sum(&original[..20]), 0xFFFF);
+        assert_eq!(&original[12..20], &[10, 0, 0, 9, 10, 0, 0, 1]);
+        assert_eq!(&original[22..24], &53u16.to_be_bytes());
+        assert_eq!(ones_complement_sum(icmp), 0xFFFF);
+
+        let custom = make_packet("icmp", vec![("icmp_type", "0b"), ("icmp_original", "45000014")], None).build().unwrap();
+        assert_eq!(&custom[42..], &[0x45, 0x00, 0x00, 0x14]);
+    }
+
+    #[test]
+    fn icmp_redirect_timestamp_and_router_messages() {
+        let redirect = make_packet("icmp", vec![("icmp_type", "05"), ("icmp_gateway", "10.0.0.254")], None).build().unwrap();
+        assert_eq!(&redirect[38..42], &[10, 0, 0, 254]);
+
+        let timestamp = make_packet("icmp", vec![("icmp_type", "0d"), ("icmp_originate", "1000")], None).build().unwrap();
+        assert_eq!(timestamp.len(), 34 + 20);
+        assert_eq!(&timestamp[42..46], &1000u32.to_be_bytes());
+
+        let advert = make_packet("icmp", vec![("icmp_type", "09"), ("icmp_routers", "10.0.0.1, 10.0.0.2:-1")], None)
+            .build()
+            .unwrap();
+        assert_eq!(&advert[38..42], &[2, 2, 0x07, 0x08]);
+        assert_eq!(&advert[50..54], &[10, 0, 0, 2]);
+        assert_eq!(&advert[54..58], &(-1i32).to_be_bytes());
+        assert_eq!(ones_complement_sum(&advert[34..]), 0xFFFF);
+
+        let solicit = make_packet("icmp", vec![("icmp_type", "0a")], None).build().unwrap();
+        assert_eq!(solicit.len(), 34 + 8);
+    }
+
+    // ── fragmentation ────────────────────────────────────────
+
+    #[test]
+    fn build_frames_fragments_ipv4_based_packets() {
+        let payload = "00".repeat(40);
+        let frames = make_packet("udp", vec![("frag_size", "16")], Some(&payload)).build_frames().unwrap();
+        // UDP 头 8 字节 + 40 字节数据 = 48 字节，分为 3 片
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|f| ones_complement_sum(&f[14..34]) == 0xFFFF));
+        assert_eq!(frames[0][20] & 0x40, 0);
+
+        let icmp = make_packet("icmp", vec![("frag_size", "8"), ("frag_order", "reverse")], None).build_frames().unwrap();
+        assert_eq!(icmp.len(), 1);
+        assert_eq!(make_packet("udp", vec![], None).build_frames().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn fragment_order_follows_seed() {
+        let payload = "00".repeat(64);
+        let builder = make_packet("udp", vec![("frag_size", "8"), ("frag_order", "random")], Some(&payload));
+        let packet = builder.build().unwrap();
+        let offsets = |seed| -> Vec<u8> {
+            builder.fragment_frames(packet.clone(), seed).unwrap().iter().map(|f| f[21]).collect()
+        };
+        assert_eq!(offsets(7), offsets(7));
+        assert!((1..20).any(|seed| offsets(seed) != offsets(7)));
+    }
+
+    #[test]
+    fn fragments_keep_checksum_mode() {
+        let frames = make_packet("tcp", vec![("frag_size", "8"), ("ip_checksum_mode", "corrupt")], None)
+            .build_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|f| ones_complement_sum(&f[14..34]) != 0xFFFF));
+    }
+
+    #[test]
+    fn unaligned_fragment_size_is_a_field_error() {
+        let builder = make_packet("tcp", vec![("frag_size", "10")], None);
+        assert_eq!(builder.validate()[0].field, "frag_size");
+        assert!(builder.build_frames().is_err());
+    }
+
+    #[test]
+    fn overlap_beyond_first_fragment_is_a_field_error() {
+        let payload = "00".repeat(64);
+        let builder = make_packet("udp", vec![
+            ("frag_size", "24"), ("frag_first_size", "8"), ("frag_overlap", "16"),
+        ], Some(&payload));
+        assert_eq!(builder.validate()[0].field, "frag_overlap");
+        assert!(builder.build_frames().is_err());
+    }
+
+    // ── application payloads ─────────────────────────────────
+
+    #[test]
+    fn build_dns_query_and_response() {
+        let query = make_packet("dns", vec![("dns_id", "4660"), ("dns_questions", "example.com AAAA")], None).build().unwrap();
+        let dns = &query[42..];
+        assert_eq!(&query[36..38], &53u16.to_be_bytes());
+        assert_eq!(&dns[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(dns::decode_message(dns).unwrap().questions[0].qtype, 28);
+
+        let response = make_packet(
+            "dns",
+            vec![
+                ("srcPort", "53"),
+                ("dns_qr", "1"),
+                ("dns_ra", "1"),
+                ("dns_answers", "example.com A 60 10.0.0.1; example.com A 60 10.0.0.2"),
+            ],
+            None,
+        )
+        .build()
+        .unwrap();
+        let message = dns::decode_message(&response[42..]).unwrap();
+        assert_eq!(message.flags, 0x8180);
+        assert_eq!(message.answers.len(), 2);
+        assert_eq!(u16::from_be_bytes([response[38], response[39]]) as usize, response.len() - 34);
+    }
+
+    #[test]
+    fn build_dhcp_discover_defaults_to_broadcast() {
+        let packet = make_packet("dhcp", vec![("src_mac", "00:11:22:33:44:55"), ("dhcp_xid", "deadbeef")], None)
+            .build()
+            .unwrap();
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert_eq!(&packet[26..34], &[0, 0, 0, 0, 255, 255, 255, 255]);
+        assert_eq!(&packet[34..38], &[0, 68, 0, 67]);
+        let message = dhcp::decode(&packet[42..]).unwrap();
+        assert_eq!((message.op, message.xid, message.chaddr), (1, 0xDEADBEEF, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]));
+        assert_eq!(message.message_type(), Some(1));
+        assert_eq!(message.option(dhcp::OPTION_PARAMETER_LIST), Some(&[1u8, 3, 6, 15][..]));
+    }
+
+    #[test]
+    fn build_dhcp_offer_options() {
+        let packet = make_packet(
+            "dhcp",
+            vec![
+                ("dhcp_message_type", "offer"),
+                ("dhcp_yiaddr", "192.168.1.50"),
+                ("dhcp_server_id", "192.168.1.1"),
+                ("dhcp_lease_time", "3600"),
+                ("dhcp_dns", "8.8.8.8, 1.1.1.1"),
+                ("dhcp_options", "60:4d53"),
+            ],
+            None,
+        )
+        .build()
+        .unwrap();
+        // 服务器消息默认从 67 端口发往 68 端口
+        assert_eq!((u16::from_be_bytes([packet[34], packet[35]]), u16::from_be_bytes([packet[36], packet[37]])), (67, 68));
+        let message = dhcp::decode(&packet[42..]).unwrap();
+        assert_eq!(message.op, 2);
+        assert_eq!(message.yiaddr, [192, 168, 1, 50]);
+        assert_eq!(message.option(dhcp::OPTION_LEASE_TIME), Some(&3600u32.to_be_bytes()[..]));
+        assert_eq!(message.option(dhcp::OPTION_DNS_SERVERS), Some(&[8, 8, 8, 8, 1, 1, 1, 1][..]));
+        assert_eq!(message.option(60), Some(&b"MS"[..]));
+        // 服务器消息不携带参数列表
+        assert!(message.option(dhcp::OPTION_PARAMETER_LIST).is_none());
+    }
+
+    #[test]
+    fn build_ntp_client_request() {
+        let packet = make_packet("ntp", vec![], None).build().unwrap();
+        assert_eq!(&packet[36..38], &123u16.to_be_bytes());
+        assert_eq!(packet.len(), 42 + ntp::PACKET_LEN);
+        let request = ntp::decode(&packet[42..]).unwrap();
+        assert_eq!((request.version, request.mode), (4, 3));
+        assert!(request.transmit_ts > 0);
+    }
+
+    #[test]
+    fn app_fields_are_validated() {
+        let errors = make_packet(
+            "dns",
+            vec![("dns_questions", "example.com BOGUS"), ("dns_answers", "x A 1 bad")],
+            None,
+        )
+        .validate();
+        assert_eq!(errors.len(), 2);
+        assert!(make_packet("dhcp", vec![("dhcp_message_type", "hello")], None).build().is_err());
+        assert!(make_packet("dhcp", vec![("dhcp_param_list", "1,300")], None).build().is_err());
+    }
+
+    // ── schema ───────────────────────────────────────────────
+
+    #[test]
+    fn schema_defaults_match_builder_defaults() {
+        for schema in PacketBuilder::schemas() {
+            if schema.protocol == "raw" {
+                continue;
+            }
+            // NTP 发送时间戳默认取当前时间，固定后再比较
+            let pinned = [("ntp_transmit_ts", "1")];
+            let defaults: Vec<(&str, &str)> = schema
+                .fields
+                .iter()
+                .filter(|f| f.name != "ntp_transmit_ts")
+                .map(|f| (f.name.as_str(), f.default.as_str()))
+                .chain(pinned)
+                .collect();
+            let explicit = make_packet(&schema.protocol, defaults, None).build().unwrap();
+            let implicit = make_packet(&schema.protocol, pinned.to_vec(), None).build().unwrap();
+            assert_eq!(explicit, implicit, "{}", schema.protocol);
+        }
+    }
+
+    #[test]
+    fn schema_covers_builder_aliases() {
+        let arp = PacketBuilder::schema("arp").unwrap();
+        let keys: Vec<&str> = arp
+            .fields
+            .iter()
+            .flat_map(|f| std::iter::once(f.name.as_str()).chain(f.aliases.iter().map(|a| a.as_str())))
+            .collect();
+        for key in ["target_mac", "senderIp", "hardware_size", "operation", "etherType"] {
+            assert!(keys.contains(&key), "{}", key);
+        }
+        assert!(PacketBuilder::schema("sctp").is_none());
+    }
 }
@@ -0,0 +1,534 @@
+use serde::{Serialize, Deserialize};
+
+// 各协议字段的唯一定义：构建、校验和前端表单都以此为准
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    Mac,
+    Ipv4,
+    // 十进制整数，闭区间
+    Dec { min: u64, max: u64 },
+    // 十六进制整数，可带 0x 前缀
+    Hex { max: u64 },
+    // 优先按十六进制解析，失败再按十进制解析（与 ARP 字段的历史行为一致）
+    HexOrDec { max: u64 },
+    // 固定宽度的十六进制字节，不足时左侧补零
+    HexBytes { bytes: usize },
+    // 0x 前缀为十六进制，否则十进制，再退回十六进制
+    Checksum,
+    Bit,
+    Bool,
+    // 变长十六进制字节串，不补齐
+    HexData { max_bytes: usize },
+    // 逗号分隔的 IPv4 地址，每项可带 :有符号优先级
+    Ipv4List,
+    // TCP 选项文本写法，见 tcp_options::parse_options_spec
+    TcpOptions,
+    // DNS 问题列表，见 dns::parse_questions
+    DnsQuestions,
+    // DNS 资源记录列表，见 dns::parse_records
+    DnsRecords,
+    // DHCP 额外选项，见 dhcp::parse_raw_options
+    DhcpOptions,
+    // 逗号分隔的 0-255 十进制数
+    ByteList,
+    // 任意文本，按 UTF-8 字节数限制长度
+    Text { max_len: usize },
+    // 固定取值之一，不区分大小写
+    Choice { options: &'static [&'static str] },
+    PayloadFormat,
+}
+
+pub struct FieldSpec {
+    // 主键名（前端使用的键），aliases 为兼容的旧键名
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub kind: FieldKind,
+    // 字段在报文中的位宽
+    pub width: u32,
+    // 空字符串表示默认值由其他字段推导，见 help
+    pub default: &'static str,
+    pub help: &'static str,
+}
+
+impl FieldSpec {
+    pub fn keys(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+struct ProtocolSpec {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    description: &'static str,
+    payload: &'static str,
+    // 按头部分组，依次排列
+    fields: &'static [&'static [FieldSpec]],
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldSchema {
+    pub name: String,
+    pub aliases: Vec<String>,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub width: u32,
+    pub default: String,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    pub options: Vec<String>,
+    pub help: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProtocolSchema {
+    pub protocol: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    pub payload: String,
+    pub fields: Vec<FieldSchema>,
+}
+
+const fn field(
+    name: &'static str,
+    aliases: &'static [&'static str],
+    kind: FieldKind,
+    width: u32,
+    default: &'static str,
+    help: &'static str,
+) -> FieldSpec {
+    FieldSpec { name, aliases, kind, width, default, help }
+}
+
+const U8: FieldKind = FieldKind::Dec { min: 0, max: 0xFF };
+const U16: FieldKind = FieldKind::Dec { min: 0, max: 0xFFFF };
+const U32: FieldKind = FieldKind::Dec { min: 0, max: 0xFFFF_FFFF };
+const MAC: FieldKind = FieldKind::Mac;
+const IPV4: FieldKind = FieldKind::Ipv4;
+const ETHER_TYPE: FieldKind = FieldKind::HexBytes { bytes: 2 };
+const CHECKSUM_MODE: FieldKind = FieldKind::Choice { options: &["auto", "fixed", "corrupt"] };
+const FRAG_ORDER: FieldKind = FieldKind::Choice { options: &["in_order", "reverse", "last_first", "random"] };
+const U64: FieldKind = FieldKind::Dec { min: 0, max: u64::MAX };
+const DHCP_MESSAGE_TYPE: FieldKind =
+    FieldKind::Choice { options: &["discover", "offer", "request", "decline", "ack", "nak", "release", "inform"] };
+
+const HEX_PAYLOAD: &str = "十六进制字节串，可用空格或冒号分隔";
+const APP_PAYLOAD: &str = "追加在应用层数据之后的十六进制字节串，可用空格或冒号分隔";
+
+static ETHERNET_HEADER: &[FieldSpec] = &[
+    field("dst_mac", &[], MAC, 48, "00:00:00:00:00:00", "目的 MAC 地址"),
+    field("src_mac", &[], MAC, 48, "00:00:00:00:00:00", "源 MAC 地址"),
+    field("ether_type", &[], ETHER_TYPE, 16, "0800", "以太网类型（十六进制），0800=IPv4，0806=ARP"),
+];
+
+// 所有基于 IPv4 的协议共用的头部字段
+static IPV4_HEADER: &[FieldSpec] = &[
+    field("version", &[], FieldKind::Dec { min: 0, max: 15 }, 4, "4", "IP 版本号"),
+    field("ihl", &[], FieldKind::Dec { min: 0, max: 15 }, 4, "", "头部长度，以 4 字节为单位；默认按选项长度自动计算"),
+    field("dscp", &[], FieldKind::Dec { min: 0, max: 63 }, 6, "0", "区分服务代码点"),
+    field("ecn", &[], FieldKind::Dec { min: 0, max: 3 }, 2, "0", "显式拥塞通知"),
+    field("tos", &[], U8, 8, "", "完整的服务类型字节，填写后忽略 dscp/ecn"),
+    field("identification", &[], U16, 16, "0", "标识"),
+    field("df", &[], FieldKind::Bit, 1, "", "不分片(DF)标志；ICMP 默认 0，其余默认 1"),
+    field("mf", &[], FieldKind::Bit, 1, "0", "还有分片(MF)标志"),
+    field("flags", &[], FieldKind::Dec { min: 0, max: 7 }, 3, "", "完整的 3 位标志，填写后忽略 df/mf"),
+    field("fragment_offset", &[], FieldKind::Dec { min: 0, max: 8191 }, 13, "0", "片偏移，以 8 字节为单位"),
+    field("ttl", &[], U8, 8, "64", "生存时间"),
+    field("ip_protocol", &[], U8, 8, "", "覆盖上层协议号（十进制）；默认由协议类型决定"),
+    field("ip_option_rr", &[], FieldKind::Dec { min: 0, max: 9 }, 0, "0", "记录路由选项的地址槽数量，0 表示不添加"),
+    field("ip_option_ts", &[], FieldKind::Dec { min: 0, max: 9 }, 0, "0", "时间戳选项的槽数量，0 表示不添加"),
+    field("ip_options", &[], FieldKind::HexData { max_bytes: 40 }, 0, "", "追加的原始选项字节（十六进制），不足 4 字节对齐时补 0"),
+    field("ip_checksum_mode", &[], CHECKSUM_MODE, 0, "auto", "头部校验和：auto 自动计算，fixed 使用 ip_checksum，corrupt 故意写入错误值"),
+    field("ip_checksum", &[], FieldKind::Checksum, 16, "", "fixed 模式下写入的头部校验和"),
+];
+
+// 分片在构建完成后进行，一个逻辑报文会变成多个帧
+static FRAGMENTATION: &[FieldSpec] = &[
+    field("frag_size", &[], FieldKind::Dec { min: 0, max: 65528 }, 0, "0", "每个分片的数据字节数（8 的倍数），0 表示不分片"),
+    field("frag_first_size", &[], FieldKind::Dec { min: 0, max: 65528 }, 0, "0", "首分片的数据字节数，0 表示与 frag_size 相同"),
+    field("frag_overlap", &[], FieldKind::Dec { min: 0, max: 65528 }, 0, "0", "后续分片与前一分片重叠的字节数（8 的倍数）"),
+    field("frag_order", &[], FRAG_ORDER, 0, "in_order", "发送顺序：in_order 顺序，reverse 倒序，last_first 末分片先发，random 随机"),
+    field("frag_duplicates", &[], FieldKind::Dec { min: 0, max: 16 }, 0, "0", "每个分片额外重复发送的次数"),
+];
+
+static IPV4_ADDRESSES: &[FieldSpec] = &[
+    field("srcIp", &[], IPV4, 32, "192.168.1.1", "源 IP 地址"),
+    field("dstIp", &[], IPV4, 32, "192.168.1.2", "目的 IP 地址"),
+];
+
+static IPV4_FIELDS: &[FieldSpec] = &[
+    field("protocol", &[], U8, 8, "6", "上层协议号（十进制），1=ICMP，6=TCP，17=UDP"),
+];
+
+static TCP_FIELDS: &[FieldSpec] = &[
+    field("srcPort", &[], U16, 16, "12345", "源端口"),
+    field("dstPort", &[], U16, 16, "80", "目的端口"),
+    field("seq", &[], U32, 32, "0", "序列号"),
+    field("ack", &[], U32, 32, "0", "确认号"),
+    field("data_offset", &[], FieldKind::Dec { min: 5, max: 15 }, 4, "", "TCP 头部长度，以 4 字节为单位；默认按选项长度自动计算"),
+    field("reserved", &[], FieldKind::Dec { min: 0, max: 7 }, 3, "0", "保留位"),
+    field("flag_urg", &[], FieldKind::Bit, 1, "0", "URG 标志"),
+    field("flag_ack", &[], FieldKind::Bit, 1, "0", "ACK 标志"),
+    field("flag_psh", &[], FieldKind::Bit, 1, "0", "PSH 标志"),
+    field("flag_rst", &[], FieldKind::Bit, 1, "0", "RST 标志"),
+    field("flag_syn", &[], FieldKind::Bit, 1, "1", "SYN 标志"),
+    field("flag_fin", &[], FieldKind::Bit, 1, "0", "FIN 标志"),
+    field("window_size", &[], U16, 16, "8192", "窗口大小"),
+    field("urgent_pointer", &[], U16, 16, "0", "紧急指针"),
+    field("checksum", &[], FieldKind::Checksum, 16, "0", "TCP 校验和，0 表示不填写"),
+    field(
+        "tcp_options",
+        &[],
+        FieldKind::TcpOptions,
+        0,
+        "",
+        "TCP 选项，逗号分隔按顺序编码：mss:1460, ws:7, sack_perm, sack:100-200;300-400, ts:值[:回显], nop, eol, raw:类型[:长度]:十六进制值",
+    ),
+];
+
+static UDP_PORTS: &[FieldSpec] = &[
+    field("srcPort", &[], U16, 16, "12345", "源端口"),
+    field("dstPort", &[], U16, 16, "53", "目的端口"),
+];
+
+static UDP_FIELDS: &[FieldSpec] = &[
+    field("length", &[], U16, 16, "0", "UDP 长度，0 表示自动计算"),
+    field("checksum", &[], FieldKind::Checksum, 16, "0", "UDP 校验和，0 表示不填写"),
+];
+
+static ARP_FIELDS: &[FieldSpec] = &[
+    field("dst_mac", &[], MAC, 48, "ff:ff:ff:ff:ff:ff", "以太网目的 MAC；未填写时依次取 dstMac/target_mac/targetMac，均未填写则广播"),
+    field("src_mac", &[], MAC, 48, "00:00:00:00:00:00", "以太网源 MAC；未填写时依次取 srcMac/sender_mac/senderMac"),
+    field("ether_type", &["etherType"], ETHER_TYPE, 16, "0806", "以太网类型（十六进制）"),
+    field("hwType", &["hardware_type"], FieldKind::HexOrDec { max: 0xFFFF }, 16, "1", "硬件类型，1=以太网"),
+    field("protoType", &["protocol_type"], FieldKind::HexOrDec { max: 0xFFFF }, 16, "0x0800", "协议类型，0x0800=IPv4"),
+    field("hwSize", &["hw_len", "hardware_size"], FieldKind::HexOrDec { max: 0xFF }, 8, "6", "硬件地址长度"),
+    field("protoSize", &["proto_len", "protocol_size"], FieldKind::HexOrDec { max: 0xFF }, 8, "4", "协议地址长度"),
+    field("opcode", &["op", "operation"], FieldKind::HexOrDec { max: 0xFFFF }, 16, "1", "操作码，1=请求，2=应答"),
+    field("srcMac", &["sender_mac", "senderMac"], MAC, 48, "", "发送方 MAC，默认与以太网源 MAC 相同"),
+    field("srcIp", &["sender_ip", "senderIp"], IPV4, 32, "0.0.0.0", "发送方 IP"),
+    field("dstMac", &["target_mac", "targetMac"], MAC, 48, "", "目标 MAC，默认与以太网目的 MAC 相同"),
+    field("dstIp", &["target_ip", "targetIp"], IPV4, 32, "0.0.0.0", "目标 IP"),
+];
+
+static DNS_FIELDS: &[FieldSpec] = &[
+    field("dns_id", &[], U16, 16, "0", "事务 ID"),
+    field("dns_qr", &[], FieldKind::Bit, 1, "0", "0=查询，1=响应"),
+    field("dns_opcode", &[], FieldKind::Dec { min: 0, max: 15 }, 4, "0", "操作码，0=标准查询"),
+    field("dns_aa", &[], FieldKind::Bit, 1, "0", "权威应答(AA)标志"),
+    field("dns_tc", &[], FieldKind::Bit, 1, "0", "截断(TC)标志"),
+    field("dns_rd", &[], FieldKind::Bit, 1, "1", "期望递归(RD)标志"),
+    field("dns_ra", &[], FieldKind::Bit, 1, "0", "可用递归(RA)标志"),
+    field("dns_rcode", &[], FieldKind::Dec { min: 0, max: 15 }, 4, "0", "响应码，0=无错误，3=域名不存在"),
+    field("dns_questions", &[], FieldKind::DnsQuestions, 0, "example.com A", "问题，\"名称 [类型 [类]]\"，多项以分号分隔，如 example.com A; example.org AAAA"),
+    field(
+        "dns_answers",
+        &[],
+        FieldKind::DnsRecords,
+        0,
+        "",
+        "回答记录，\"名称 类型 TTL 数据\"，多项以分号分隔；MX 数据为\"优先级 域名\"，SRV 为\"优先级 权重 端口 目标\"，未知类型为十六进制",
+    ),
+    field("dns_authorities", &[], FieldKind::DnsRecords, 0, "", "授权记录，格式同 dns_answers"),
+    field("dns_additionals", &[], FieldKind::DnsRecords, 0, "", "附加记录，格式同 dns_answers"),
+];
+
+// DHCP 客户端请求通常从 0.0.0.0 广播发出
+static DHCP_ETHERNET_HEADER: &[FieldSpec] = &[
+    field("dst_mac", &[], MAC, 48, "ff:ff:ff:ff:ff:ff", "目的 MAC 地址"),
+    field("src_mac", &[], MAC, 48, "00:00:00:00:00:00", "源 MAC 地址"),
+    field("ether_type", &[], ETHER_TYPE, 16, "0800", "以太网类型（十六进制）"),
+];
+
+static DHCP_ADDRESSES: &[FieldSpec] = &[
+    field("srcIp", &[], IPV4, 32, "0.0.0.0", "源 IP 地址"),
+    field("dstIp", &[], IPV4, 32, "255.255.255.255", "目的 IP 地址"),
+];
+
+static DHCP_PORTS: &[FieldSpec] = &[
+    field("srcPort", &[], U16, 16, "68", "源端口，未填写时客户端消息为 68，BOOTREPLY 为 67"),
+    field("dstPort", &[], U16, 16, "67", "目的端口，未填写时客户端消息为 67，BOOTREPLY 为 68"),
+];
+
+static DHCP_FIELDS: &[FieldSpec] = &[
+    field("dhcp_message_type", &[], DHCP_MESSAGE_TYPE, 8, "discover", "DHCP 消息类型（选项 53）"),
+    field("dhcp_op", &[], FieldKind::Dec { min: 1, max: 2 }, 8, "", "1=BOOTREQUEST，2=BOOTREPLY；默认由消息类型决定"),
+    field("dhcp_xid", &[], FieldKind::Hex { max: 0xFFFF_FFFF }, 32, "0", "事务 ID（十六进制）"),
+    field("dhcp_secs", &[], U16, 16, "0", "客户端已等待的秒数"),
+    field("dhcp_hops", &[], U8, 8, "0", "中继跳数"),
+    field("dhcp_broadcast", &[], FieldKind::Bit, 1, "1", "广播标志，要求服务器以广播应答"),
+    field("dhcp_client_mac", &[], MAC, 48, "", "客户端硬件地址，默认与以太网源 MAC 相同"),
+    field("dhcp_ciaddr", &[], IPV4, 32, "0.0.0.0", "客户端当前地址"),
+    field("dhcp_yiaddr", &[], IPV4, 32, "0.0.0.0", "分配给客户端的地址（offer/ack）"),
+    field("dhcp_siaddr", &[], IPV4, 32, "0.0.0.0", "下一跳服务器地址"),
+    field("dhcp_giaddr", &[], IPV4, 32, "0.0.0.0", "中继代理地址"),
+    field("dhcp_requested_ip", &[], IPV4, 32, "", "请求的地址（选项 50），未填写时不添加"),
+    field("dhcp_server_id", &[], IPV4, 32, "", "服务器标识（选项 54），未填写时不添加"),
+    field("dhcp_lease_time", &[], U32, 32, "", "租期秒数（选项 51），未填写时不添加"),
+    field("dhcp_subnet_mask", &[], IPV4, 32, "", "子网掩码（选项 1），未填写时不添加"),
+    field("dhcp_router", &[], FieldKind::Ipv4List, 0, "", "默认网关（选项 3），逗号分隔"),
+    field("dhcp_dns", &[], FieldKind::Ipv4List, 0, "", "DNS 服务器（选项 6），逗号分隔"),
+    field("dhcp_hostname", &[], FieldKind::Text { max_len: 255 }, 0, "", "主机名（选项 12），未填写时不添加"),
+    field("dhcp_param_list", &[], FieldKind::ByteList, 0, "1,3,6,15", "请求参数列表（选项 55），仅客户端消息携带"),
+    field("dhcp_options", &[], FieldKind::DhcpOptions, 0, "", "额外选项，\"代码:十六进制值\"，多项以分号分隔，如 60:4d53465420352e30"),
+];
+
+static NTP_PORTS: &[FieldSpec] = &[
+    field("srcPort", &[], U16, 16, "12345", "源端口"),
+    field("dstPort", &[], U16, 16, "123", "目的端口"),
+];
+
+static NTP_FIELDS: &[FieldSpec] = &[
+    field("ntp_leap", &[], FieldKind::Dec { min: 0, max: 3 }, 2, "0", "闰秒指示"),
+    field("ntp_version", &[], FieldKind::Dec { min: 0, max: 7 }, 3, "4", "版本号"),
+    field("ntp_mode", &[], FieldKind::Dec { min: 0, max: 7 }, 3, "3", "模式，3=客户端，4=服务器，5=广播"),
+    field("ntp_stratum", &[], U8, 8, "0", "层级"),
+    field("ntp_poll", &[], U8, 8, "0", "轮询间隔（以 2 为底的指数，按补码填写）"),
+    field("ntp_precision", &[], U8, 8, "0", "时钟精度（以 2 为底的指数，按补码填写）"),
+    field("ntp_root_delay", &[], U32, 32, "0", "根延迟（16.16 定点数）"),
+    field("ntp_root_dispersion", &[], U32, 32, "0", "根离散（16.16 定点数）"),
+    field("ntp_reference_id", &[], FieldKind::HexBytes { bytes: 4 }, 32, "00000000", "参考标识（十六进制）"),
+    field("ntp_reference_ts", &[], U64, 64, "0", "参考时间戳（64 位 NTP 时间戳）"),
+    field("ntp_origin_ts", &[], U64, 64, "0", "起始时间戳"),
+    field("ntp_receive_ts", &[], U64, 64, "0", "接收时间戳"),
+    field("ntp_transmit_ts", &[], U64, 64, "", "发送时间戳，默认为当前时间"),
+];
+
+// ICMP 的 IP 头部沿用十六进制的 id/protocol，地址兼容旧的 src_ip/dst_ip 键名
+static ICMP_IP_FIELDS: &[FieldSpec] = &[
+    field("id", &[], FieldKind::HexBytes { bytes: 2 }, 16, "0000", "IP 标识（十六进制），填写 identification 时忽略"),
+    field("protocol", &[], FieldKind::Hex { max: 0xFF }, 8, "01", "IP 上层协议号（十六进制）"),
+    field("srcIp", &["src_ip"], IPV4, 32, "192.168.1.1", "源 IP 地址"),
+    field("dstIp", &["dst_ip"], IPV4, 32, "192.168.1.2", "目的 IP 地址"),
+];
+
+// 各字段仅在对应类型的报文中使用
+static ICMP_FIELDS: &[FieldSpec] = &[
+    field("icmp_type", &[], FieldKind::Hex { max: 0xFF }, 8, "08", "ICMP 类型（十六进制）：00/08 回显，03 不可达，05 重定向，09/0A 路由器通告/请求，0B 超时，0D/0E 时间戳"),
+    field("icmp_code", &[], FieldKind::Hex { max: 0xFF }, 8, "00", "ICMP 代码（十六进制）"),
+    field("identifier", &[], FieldKind::HexBytes { bytes: 2 }, 16, "0000", "回显/时间戳标识符（十六进制）"),
+    field("sequence", &[], FieldKind::HexBytes { bytes: 2 }, 16, "0000", "回显/时间戳序列号（十六进制）"),
+    field("icmp_mtu", &[], U16, 16, "0", "不可达（需要分片，代码 4）时的下一跳 MTU"),
+    field("icmp_pointer", &[], U8, 8, "0", "参数问题（类型 0C）指向出错字节的偏移"),
+    field("icmp_gateway", &[], IPV4, 32, "0.0.0.0", "重定向的网关地址"),
+    field(
+        "icmp_original",
+        &[],
+        FieldKind::HexData { max_bytes: 576 },
+        0,
+        "",
+        "差错报文携带的原始数据报（十六进制）；未填写时按 orig_* 字段生成 IP 头部 + 8 字节传输层头部",
+    ),
+    field("orig_protocol", &[], U8, 8, "17", "生成的原始数据报的协议号"),
+    field("orig_dst_ip", &[], IPV4, 32, "", "生成的原始数据报的目的地址，默认与本报文源地址相同；其源地址为本报文的目的地址"),
+    field("orig_src_port", &[], U16, 16, "12345", "生成的原始数据报的源端口"),
+    field("orig_dst_port", &[], U16, 16, "33434", "生成的原始数据报的目的端口"),
+    field("icmp_originate", &[], U32, 32, "0", "时间戳请求/应答的发起时间戳（自午夜 UTC 起的毫秒数）"),
+    field("icmp_receive", &[], U32, 32, "0", "时间戳应答的接收时间戳"),
+    field("icmp_transmit", &[], U32, 32, "0", "时间戳应答的发送时间戳"),
+    field("icmp_lifetime", &[], U16, 16, "1800", "路由器通告的生存期（秒）"),
+    field("icmp_routers", &[], FieldKind::Ipv4List, 0, "", "路由器通告中的地址，逗号分隔，可带 :优先级（如 192.168.1.1:0）"),
+];
+
+static RAW_FIELDS: &[FieldSpec] = &[
+    field("payload_format", &["format"], FieldKind::PayloadFormat, 0, "", "载荷格式；未填写时按 base64:/file: 前缀判断，否则为十六进制"),
+    field("fixup", &["auto_fixup"], FieldKind::Bool, 0, "false", "自动修正 IPv4 总长度、校验和及 TCP/UDP/ICMP 校验和"),
+];
+
+static PROTOCOLS: &[ProtocolSpec] = &[
+    ProtocolSpec { name: "ethernet", aliases: &[], description: "以太网帧", payload: HEX_PAYLOAD, fields: &[ETHERNET_HEADER] },
+    ProtocolSpec {
+        name: "ipv4",
+        aliases: &["ip"],
+        description: "以太网 + IPv4",
+        payload: HEX_PAYLOAD,
+        fields: &[ETHERNET_HEADER, IPV4_HEADER, IPV4_FIELDS, IPV4_ADDRESSES, FRAGMENTATION],
+    },
+    ProtocolSpec {
+        name: "tcp",
+        aliases: &[],
+        description: "以太网 + IPv4 + TCP",
+        payload: HEX_PAYLOAD,
+        fields: &[ETHERNET_HEADER, IPV4_HEADER, IPV4_ADDRESSES, TCP_FIELDS, FRAGMENTATION],
+    },
+    ProtocolSpec {
+        name: "udp",
+        aliases: &[],
+        description: "以太网 + IPv4 + UDP",
+        payload: HEX_PAYLOAD,
+        fields: &[ETHERNET_HEADER, IPV4_HEADER, IPV4_ADDRESSES, UDP_PORTS, UDP_FIELDS, FRAGMENTATION],
+    },
+    ProtocolSpec {
+        name: "dns",
+        aliases: &[],
+        description: "以太网 + IPv4 + UDP + DNS",
+        payload: APP_PAYLOAD,
+        fields: &[ETHERNET_HEADER, IPV4_HEADER, IPV4_ADDRESSES, UDP_PORTS, UDP_FIELDS, DNS_FIELDS, FRAGMENTATION],
+    },
+    ProtocolSpec {
+        name: "dhcp",
+        aliases: &["bootp"],
+        description: "以太网 + IPv4 + UDP + DHCP",
+        payload: APP_PAYLOAD,
+        fields: &[DHCP_ETHERNET_HEADER, IPV4_HEADER, DHCP_ADDRESSES, DHCP_PORTS, UDP_FIELDS, DHCP_FIELDS, FRAGMENTATION],
+    },
+    ProtocolSpec {
+        name: "ntp",
+        aliases: &[],
+        description: "以太网 + IPv4 + UDP + NTP",
+        payload: APP_PAYLOAD,
+        fields: &[ETHERNET_HEADER, IPV4_HEADER, IPV4_ADDRESSES, NTP_PORTS, UDP_FIELDS, NTP_FIELDS, FRAGMENTATION],
+    },
+    ProtocolSpec { name: "arp", aliases: &[], description: "以太网 + ARP", payload: HEX_PAYLOAD, fields: &[ARP_FIELDS] },
+    ProtocolSpec {
+        name: "icmp",
+        aliases: &[],
+        description: "以太网 + IPv4 + ICMP",
+        payload: HEX_PAYLOAD,
+        fields: &[ETHERNET_HEADER, IPV4_HEADER, ICMP_IP_FIELDS, ICMP_FIELDS, FRAGMENTATION],
+    },
+    ProtocolSpec {
+        name: "raw",
+        aliases: &["eth"],
+        description: "原始以太网帧，按原样发送",
+        payload: "完整的以太网帧：十六进制，或 base64: 前缀，或 file: 文件路径",
+        fields: &[RAW_FIELDS],
+    },
+];
+
+fn find_protocol(protocol: &str) -> Option<&'static ProtocolSpec> {
+    let protocol = protocol.to_lowercase();
+    PROTOCOLS
+        .iter()
+        .find(|p| p.name == protocol || p.aliases.contains(&protocol.as_str()))
+}
+
+// 协议对应的字段定义，未知协议返回 None
+pub fn protocol_fields(protocol: &str) -> Option<impl Iterator<Item = &'static FieldSpec>> {
+    find_protocol(protocol).map(|p| p.fields.iter().flat_map(|group| group.iter()))
+}
+
+// 协议中主键名为 name 的字段定义
+pub fn field_spec(protocol: &str, name: &str) -> Option<&'static FieldSpec> {
+    protocol_fields(protocol)?.find(|f| f.name == name)
+}
+
+pub fn supported_protocols() -> Vec<&'static str> {
+    PROTOCOLS.iter().map(|p| p.name).collect()
+}
+
+pub fn protocol_schema(protocol: &str) -> Option<ProtocolSchema> {
+    find_protocol(protocol).map(to_schema)
+}
+
+pub fn all_schemas() -> Vec<ProtocolSchema> {
+    PROTOCOLS.iter().map(to_schema).collect()
+}
+
+fn to_schema(spec: &ProtocolSpec) -> ProtocolSchema {
+    ProtocolSchema {
+        protocol: spec.name.to_string(),
+        aliases: spec.aliases.iter().map(|a| a.to_string()).collect(),
+        description: spec.description.to_string(),
+        payload: spec.payload.to_string(),
+        fields: spec.fields.iter().flat_map(|group| group.iter()).map(field_schema).collect(),
+    }
+}
+
+fn field_schema(spec: &FieldSpec) -> FieldSchema {
+    let (field_type, min, max, options) = match spec.kind {
+        FieldKind::Mac => ("mac", None, None, vec![]),
+        FieldKind::Ipv4 => ("ipv4", None, None, vec![]),
+        FieldKind::Dec { min, max } => ("integer", Some(min), Some(max), vec![]),
+        FieldKind::Hex { max } => ("hex", Some(0), Some(max), vec![]),
+        FieldKind::HexOrDec { max } => ("hex_or_integer", Some(0), Some(max), vec![]),
+        FieldKind::HexBytes { bytes } => ("hex_bytes", Some(0), Some(u64::MAX >> (64 - bytes * 8)), vec![]),
+        // 变长字节串的 max 表示最大字节数
+        FieldKind::HexData { max_bytes } => ("hex_data", None, Some(max_bytes as u64), vec![]),
+        FieldKind::Checksum => ("checksum", Some(0), Some(0xFFFF), vec![]),
+        FieldKind::Bit => ("bit", Some(0), Some(1), vec![]),
+        FieldKind::Bool => ("bool", None, None, vec!["true", "false"]),
+        FieldKind::Ipv4List => ("ipv4_list", None, None, vec![]),
+        FieldKind::TcpOptions => ("tcp_options", None, None, vec![]),
+        FieldKind::DnsQuestions => ("dns_questions", None, None, vec![]),
+        FieldKind::DnsRecords => ("dns_records", None, None, vec![]),
+        FieldKind::DhcpOptions => ("dhcp_options", None, None, vec![]),
+        FieldKind::ByteList => ("byte_list", Some(0), Some(0xFF), vec![]),
+        // 文本的 max 表示最大字节数
+        FieldKind::Text { max_len } => ("text", None, Some(max_len as u64), vec![]),
+        FieldKind::Choice { options } => ("enum", None, None, options.to_vec()),
+        FieldKind::PayloadFormat => ("enum", None, None, vec!["hex", "base64", "file"]),
+    };
+
+    FieldSchema {
+        name: spec.name.to_string(),
+        aliases: spec.aliases.iter().map(|a| a.to_string()).collect(),
+        field_type: field_type.to_string(),
+        width: spec.width,
+        default: spec.default.to_string(),
+        min,
+        max,
+        options: options.into_iter().map(String::from).collect(),
+        help: spec.help.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::packet_validation::check_value;
+    use std::collections::HashSet;
+
+    #[test]
+    fn defaults_satisfy_their_own_rules() {
+        for protocol in PROTOCOLS {
+            for spec in protocol_fields(protocol.name).unwrap() {
+                assert!(check_value(spec.kind, spec.default).is_ok(), "{}.{}", protocol.name, spec.name);
+            }
+        }
+    }
+
+    #[test]
+    fn keys_are_unique_per_protocol() {
+        for protocol in PROTOCOLS {
+            let mut seen = HashSet::new();
+            for spec in protocol_fields(protocol.name).unwrap() {
+                for key in spec.keys() {
+                    assert!(seen.insert(key), "{} 重复的键 {}", protocol.name, key);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn aliases_resolve_to_same_protocol() {
+        assert_eq!(protocol_schema("IP").unwrap().protocol, "ipv4");
+        assert_eq!(protocol_schema("eth").unwrap().protocol, "raw");
+        assert!(protocol_schema("sctp").is_none());
+    }
+
+    #[test]
+    fn schema_describes_ranges_and_types() {
+        let tcp = protocol_schema("tcp").unwrap();
+        let port = tcp.fields.iter().find(|f| f.name == "dstPort").unwrap();
+        assert_eq!((port.field_type.as_str(), port.width, port.min, port.max), ("integer", 16, Some(0), Some(65535)));
+
+        let arp = protocol_schema("arp").unwrap();
+        let sender = arp.fields.iter().find(|f| f.name == "srcMac").unwrap();
+        assert_eq!(sender.aliases, vec!["sender_mac", "senderMac"]);
+
+        let ether_type = arp.fields.iter().find(|f| f.name == "ether_type").unwrap();
+        assert_eq!(ether_type.max, Some(0xFFFF));
+    }
+
+    #[test]
+    fn application_protocols_use_their_own_ports() {
+        let port = |protocol: &str, name: &str| {
+            protocol_fields(protocol).unwrap().find(|f| f.name == name).unwrap().default
+        };
+        assert_eq!((port("dhcp", "srcPort"), port("dhcp", "dstPort")), ("68", "67"));
+        assert_eq!(port("ntp", "dstPort"), "123");
+        assert_eq!(port("dns", "dstPort"), "53");
+        assert_eq!(protocol_schema("bootp").unwrap().protocol, "dhcp");
+    }
+}
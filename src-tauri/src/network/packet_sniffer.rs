@@ -1,11 +1,19 @@
 use pcap::{Capture, Device, Active};
 use serde::{Serialize, Deserialize};
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::collections::{HashMap, VecDeque};
 use std::time::{SystemTime, UNIX_EPOCH};
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
 use anyhow::{Result, anyhow};
+use crate::network::stream_reassembler::{FlowTable, StreamConversation, FollowedStream};
+use crate::network::conversation_stats::{TrafficStatistics, EndpointStats, ConversationStats};
+use crate::network::rate_series::{RateHistory, RateSeriesSnapshot};
+use crate::network::pcapng_writer::{PcapngRingWriter, RingBufferConfig, RingBufferStatus};
+use crate::network::tcp_options::{self, TcpOption};
+use crate::network::{dns, dhcp, ntp};
+use crate::network::arp::{ArpInfo, ArpTable, ArpEntry};
+use crate::network::interface_correlation::{self, CorrelationReport, TimelineMerger};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CapturedPacket {
@@ -21,6 +29,43 @@ pub struct CapturedPacket {
     pub size: usize,
     pub info: String,
     pub raw_data: Vec<u8>,
+    // 解析出的 TCP 选项，非 TCP 报文为空
+    #[serde(default)]
+    pub tcp_options: Vec<TcpOption>,
+    // 解析出的 ARP 操作码和地址，非 ARP 报文为 None
+    #[serde(default)]
+    pub arp: Option<ArpInfo>,
+    // pcap 记录的捕获时间（微秒），用于多接口合并排序和转发时延计算
+    #[serde(default)]
+    pub timestamp_us: u64,
+    // 捕获该报文的接口
+    #[serde(default)]
+    pub interface: Option<String>,
+}
+
+// 测试用：只有原始帧的报文，其余字段为空，按需用结构体更新语法覆盖
+#[cfg(test)]
+impl CapturedPacket {
+    pub(crate) fn from_frame(raw_data: Vec<u8>) -> Self {
+        Self {
+            id: String::new(),
+            timestamp: 0,
+            protocol: String::new(),
+            src_mac: String::new(),
+            dst_mac: String::new(),
+            src_ip: None,
+            dst_ip: None,
+            src_port: None,
+            dst_port: None,
+            size: raw_data.len(),
+            info: String::new(),
+            raw_data,
+            tcp_options: Vec::new(),
+            arp: None,
+            timestamp_us: 0,
+            interface: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,6 +74,15 @@ pub struct PacketStatistics {
     pub bytes_per_sec: f64,
     pub packets_per_sec: f64,
     pub protocol_stats: HashMap<String, u64>,
+    // 内核缓冲区丢包（pcap stats ps_drop）
+    #[serde(default)]
+    pub kernel_dropped: u64,
+    // 网卡/驱动丢包（pcap stats ps_ifdrop）
+    #[serde(default)]
+    pub interface_dropped: u64,
+    // 内部通道已满导致的丢包
+    #[serde(default)]
+    pub channel_dropped: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -64,6 +118,9 @@ impl PacketSniffer {
                 bytes_per_sec: 0.0,
                 packets_per_sec: 0.0,
                 protocol_stats: HashMap::new(),
+                kernel_dropped: 0,
+                interface_dropped: 0,
+                channel_dropped: 0,
             })),
             filters: Arc::new(Mutex::new(CaptureFilters {
                 protocol: None,
@@ -210,6 +267,10 @@ impl PacketSniffer {
                                 size: 0,
                                 info: String::new(),
                                 raw_data: Vec::new(),
+                                tcp_options: Vec::new(),
+                                arp: None,
+                                timestamp_us: 0,
+                                interface: None,
                             });
                         }
                         continue;
@@ -254,6 +315,7 @@ impl PacketSniffer {
         let mut src_port = None;
         let mut dst_port = None;
         let mut info = String::new();
+        let mut arp = None;
 
         match ether_type {
             0x0800 => {
@@ -270,12 +332,11 @@ impl PacketSniffer {
             0x0806 => {
                 // ARP
                 protocol = "arp".to_string();
-                if let Some((sip, dip)) = PacketSniffer::parse_arp(&data[14..]) {
-                    src_ip = Some(sip);
-                    dst_ip = Some(dip);
-                    info = format!("ARP: {} -> {}", 
-                        src_ip.as_ref().unwrap(), 
-                        dst_ip.as_ref().unwrap());
+                if let Some(parsed) = ArpInfo::parse(&data[14..]) {
+                    src_ip = Some(parsed.sender_ip.clone());
+                    dst_ip = Some(parsed.target_ip.clone());
+                    info = parsed.summary();
+                    arp = Some(parsed);
                 }
             }
             _ => {
@@ -284,6 +345,14 @@ impl PacketSniffer {
             }
         }
 
+        let tcp_options = if ether_type == 0x0800 {
+            PacketSniffer::tcp_option_bytes(&data[14..])
+                .map(tcp_options::decode_options)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
         Some(CapturedPacket {
             id: uuid::Uuid::new_v4().to_string(),
             timestamp,
@@ -297,6 +366,10 @@ impl PacketSniffer {
             size: data.len(),
             info,
             raw_data: data.to_vec(),
+            tcp_options,
+            arp,
+            timestamp_us: packet.header.ts.tv_sec as u64 * 1_000_000 + packet.header.ts.tv_usec as u64,
+            interface: None,
         })
     }
 
@@ -312,7 +385,7 @@ impl PacketSniffer {
         let header_len = ((data[0] & 0x0f) * 4) as usize;
         let ttl = data[8];
         let _flags = (data[6] & 0xe0) >> 5;
-        let _fragment_offset = (((data[6] & 0x1f) as u16) << 8) | (data[7] as u16);
+        let fragment_offset = (((data[6] & 0x1f) as u16) << 8) | (data[7] as u16);
         
         let mut proto_name = "ipv4".to_string();
         let mut src_port = None;
@@ -369,6 +442,15 @@ impl PacketSniffer {
                             window,
                             data_len
                         );
+
+                        let options = PacketSniffer::tcp_option_bytes(data)
+                            .map(tcp_options::decode_options)
+                            .unwrap_or_default();
+                        let options_str = tcp_options::describe_options(&options);
+                        if !options_str.is_empty() {
+                            info.push(' ');
+                            info.push_str(&options_str);
+                        }
                     }
                 }
                 17 => {
@@ -385,6 +467,14 @@ impl PacketSniffer {
                             dst_port.unwrap(),
                             data_len
                         );
+
+                        // 后续分片不含 UDP 头部，不做应用层解析
+                        if fragment_offset == 0 {
+                            let payload = &transport_data[8..];
+                            if let Some(app) = PacketSniffer::describe_udp_application(src_port.unwrap(), dst_port.unwrap(), payload) {
+                                info = format!("{} → {} {}", src_port.unwrap(), dst_port.unwrap(), app);
+                            }
+                        }
                     }
                 }
                 1 => {
@@ -429,15 +519,28 @@ impl PacketSniffer {
         Some((proto_name, src_ip, dst_ip, src_port, dst_port, info))
     }
 
-    fn parse_arp(data: &[u8]) -> Option<(String, String)> {
-        if data.len() < 28 {
-            return None;
+    // 按知名端口识别 DNS/DHCP/NTP，解析失败时返回 None 保留普通 UDP 摘要
+    fn describe_udp_application(src_port: u16, dst_port: u16, payload: &[u8]) -> Option<String> {
+        let uses = |port: u16| src_port == port || dst_port == port;
+        if uses(53) || uses(5353) {
+            dns::decode_message(payload).map(|m| format!("DNS {}", dns::describe(&m)))
+        } else if uses(67) || uses(68) {
+            dhcp::decode(payload).map(|m| format!("DHCP {}", dhcp::describe(&m)))
+        } else if uses(123) {
+            ntp::decode(payload).map(|p| ntp::describe(&p))
+        } else {
+            None
         }
+    }
 
-        let src_ip = format!("{}.{}.{}.{}", data[14], data[15], data[16], data[17]);
-        let dst_ip = format!("{}.{}.{}.{}", data[24], data[25], data[26], data[27]);
-
-        Some((src_ip, dst_ip))
+    // TCP 头部中的选项字节，data 从 IPv4 头部开始
+    fn tcp_option_bytes(data: &[u8]) -> Option<&[u8]> {
+        if data.len() < 20 || data[9] != 6 {
+            return None;
+        }
+        let tcp = data.get(((data[0] & 0x0f) as usize) * 4..)?;
+        let tcp_header_len = ((*tcp.get(12)? >> 4) as usize) * 4;
+        tcp.get(20..tcp_header_len.min(tcp.len()))
     }
 
     fn matches_filters_static(packet: &CapturedPacket, filters: &Arc<Mutex<CaptureFilters>>) -> bool {
@@ -544,66 +647,221 @@ impl PacketSniffer {
     }
 }
 
+const SUBSCRIBER_CAPACITY: usize = 5000;
+// 多接口捕获时按时间戳重排的等待窗口
+const MERGE_WINDOW_US: u64 = 50_000;
+
 // 全局的数据包嗅探器管理器
 pub struct SnifferManager {
     _sniffer: Arc<Mutex<Option<PacketSniffer>>>,
-    capture_thread: Option<std::thread::JoinHandle<()>>,
+    capture_threads: Vec<std::thread::JoinHandle<()>>,
     // 将数据包通道的接收端保留在管理器中
     packet_receiver: Option<Receiver<CapturedPacket>>,
-    statistics: Option<Arc<Mutex<PacketStatistics>>>,
+    // 每个捕获接口一份统计，查询时合并
+    statistics: Vec<Arc<Mutex<PacketStatistics>>>,
     running: Arc<AtomicBool>,
     // 添加数据包缓存，支持多次访问和过滤
     packet_cache: Arc<Mutex<VecDeque<CapturedPacket>>>,
     // 跟踪已发送给前端的数据包索引，避免重复发送
     last_sent_index: Arc<Mutex<usize>>,
+    // 捕获线程中增量维护的分析数据
+    analysis: CaptureAnalysis,
+    // 环形缓冲区写盘（未启用时为 None）
+    ring_writer: Arc<Mutex<Option<PcapngRingWriter>>>,
+    // 最近一次启动捕获的接口和过滤条件
+    interface_names: Vec<String>,
+    filters: Option<CaptureFilters>,
+}
+
+// 订阅通道及其溢出丢弃计数
+type Subscriber = (Sender<CapturedPacket>, Arc<AtomicU64>);
+
+// 捕获线程与管理器共享的分析状态
+#[derive(Clone)]
+struct CaptureAnalysis {
+    // 直接订阅捕获流的消费者（如响应监控），独立于前端显示缓存；跨捕获会话保留
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    // TCP/UDP 会话流表，用于流重组和"跟踪流"
+    flow_table: Arc<Mutex<FlowTable>>,
+    // 端点与会话统计表
+    traffic_stats: Arc<Mutex<TrafficStatistics>>,
+    // 按固定间隔分桶的速率序列
+    rate_history: Arc<Mutex<RateHistory>>,
+    // 从 ARP 报文学习到的 IP/MAC 对应关系
+    arp_table: Arc<Mutex<ArpTable>>,
+}
+
+impl CaptureAnalysis {
+    fn new() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            flow_table: Arc::new(Mutex::new(FlowTable::new())),
+            traffic_stats: Arc::new(Mutex::new(TrafficStatistics::new())),
+            rate_history: Arc::new(Mutex::new(RateHistory::new())),
+            arp_table: Arc::new(Mutex::new(ArpTable::new())),
+        }
+    }
+
+    // 新的捕获会话使用新的流表和统计
+    fn clear(&self) {
+        if let Ok(mut flow_table) = self.flow_table.lock() {
+            flow_table.clear();
+        }
+        if let Ok(mut traffic_stats) = self.traffic_stats.lock() {
+            traffic_stats.clear();
+        }
+        if let Ok(mut rate_history) = self.rate_history.lock() {
+            rate_history.clear();
+        }
+        if let Ok(mut arp_table) = self.arp_table.lock() {
+            arp_table.clear();
+        }
+    }
+
+    // wire_len 为原始帧长度
+    fn record(&self, packet: &CapturedPacket, wire_len: usize) {
+        if let Ok(mut traffic) = self.traffic_stats.lock() {
+            traffic.record(packet);
+        }
+        if let Ok(mut rates) = self.rate_history.lock() {
+            rates.record(packet.timestamp, &packet.protocol, 1, wire_len as u64);
+        }
+        if matches!(packet.protocol.as_str(), "tcp" | "udp") {
+            if let Ok(mut table) = self.flow_table.lock() {
+                table.process_packet(packet);
+            }
+        }
+        if let Some(arp) = &packet.arp {
+            if let Ok(mut table) = self.arp_table.lock() {
+                table.record(arp, packet.timestamp);
+            }
+        }
+    }
+
+    // 分发给各订阅者；订阅者处理不及时丢弃该包并计数，接收端已释放的订阅自动移除
+    fn publish(&self, packet: &CapturedPacket) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|(sender, dropped)| match sender.try_send(packet.clone()) {
+                Err(TrySendError::Full(_)) => {
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+                Ok(()) => true,
+            });
+        }
+    }
+
+    // 没有流量时也推进速率序列，保证时间轴连续
+    fn tick(&self) {
+        if let Ok(mut rates) = self.rate_history.lock() {
+            rates.advance(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64);
+        }
+    }
 }
 
 impl SnifferManager {
     pub fn new() -> Self {
         Self {
             _sniffer: Arc::new(Mutex::new(None)),
-            capture_thread: None,
+            capture_threads: Vec::new(),
             packet_receiver: None,
-            statistics: None,
+            statistics: Vec::new(),
             running: Arc::new(AtomicBool::new(false)),
             packet_cache: Arc::new(Mutex::new(VecDeque::new())),
             last_sent_index: Arc::new(Mutex::new(0)),
+            analysis: CaptureAnalysis::new(),
+            ring_writer: Arc::new(Mutex::new(None)),
+            interface_names: Vec::new(),
+            filters: None,
         }
     }
 
-    pub fn start_capture(&mut self, interface_name: String, filters: CaptureFilters) -> Result<()> {
+    pub fn interface_names(&self) -> &[String] {
+        &self.interface_names
+    }
+
+    pub fn filters(&self) -> Option<&CaptureFilters> {
+        self.filters.as_ref()
+    }
+
+    pub fn start_capture(&mut self, interface_name: String, namespaces: &HashMap<String, String>, filters: CaptureFilters, ring_buffer: Option<RingBufferConfig>) -> Result<()> {
+        self.start_multi_capture(vec![interface_name], namespaces, filters, ring_buffer)
+    }
+
+    // 在多个接口上同时捕获，报文标记来源接口并按时间戳合并到同一缓存；
+    // namespaces 为命名空间隔离中的网卡所在的命名空间，这些网卡在其命名空间中打开
+    pub fn start_multi_capture(&mut self, interface_names: Vec<String>, namespaces: &HashMap<String, String>, filters: CaptureFilters, ring_buffer: Option<RingBufferConfig>) -> Result<()> {
+        let mut interface_names: Vec<String> = interface_names
+            .into_iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        interface_names.sort();
+        interface_names.dedup();
+        if interface_names.is_empty() {
+            return Err(anyhow!("未指定捕获接口"));
+        }
+        if ring_buffer.is_some() && interface_names.len() > 1 {
+            return Err(anyhow!("环形缓冲区暂只支持单接口捕获"));
+        }
+
         // 停止现有捕获
         self.stop_capture();
 
+        // 启用环形缓冲区时先创建写盘器，目录无效等错误直接返回
+        let writer = match ring_buffer {
+            Some(config) => Some(PcapngRingWriter::new(config, &interface_names[0])?),
+            None => None,
+        };
+        if let Ok(mut ring_writer) = self.ring_writer.lock() {
+            *ring_writer = writer;
+        }
+        let ring_writer = self.ring_writer.clone();
+
         // 调试信息已移除以避免崩溃
 
         // 创建独立的数据包通道
         let (packet_sender, packet_receiver) = bounded(5000);
-        let statistics = Arc::new(Mutex::new(PacketStatistics {
-            total_packets: 0,
-            bytes_per_sec: 0.0,
-            packets_per_sec: 0.0,
-            protocol_stats: HashMap::new(),
-        }));
         
         // 克隆接收端用于缓存线程
         let packet_receiver_clone = packet_receiver.clone();
+
+        self.analysis.clear();
         
-        // 保存通道接收端和统计信息在管理器中
+        // 保存通道接收端在管理器中
         self.packet_receiver = Some(packet_receiver);
-        self.statistics = Some(statistics.clone());
         self.running.store(true, Ordering::Relaxed);
-
-        // 创建捕获配置传递给线程
-        let interface_name_clone = interface_name.clone();
-        let filters_clone = filters.clone();
-        let running_clone = self.running.clone();
-
-        // 启动捕获线程
-        let capture_thread = std::thread::spawn(move || {
-            // 在线程中创建 PacketSniffer 的核心功能
-            let _ = Self::run_packet_capture(interface_name_clone, filters_clone, packet_sender, statistics, running_clone);
-        });
+        self.interface_names = interface_names.clone();
+        self.filters = Some(filters.clone());
+        // 多接口时各线程送达顺序与捕获时间不一致，需立即投递并在缓存前按时间戳重排
+        let multi_interface = interface_names.len() > 1;
+
+        // 每个接口启动一个捕获线程，共用数据包通道和分析状态
+        for interface_name in interface_names {
+            let statistics = Arc::new(Mutex::new(PacketStatistics {
+                total_packets: 0,
+                bytes_per_sec: 0.0,
+                packets_per_sec: 0.0,
+                protocol_stats: HashMap::new(),
+                kernel_dropped: 0,
+                interface_dropped: 0,
+                channel_dropped: 0,
+            }));
+            self.statistics.push(statistics.clone());
+
+            let namespace = namespaces.get(&interface_name).cloned();
+            let filters_clone = filters.clone();
+            let packet_sender = packet_sender.clone();
+            let analysis = self.analysis.clone();
+            let ring_writer = ring_writer.clone();
+            let running_clone = self.running.clone();
+            let capture_thread = std::thread::spawn(move || {
+                // 在线程中创建 PacketSniffer 的核心功能
+                let _ = Self::run_packet_capture(interface_name, namespace, filters_clone, packet_sender, statistics, analysis, ring_writer, running_clone, multi_interface);
+            });
+            self.capture_threads.push(capture_thread);
+        }
 
         // 启动数据包缓存收集线程
         let packet_cache_clone = self.packet_cache.clone();
@@ -611,24 +869,35 @@ impl SnifferManager {
         
         std::thread::spawn(move || {
             const MAX_CACHE_SIZE: usize = 10000; // 增加缓存大小以保留更多历史数据包
-            
-            while running_for_cache.load(Ordering::Relaxed) {
-                match packet_receiver_clone.recv_timeout(std::time::Duration::from_millis(100)) {
-                    Ok(packet) => {
-                        if let Ok(mut cache) = packet_cache_clone.lock() {
-                            cache.push_back(packet);
-                            // 保持缓存大小在合理范围内
-                            while cache.len() > MAX_CACHE_SIZE {
-                                cache.pop_front();
-                            }
-                        }
+            let mut merger = TimelineMerger::new(if multi_interface { MERGE_WINDOW_US } else { 0 });
+            let store = |ready: Vec<CapturedPacket>| {
+                if ready.is_empty() {
+                    return;
+                }
+                if let Ok(mut cache) = packet_cache_clone.lock() {
+                    cache.extend(ready);
+                    // 保持缓存大小在合理范围内
+                    while cache.len() > MAX_CACHE_SIZE {
+                        cache.pop_front();
                     }
-                    Err(_) => continue,
                 }
+            };
+            
+            while running_for_cache.load(Ordering::Relaxed) {
+                store(match packet_receiver_clone.recv_timeout(std::time::Duration::from_millis(100)) {
+                    Ok(packet) => merger.push(packet),
+                    // 空闲时输出合并窗口内剩余的报文
+                    Err(_) => merger.flush(),
+                });
+            }
+
+            // 停止后通道和合并窗口中剩余的报文也写入缓存
+            for packet in packet_receiver_clone.try_iter() {
+                store(merger.push(packet));
             }
+            store(merger.flush());
         });
 
-        self.capture_thread = Some(capture_thread);
         Ok(())
     }
     
@@ -683,30 +952,37 @@ impl SnifferManager {
     }
 
     // 在独立线程中运行的数据包捕获逻辑
+    #[allow(clippy::too_many_arguments)]
     fn run_packet_capture(
         interface_name: String, 
+        namespace: Option<String>,
         filters: CaptureFilters, 
         packet_sender: Sender<CapturedPacket>, 
         statistics: Arc<Mutex<PacketStatistics>>,
-        running: Arc<AtomicBool>
+        analysis: CaptureAnalysis,
+        ring_writer: Arc<Mutex<Option<PcapngRingWriter>>>,
+        running: Arc<AtomicBool>,
+        immediate: bool
     ) -> Result<()> {
         use pcap::{Capture, Device};
         
-        // 查找指定网络接口
-        let devices = Device::list().map_err(|e| anyhow!("获取网络设备列表失败: {}", e))?;
-        let device = devices
-            .into_iter()
-            .find(|d| d.name == interface_name)
-            .ok_or_else(|| anyhow!("未找到网络接口: {}", interface_name))?;
-
-        // 创建捕获实例
-        let mut capture = Capture::from_device(device)
-            .map_err(|e| anyhow!("创建捕获实例失败: {}", e))?
-            .promisc(false)
-            .timeout(1000)
-            .buffer_size(1024 * 1024)
-            .open()
-            .map_err(|e| anyhow!("打开网络捕获失败: {}", e))?;
+        // 查找指定网络接口并创建捕获实例，隔离到命名空间的网卡在其命名空间中打开
+        let name = interface_name.clone();
+        let mut capture = crate::network::netns::open_in(namespace.as_deref(), move || {
+            let devices = Device::list().map_err(|e| anyhow!("获取网络设备列表失败: {}", e))?;
+            let device = devices
+                .into_iter()
+                .find(|d| d.name == name)
+                .ok_or_else(|| anyhow!("未找到网络接口: {}", name))?;
+            Capture::from_device(device)
+                .map_err(|e| anyhow!("创建捕获实例失败: {}", e))?
+                .promisc(false)
+                .timeout(1000)
+                .buffer_size(1024 * 1024)
+                .immediate_mode(immediate)
+                .open()
+                .map_err(|e| anyhow!("打开网络捕获失败: {}", e))
+        })?;
 
         // 应用 BPF 过滤器
         let bpf_filter = Self::build_bpf_filter(&filters);
@@ -715,24 +991,39 @@ impl SnifferManager {
                 .map_err(|e| anyhow!("设置BPF过滤器失败: {} (过滤器: {})", e, bpf_filter))?;
         }
         
+        if let Ok(mut writer) = ring_writer.lock() {
+            if let Some(writer) = writer.as_mut() {
+                writer.set_link_type(capture.get_datalink().0 as u16);
+            }
+        }
+        
         let filters_arc = Arc::new(Mutex::new(filters));
         let mut _raw_packet_count = 0u64;
         let mut _raw_byte_count = 0u64;
         let mut filtered_packet_count = 0u64;
         let mut filtered_byte_count = 0u64;
+        let mut channel_dropped = 0u64;
         let start_time = SystemTime::now();
+        let mut last_stats_poll = std::time::Instant::now();
 
         while running.load(Ordering::Relaxed) {
+            // 每秒读取一次内核丢包计数
+            if last_stats_poll.elapsed() >= std::time::Duration::from_secs(1) {
+                Self::update_drop_statistics(&mut capture, &statistics, channel_dropped);
+                last_stats_poll = std::time::Instant::now();
+            }
+
             match capture.next_packet() {
                 Ok(packet) => {
                     _raw_packet_count += 1;
                     _raw_byte_count += packet.data.len() as u64;
 
                     // 解析数据包以便检查协议
-                    let parsed_packet = match PacketSniffer::parse_packet_static(&packet) {
+                    let mut parsed_packet = match PacketSniffer::parse_packet_static(&packet) {
                         Some(p) => p,
                         None => continue,
                     };
+                    parsed_packet.interface = Some(interface_name.clone());
                     
                     // 检查是否为响应监控相关的协议（ICMP、ARP等）
                     let _is_response_monitoring_packet = matches!(parsed_packet.protocol.as_str(), "icmp" | "arp");
@@ -758,11 +1049,34 @@ impl SnifferManager {
                             }
                         }
                         
-                        // 非阻塞发送数据包
-                        let _ = packet_sender.try_send(parsed_packet.clone());
+                        // 在捕获线程中更新会话统计、速率和流表，不受前端缓存淘汰影响
+                        analysis.record(&parsed_packet, packet.data.len());
+                        
+                        // 环形缓冲区写盘，不受通道容量限制
+                        if let Ok(mut writer) = ring_writer.lock() {
+                            if let Some(writer) = writer.as_mut() {
+                                let ts_us = packet.header.ts.tv_sec as u64 * 1_000_000 + packet.header.ts.tv_usec as u64;
+                                writer.write_packet(ts_us, packet.data, packet.header.len);
+                            }
+                        }
+                        
+                        analysis.publish(&parsed_packet);
+
+                        // 非阻塞发送数据包，通道满时计入丢包
+                        if packet_sender.try_send(parsed_packet).is_err() {
+                            channel_dropped += 1;
+                        }
                     }
                 }
                 Err(pcap::Error::TimeoutExpired) => {
+                    analysis.tick();
+                    Self::update_drop_statistics(&mut capture, &statistics, channel_dropped);
+                    last_stats_poll = std::time::Instant::now();
+                    if let Ok(mut writer) = ring_writer.lock() {
+                        if let Some(writer) = writer.as_mut() {
+                            writer.flush();
+                        }
+                    }
                     continue;
                 }
                 Err(_) => {
@@ -773,18 +1087,29 @@ impl SnifferManager {
             }
         }
         
+        Self::update_drop_statistics(&mut capture, &statistics, channel_dropped);
         Ok(())
     }
 
+    fn update_drop_statistics(capture: &mut Capture<Active>, statistics: &Arc<Mutex<PacketStatistics>>, channel_dropped: u64) {
+        let kernel_stats = capture.stats().ok();
+        if let Ok(mut stats) = statistics.lock() {
+            if let Some(kernel_stats) = kernel_stats {
+                stats.kernel_dropped = kernel_stats.dropped as u64;
+                stats.interface_dropped = kernel_stats.if_dropped as u64;
+            }
+            stats.channel_dropped = channel_dropped;
+        }
+    }
+
     pub fn stop_capture(&mut self) {
         // 设置停止标志
         self.running.store(false, Ordering::Relaxed);
         
-        // 等待捕获线程结束
-        if let Some(handle) = self.capture_thread.take() {
-            let start = std::time::Instant::now();
-            const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
-            
+        // 等待捕获线程结束，各接口线程共用一个超时
+        let start = std::time::Instant::now();
+        const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+        for handle in self.capture_threads.drain(..) {
             loop {
                 if handle.is_finished() {
                     let _ = handle.join();
@@ -800,9 +1125,16 @@ impl SnifferManager {
             }
         }
         
+        // 写出环形缓冲区中尚未落盘的数据，保留写盘状态供查询
+        if let Ok(mut writer) = self.ring_writer.lock() {
+            if let Some(writer) = writer.as_mut() {
+                writer.flush();
+            }
+        }
+        
         // 清理状态
         self.packet_receiver = None;
-        self.statistics = None;
+        self.statistics.clear();
         
         // 清理数据包缓存和重置索引
         if let Ok(mut cache) = self.packet_cache.lock() {
@@ -819,15 +1151,35 @@ impl SnifferManager {
 
     pub fn get_statistics(&self) -> Option<PacketStatistics> {
         // 直接返回实际的统计信息，不从缓存重新计算
-        // 这样确保统计数据反映真实的捕获情况，而非当前缓存状态
-        if let Some(ref stats) = self.statistics {
-            if let Ok(stats_guard) = stats.lock() {
-                Some(stats_guard.clone())
-            } else {
-                None
+        // 这样确保统计数据反映真实的捕获情况，而非当前缓存状态；多接口时逐项相加
+        let mut merged: Option<PacketStatistics> = None;
+        for stats in &self.statistics {
+            let Ok(stats) = stats.lock() else {
+                return None;
+            };
+            match merged.as_mut() {
+                None => merged = Some(stats.clone()),
+                Some(total) => {
+                    total.total_packets += stats.total_packets;
+                    total.bytes_per_sec += stats.bytes_per_sec;
+                    total.packets_per_sec += stats.packets_per_sec;
+                    total.kernel_dropped += stats.kernel_dropped;
+                    total.interface_dropped += stats.interface_dropped;
+                    total.channel_dropped += stats.channel_dropped;
+                    for (protocol, count) in &stats.protocol_stats {
+                        *total.protocol_stats.entry(protocol.clone()).or_insert(0) += count;
+                    }
+                }
             }
-        } else {
-            None
+        }
+        merged
+    }
+
+    // 在当前缓存中匹配各接口上的相同报文，计算转发时延
+    pub fn correlate_interfaces(&self, max_latency_us: u64) -> CorrelationReport {
+        match self.packet_cache.lock() {
+            Ok(mut cache) => interface_correlation::correlate(cache.make_contiguous(), max_latency_us),
+            Err(_) => CorrelationReport::default(),
         }
     }
 
@@ -863,6 +1215,61 @@ impl SnifferManager {
         }
     }
 
+    pub fn get_stream_conversations(&self) -> Vec<StreamConversation> {
+        match self.analysis.flow_table.lock() {
+            Ok(table) => table.conversations(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn follow_stream(&self, flow_id: u64) -> Option<FollowedStream> {
+        self.analysis.flow_table.lock().ok()?.follow(flow_id)
+    }
+
+    pub fn get_ring_buffer_status(&self) -> Option<RingBufferStatus> {
+        self.ring_writer.lock().ok()?.as_ref().map(|w| w.status())
+    }
+
+    pub fn get_rate_series(&self, interval_ms: u64, since_ms: Option<u64>) -> Option<RateSeriesSnapshot> {
+        self.analysis.rate_history.lock().ok()?.snapshot(interval_ms, since_ms)
+    }
+
+    pub fn get_endpoint_statistics(&self, kind: &str) -> Vec<EndpointStats> {
+        match self.analysis.traffic_stats.lock() {
+            Ok(stats) => stats.endpoints(kind),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn get_conversation_statistics(&self, kind: &str) -> Vec<ConversationStats> {
+        match self.analysis.traffic_stats.lock() {
+            Ok(stats) => stats.conversations(kind),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // 订阅捕获线程的数据包流，不经过前端轮询的缓存；释放接收端即取消订阅
+    pub fn subscribe(&self) -> Receiver<CapturedPacket> {
+        self.subscribe_counted().0
+    }
+
+    // 同 subscribe，另返回该订阅因处理不及时被丢弃的报文计数
+    pub fn subscribe_counted(&self) -> (Receiver<CapturedPacket>, Arc<AtomicU64>) {
+        let (sender, receiver) = bounded(SUBSCRIBER_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        if let Ok(mut subscribers) = self.analysis.subscribers.lock() {
+            subscribers.push((sender, dropped.clone()));
+        }
+        (receiver, dropped)
+    }
+
+    pub fn get_arp_table(&self) -> Vec<ArpEntry> {
+        match self.analysis.arp_table.lock() {
+            Ok(table) => table.entries(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     // 添加新方法：支持前端过滤的数据包获取
     pub fn get_filtered_packets(&self, max_count: usize, protocol_filter: Option<&str>) -> Vec<CapturedPacket> {
         if let Ok(cache) = self.packet_cache.lock() {
@@ -896,4 +1303,41 @@ impl Default for SnifferManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(id: &str) -> CapturedPacket {
+        CapturedPacket { id: id.to_string(), protocol: "icmp".to_string(), ..CapturedPacket::from_frame(Vec::new()) }
+    }
+
+    #[test]
+    fn subscribers_receive_packets_until_dropped() {
+        let manager = SnifferManager::new();
+        let first = manager.subscribe();
+        let second = manager.subscribe();
+
+        manager.analysis.publish(&packet("a"));
+        assert_eq!(first.try_recv().unwrap().id, "a");
+        assert_eq!(second.try_recv().unwrap().id, "a");
+
+        // 释放接收端即取消订阅
+        drop(second);
+        manager.analysis.publish(&packet("b"));
+        assert_eq!(first.try_recv().unwrap().id, "b");
+        assert_eq!(manager.analysis.subscribers.lock().unwrap().len(), 1);
+
+        // 新的捕获会话不影响已有订阅
+        manager.analysis.clear();
+        manager.analysis.publish(&packet("c"));
+        assert_eq!(first.try_recv().unwrap().id, "c");
+
+        // 通道满时丢弃并计数
+        let (_slow, dropped) = manager.subscribe_counted();
+        for _ in 0..SUBSCRIBER_CAPACITY + 2 {
+            manager.analysis.publish(&packet("d"));
+        }
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,294 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use crate::network::{packet_schema, tcp_options, dns, dhcp};
+
+pub use crate::network::packet_schema::FieldKind;
+
+// 字段校验错误：字段名、原始值、期望的格式或范围
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub value: String,
+    pub expected: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = \"{}\"，应为{}", self.field, self.value, self.expected)
+    }
+}
+
+// 空值表示使用默认值，不做校验；返回 Err 时携带期望格式的描述
+pub fn check_value(kind: FieldKind, value: &str) -> Result<(), String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+
+    let ok = match kind {
+        FieldKind::Mac => parse_mac_value(trimmed).is_some(),
+        FieldKind::Ipv4 => parse_ipv4_value(trimmed).is_some(),
+        FieldKind::Dec { min, max } => parse_dec_value(trimmed).is_some_and(|n| n >= min && n <= max),
+        FieldKind::Hex { max } => parse_hex_number(trimmed).is_some_and(|n| n <= max),
+        FieldKind::HexOrDec { max } => parse_hex_or_dec(trimmed, max).is_some(),
+        FieldKind::HexBytes { bytes } => parse_hex_bytes_value(trimmed, bytes).is_some(),
+        FieldKind::Checksum => parse_checksum_value(trimmed).is_some(),
+        FieldKind::Bit => matches!(trimmed, "0" | "1"),
+        FieldKind::Bool => parse_bool_value(trimmed).is_some(),
+        FieldKind::HexData { max_bytes } => parse_hex_data(trimmed).is_some_and(|b| b.len() <= max_bytes),
+        FieldKind::Ipv4List => parse_ipv4_list(trimmed).is_some(),
+        FieldKind::TcpOptions => tcp_options::parse_options_spec(trimmed).is_ok(),
+        FieldKind::DnsQuestions => dns::parse_questions(trimmed).is_ok(),
+        FieldKind::DnsRecords => dns::parse_records(trimmed).is_ok(),
+        FieldKind::DhcpOptions => dhcp::parse_raw_options(trimmed).is_ok(),
+        FieldKind::ByteList => parse_byte_list(trimmed).is_some(),
+        FieldKind::Text { max_len } => trimmed.len() <= max_len,
+        FieldKind::Choice { options } => options.iter().any(|o| o.eq_ignore_ascii_case(trimmed)),
+        FieldKind::PayloadFormat => matches!(trimmed.to_lowercase().as_str(), "hex" | "base64" | "file"),
+    };
+
+    if ok { Ok(()) } else { Err(describe(kind)) }
+}
+
+// 对协议的所有字段进行校验，一次返回全部错误
+pub fn validate_fields(protocol: &str, fields: &std::collections::HashMap<String, String>) -> Vec<FieldError> {
+    let specs = match packet_schema::protocol_fields(protocol) {
+        Some(r) => r,
+        None => {
+            return vec![FieldError {
+                field: "protocol".to_string(),
+                value: protocol.to_string(),
+                expected: format!("{} 之一", packet_schema::supported_protocols().join("/")),
+            }];
+        }
+    };
+
+    let mut errors = Vec::new();
+    for spec in specs {
+        // 别名各自出现时都需要校验
+        for key in spec.keys() {
+            if let Some(value) = fields.get(key) {
+                if let Err(expected) = check_value(spec.kind, value) {
+                    errors.push(FieldError {
+                        field: key.to_string(),
+                        value: value.clone(),
+                        expected,
+                    });
+                }
+            }
+        }
+    }
+    errors
+}
+
+pub fn describe(kind: FieldKind) -> String {
+    match kind {
+        FieldKind::Mac => "MAC 地址（如 AA:BB:CC:DD:EE:FF）".to_string(),
+        FieldKind::Ipv4 => "IPv4 地址（如 192.168.1.1）".to_string(),
+        FieldKind::Dec { min, max } => format!("十进制整数 {}-{}", min, max),
+        FieldKind::Hex { max } => format!("十六进制整数 0-{:X}", max),
+        FieldKind::HexOrDec { max } => format!("十六进制或十进制整数，不超过 0x{:X}", max),
+        FieldKind::HexBytes { bytes } => format!("最多 {} 位十六进制数", bytes * 2),
+        FieldKind::Checksum => "0-65535 的十进制数或 0x 开头的十六进制数".to_string(),
+        FieldKind::Bit => "0 或 1".to_string(),
+        FieldKind::Bool => "true/false、1/0、yes/no 或 on/off".to_string(),
+        FieldKind::HexData { max_bytes } => format!("偶数位十六进制字节串，最多 {} 字节", max_bytes),
+        FieldKind::Ipv4List => "逗号分隔的 IPv4 地址，可带 :优先级".to_string(),
+        FieldKind::TcpOptions => "逗号分隔的 TCP 选项（如 mss:1460, sack_perm, ws:7），合计不超过 40 字节".to_string(),
+        FieldKind::DnsQuestions => "分号分隔的 DNS 问题（如 example.com A; example.org AAAA）".to_string(),
+        FieldKind::DnsRecords => "分号分隔的 DNS 记录（如 example.com A 300 93.184.216.34）".to_string(),
+        FieldKind::DhcpOptions => "分号分隔的 DHCP 选项（如 60:4d53465420352e30），代码 1-254".to_string(),
+        FieldKind::ByteList => "逗号分隔的 0-255 整数".to_string(),
+        FieldKind::Text { max_len } => format!("不超过 {} 字节的文本", max_len),
+        FieldKind::Choice { options } => format!("{} 之一", options.join("/")),
+        FieldKind::PayloadFormat => "hex、base64 或 file".to_string(),
+    }
+}
+
+fn strip_hex_prefix(value: &str) -> &str {
+    value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value)
+}
+
+pub fn parse_dec_value(value: &str) -> Option<u64> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || !trimmed.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    trimmed.parse::<u64>().ok()
+}
+
+pub fn parse_hex_number(value: &str) -> Option<u64> {
+    let digits = strip_hex_prefix(value.trim());
+    if digits.is_empty() || digits.len() > 16 {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
+
+// 十六进制超出范围时再尝试十进制，与 parse_u16_value/parse_u8_value 一致
+pub fn parse_hex_or_dec(value: &str, max: u64) -> Option<u64> {
+    parse_hex_number(value)
+        .filter(|n| *n <= max)
+        .or_else(|| parse_dec_value(value).filter(|n| *n <= max))
+}
+
+// 去掉空格/冒号/0x 前缀后按大端补齐到指定字节数
+pub fn parse_hex_bytes_value(value: &str, bytes: usize) -> Option<Vec<u8>> {
+    let cleaned = value.trim().replace([' ', ':'], "");
+    let digits = strip_hex_prefix(&cleaned);
+    if digits.is_empty() || digits.len() > bytes * 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let padded = format!("{:0>width$}", digits, width = bytes * 2);
+    (0..bytes)
+        .map(|i| u8::from_str_radix(&padded[i * 2..i * 2 + 2], 16).ok())
+        .collect()
+}
+
+// 去掉空格/冒号/0x 前缀后按原长度解析，要求偶数位
+pub fn parse_hex_data(value: &str) -> Option<Vec<u8>> {
+    let cleaned = value.trim().replace([' ', ':'], "");
+    let digits = strip_hex_prefix(&cleaned);
+    if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..digits.len() / 2)
+        .map(|i| u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).ok())
+        .collect()
+}
+
+pub fn parse_checksum_value(value: &str) -> Option<u16> {
+    let trimmed = value.trim();
+    if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+        return u16::from_str_radix(&trimmed[2..], 16).ok();
+    }
+    if let Ok(dec) = trimmed.parse::<u16>() {
+        return Some(dec);
+    }
+    u16::from_str_radix(trimmed, 16).ok()
+}
+
+pub fn parse_bool_value(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn parse_mac_value(value: &str) -> Option<Vec<u8>> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() != 6 {
+        return None;
+    }
+    parts
+        .iter()
+        .map(|p| {
+            if p.is_empty() || p.len() > 2 {
+                None
+            } else {
+                u8::from_str_radix(p, 16).ok()
+            }
+        })
+        .collect()
+}
+
+// "地址[:优先级]" 列表，优先级为有符号 32 位整数，缺省为 0
+pub fn parse_ipv4_list(value: &str) -> Option<Vec<(Vec<u8>, i32)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|item| {
+            let (addr, pref) = item.split_once(':').unwrap_or((item, "0"));
+            Some((parse_ipv4_value(addr)?, pref.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+pub fn parse_byte_list(value: &str) -> Option<Vec<u8>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
+        .collect()
+}
+
+pub fn parse_ipv4_value(value: &str) -> Option<Vec<u8>> {
+    let parts: Vec<&str> = value.trim().split('.').collect();
+    if parts.len() != 4 {
+        return None;
+    }
+    parts.iter().map(|p| p.parse::<u8>().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let errors = validate_fields("tcp", &fields(&[
+            ("srcPort", "70000"),
+            ("dstPort", "8o"),
+            ("flag_syn", "2"),
+            ("dstIp", "10.0.0.256"),
+            ("seq", "4294967295"),
+        ]));
+        let names: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(names, vec!["dstIp", "srcPort", "dstPort", "flag_syn"]);
+        assert_eq!(errors[1].value, "70000");
+        assert!(errors[1].expected.contains("0-65535"));
+    }
+
+    #[test]
+    fn empty_values_mean_default() {
+        let errors = validate_fields("udp", &fields(&[("srcPort", ""), ("dst_mac", " "), ("checksum", "")]));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn accepts_existing_formats() {
+        assert!(check_value(FieldKind::HexOrDec { max: 0xFFFF }, "0x0800").is_ok());
+        assert!(check_value(FieldKind::HexOrDec { max: 0xFFFF }, "10000").is_ok());
+        assert!(check_value(FieldKind::HexOrDec { max: 0xFF }, "300").is_err());
+        assert!(check_value(FieldKind::HexBytes { bytes: 2 }, "86DD").is_ok());
+        assert!(check_value(FieldKind::HexBytes { bytes: 2 }, "0x800").is_ok());
+        assert!(check_value(FieldKind::HexBytes { bytes: 2 }, "080000").is_err());
+        assert!(check_value(FieldKind::Checksum, "0xBEEF").is_ok());
+        assert!(check_value(FieldKind::Checksum, "ABCD").is_ok());
+        assert!(check_value(FieldKind::Checksum, "0xG").is_err());
+        assert!(check_value(FieldKind::Hex { max: 0xFF }, "08").is_ok());
+        assert!(check_value(FieldKind::Hex { max: 0xFF }, "100").is_err());
+        assert!(check_value(FieldKind::Bool, "on").is_ok());
+        assert!(check_value(FieldKind::Mac, "aa:bb:cc:dd:ee").is_err());
+    }
+
+    #[test]
+    fn aliases_are_checked_and_unknown_keys_ignored() {
+        let errors = validate_fields("arp", &fields(&[("opcode", "1"), ("op", "zz"), ("data", "whatever")]));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "op");
+    }
+
+    #[test]
+    fn unknown_protocol_is_an_error() {
+        let errors = validate_fields("sctp", &HashMap::new());
+        assert_eq!(errors[0].field, "protocol");
+    }
+
+    #[test]
+    fn hex_bytes_are_left_padded() {
+        assert_eq!(parse_hex_bytes_value("800", 2), Some(vec![0x08, 0x00]));
+        assert_eq!(parse_hex_bytes_value("1", 2), Some(vec![0x00, 0x01]));
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// pcapng 块类型
+const BLOCK_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const BLOCK_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const BLOCK_ENHANCED_PACKET: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+// 选项类型
+const OPT_END: u16 = 0;
+const OPT_SHB_USERAPPL: u16 = 4;
+const OPT_IF_NAME: u16 = 2;
+const OPT_IF_TSRESOL: u16 = 9;
+
+const LINKTYPE_ETHERNET: u16 = 1;
+const DEFAULT_SNAPLEN: u32 = 65535;
+const DEFAULT_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RingBufferConfig {
+    pub directory: String,
+    pub file_prefix: Option<String>,
+    // 以下三种轮转条件任意满足一个即切换文件，均未设置时按 100MB 轮转
+    pub max_file_size: Option<u64>,
+    pub max_packets_per_file: Option<u64>,
+    pub max_file_duration_secs: Option<u64>,
+    // 保留的文件个数，默认 10
+    pub max_files: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RingBufferStatus {
+    pub directory: String,
+    pub current_file: Option<String>,
+    pub files: Vec<String>,
+    pub files_created: u64,
+    pub packets_written: u64,
+    pub bytes_written: u64,
+    pub error: Option<String>,
+}
+
+struct OpenFile {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    bytes: u64,
+    packets: u64,
+    first_ts_us: Option<u64>,
+}
+
+// 以 pcapng 格式写入轮转文件，只保留最近的 N 个文件
+pub struct PcapngRingWriter {
+    config: RingBufferConfig,
+    interface_name: String,
+    link_type: u16,
+    current: Option<OpenFile>,
+    files: VecDeque<PathBuf>,
+    files_created: u64,
+    packets_written: u64,
+    bytes_written: u64,
+    error: Option<String>,
+}
+
+impl PcapngRingWriter {
+    pub fn new(config: RingBufferConfig, interface_name: &str) -> Result<Self> {
+        if config.directory.trim().is_empty() {
+            return Err(anyhow!("环形缓冲区目录不能为空"));
+        }
+        if config.max_files == Some(0) {
+            return Err(anyhow!("保留文件数必须大于 0"));
+        }
+        fs::create_dir_all(&config.directory)
+            .map_err(|e| anyhow!("创建捕获目录失败: {} ({})", e, config.directory))?;
+
+        Ok(Self {
+            config,
+            interface_name: interface_name.to_string(),
+            link_type: LINKTYPE_ETHERNET,
+            current: None,
+            files: VecDeque::new(),
+            files_created: 0,
+            packets_written: 0,
+            bytes_written: 0,
+            error: None,
+        })
+    }
+
+    // 在第一个文件创建前设置链路类型（由捕获句柄获得）
+    pub fn set_link_type(&mut self, link_type: u16) {
+        self.link_type = link_type;
+    }
+
+    // 写入失败后停止写盘，错误保留在状态中
+    pub fn write_packet(&mut self, ts_us: u64, data: &[u8], orig_len: u32) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(e) = self.try_write_packet(ts_us, data, orig_len) {
+            self.error = Some(e.to_string());
+            self.current = None;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(file) = self.current.as_mut() {
+            if let Err(e) = file.writer.flush() {
+                self.error = Some(format!("写入捕获文件失败: {}", e));
+            }
+        }
+    }
+
+    pub fn status(&self) -> RingBufferStatus {
+        RingBufferStatus {
+            directory: self.config.directory.clone(),
+            current_file: self.current.as_ref().map(|f| f.path.to_string_lossy().to_string()),
+            files: self.files.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+            files_created: self.files_created,
+            packets_written: self.packets_written,
+            bytes_written: self.bytes_written,
+            error: self.error.clone(),
+        }
+    }
+
+    fn try_write_packet(&mut self, ts_us: u64, data: &[u8], orig_len: u32) -> Result<()> {
+        if self.should_rotate(ts_us) {
+            self.rotate()?;
+        }
+
+        let block = enhanced_packet_block(ts_us, data, orig_len);
+        let file = self.current.as_mut().ok_or_else(|| anyhow!("捕获文件未打开"))?;
+        file.writer.write_all(&block).map_err(|e| anyhow!("写入捕获文件失败: {}", e))?;
+        file.bytes += block.len() as u64;
+        file.packets += 1;
+        file.first_ts_us.get_or_insert(ts_us);
+
+        self.packets_written += 1;
+        self.bytes_written += block.len() as u64;
+        Ok(())
+    }
+
+    fn should_rotate(&self, ts_us: u64) -> bool {
+        let file = match self.current.as_ref() {
+            Some(f) => f,
+            None => return true,
+        };
+        if file.packets == 0 {
+            return false;
+        }
+
+        let no_limit = self.config.max_file_size.is_none()
+            && self.config.max_packets_per_file.is_none()
+            && self.config.max_file_duration_secs.is_none();
+        let max_size = if no_limit { Some(DEFAULT_MAX_FILE_SIZE) } else { self.config.max_file_size };
+
+        if max_size.is_some_and(|max| file.bytes >= max) {
+            return true;
+        }
+        if self.config.max_packets_per_file.is_some_and(|max| file.packets >= max) {
+            return true;
+        }
+        if let (Some(secs), Some(first)) = (self.config.max_file_duration_secs, file.first_ts_us) {
+            if ts_us.saturating_sub(first) >= secs * 1_000_000 {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        if let Some(mut old) = self.current.take() {
+            old.writer.flush().map_err(|e| anyhow!("写入捕获文件失败: {}", e))?;
+        }
+
+        let prefix = self.config.file_prefix.as_deref().unwrap_or("capture");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let path = PathBuf::from(&self.config.directory)
+            .join(format!("{}_{:05}_{}.pcapng", prefix, self.files_created + 1, now));
+
+        let file = File::create(&path)
+            .map_err(|e| anyhow!("创建捕获文件失败: {} ({})", e, path.display()))?;
+        let mut writer = BufWriter::new(file);
+        let header = [section_header_block(), interface_description_block(self.link_type, &self.interface_name)].concat();
+        writer.write_all(&header).map_err(|e| anyhow!("写入捕获文件失败: {}", e))?;
+
+        self.files_created += 1;
+        self.files.push_back(path.clone());
+        self.current = Some(OpenFile {
+            writer,
+            path,
+            bytes: header.len() as u64,
+            packets: 0,
+            first_ts_us: None,
+        });
+
+        // 删除超出保留个数的旧文件
+        let max_files = self.config.max_files.unwrap_or(DEFAULT_MAX_FILES);
+        while self.files.len() > max_files {
+            if let Some(old) = self.files.pop_front() {
+                let _ = fs::remove_file(old);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PcapngRingWriter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad_to_4(body);
+}
+
+fn pad_to_4(body: &mut Vec<u8>) {
+    while !body.len().is_multiple_of(4) {
+        body.push(0);
+    }
+}
+
+// 块结构：类型 + 总长度 + 内容 + 总长度
+fn wrap_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let total = (body.len() + 12) as u32;
+    let mut block = Vec::with_capacity(total as usize);
+    block.extend_from_slice(&block_type.to_le_bytes());
+    block.extend_from_slice(&total.to_le_bytes());
+    block.extend_from_slice(body);
+    block.extend_from_slice(&total.to_le_bytes());
+    block
+}
+
+fn section_header_block() -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes()); // 主版本
+    body.extend_from_slice(&0u16.to_le_bytes()); // 次版本
+    body.extend_from_slice(&(-1i64).to_le_bytes()); // 段长度未知
+    push_option(&mut body, OPT_SHB_USERAPPL, b"BitSender");
+    push_option(&mut body, OPT_END, &[]);
+    wrap_block(BLOCK_SECTION_HEADER, &body)
+}
+
+fn interface_description_block(link_type: u16, interface_name: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&link_type.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    body.extend_from_slice(&DEFAULT_SNAPLEN.to_le_bytes());
+    if !interface_name.is_empty() {
+        push_option(&mut body, OPT_IF_NAME, interface_name.as_bytes());
+    }
+    push_option(&mut body, OPT_IF_TSRESOL, &[6]); // 微秒精度
+    push_option(&mut body, OPT_END, &[]);
+    wrap_block(BLOCK_INTERFACE_DESCRIPTION, &body)
+}
+
+fn enhanced_packet_block(ts_us: u64, data: &[u8], orig_len: u32) -> Vec<u8> {
+    let mut body = Vec::with_capacity(data.len() + 24);
+    body.extend_from_slice(&0u32.to_le_bytes()); // 接口 ID
+    body.extend_from_slice(&((ts_us >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(ts_us as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&orig_len.max(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(data);
+    pad_to_4(&mut body);
+    wrap_block(BLOCK_ENHANCED_PACKET, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("bitsender-pcapng-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn config(dir: &std::path::Path) -> RingBufferConfig {
+        RingBufferConfig {
+            directory: dir.to_string_lossy().to_string(),
+            file_prefix: Some("test".to_string()),
+            max_file_size: None,
+            max_packets_per_file: None,
+            max_file_duration_secs: None,
+            max_files: None,
+        }
+    }
+
+    fn read_u32(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    // 返回文件中每个块的 (类型, 起始偏移)
+    fn blocks(buf: &[u8]) -> Vec<(u32, usize)> {
+        let mut result = Vec::new();
+        let mut offset = 0;
+        while offset < buf.len() {
+            let len = read_u32(buf, offset + 4) as usize;
+            assert_eq!(read_u32(buf, offset + len - 4) as usize, len);
+            assert_eq!(len % 4, 0);
+            result.push((read_u32(buf, offset), offset));
+            offset += len;
+        }
+        result
+    }
+
+    #[test]
+    fn writes_valid_pcapng_blocks() {
+        let dir = temp_dir();
+        let mut writer = PcapngRingWriter::new(config(&dir), "eth0").unwrap();
+        writer.write_packet(1_700_000_000_123_456, &[0xAA; 61], 61);
+        let path = writer.status().current_file.unwrap();
+        drop(writer);
+
+        let buf = fs::read(&path).unwrap();
+        let list = blocks(&buf);
+        let types: Vec<u32> = list.iter().map(|b| b.0).collect();
+        assert_eq!(types, vec![BLOCK_SECTION_HEADER, BLOCK_INTERFACE_DESCRIPTION, BLOCK_ENHANCED_PACKET]);
+        assert_eq!(read_u32(&buf, 8), BYTE_ORDER_MAGIC);
+
+        let epb = list[2].1;
+        let ts = ((read_u32(&buf, epb + 12) as u64) << 32) | read_u32(&buf, epb + 16) as u64;
+        assert_eq!(ts, 1_700_000_000_123_456);
+        assert_eq!(read_u32(&buf, epb + 20), 61);
+        assert_eq!(&buf[epb + 28..epb + 28 + 61], &[0xAA; 61][..]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotates_by_packet_count_and_keeps_last_files() {
+        let dir = temp_dir();
+        let mut cfg = config(&dir);
+        cfg.max_packets_per_file = Some(2);
+        cfg.max_files = Some(2);
+        let mut writer = PcapngRingWriter::new(cfg, "eth0").unwrap();
+        for i in 0..7u64 {
+            writer.write_packet(i, &[0u8; 60], 60);
+        }
+        let status = writer.status();
+        drop(writer);
+
+        assert_eq!(status.files_created, 4);
+        assert_eq!(status.packets_written, 7);
+        assert_eq!(status.files.len(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert!(status.files[1].ends_with(".pcapng"));
+        assert_eq!(status.current_file.as_ref(), Some(&status.files[1]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotates_by_duration() {
+        let dir = temp_dir();
+        let mut cfg = config(&dir);
+        cfg.max_file_duration_secs = Some(1);
+        let mut writer = PcapngRingWriter::new(cfg, "eth0").unwrap();
+        writer.write_packet(0, &[0u8; 60], 60);
+        writer.write_packet(500_000, &[0u8; 60], 60);
+        writer.write_packet(1_000_000, &[0u8; 60], 60);
+        assert_eq!(writer.status().files_created, 2);
+        drop(writer);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        let dir = temp_dir();
+        let mut cfg = config(&dir);
+        cfg.max_files = Some(0);
+        assert!(PcapngRingWriter::new(cfg, "eth0").is_err());
+        cfg = config(&dir);
+        cfg.directory = " ".to_string();
+        assert!(PcapngRingWriter::new(cfg, "eth0").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+
+// 支持的时间粒度（毫秒）及各自保留的桶数：100ms 保留 60 秒，1s 保留 10 分钟
+pub const RATE_INTERVALS_MS: [u64; 2] = [100, 1000];
+const MAX_BUCKETS: usize = 600;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProtocolRate {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateBucket {
+    pub start_ms: u64,
+    pub packets: u64,
+    pub bytes: u64,
+    pub protocols: HashMap<String, ProtocolRate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateSeriesSnapshot {
+    pub interval_ms: u64,
+    pub buckets: Vec<RateBucket>,
+}
+
+// 固定间隔的环形时间序列，空闲时段以零值桶补齐，便于前端直接绘图
+pub struct RateSeries {
+    interval_ms: u64,
+    max_buckets: usize,
+    buckets: VecDeque<RateBucket>,
+}
+
+impl RateSeries {
+    pub fn new(interval_ms: u64, max_buckets: usize) -> Self {
+        Self {
+            interval_ms: interval_ms.max(1),
+            max_buckets: max_buckets.max(1),
+            buckets: VecDeque::new(),
+        }
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+
+    pub fn record(&mut self, timestamp_ms: u64, protocol: &str, packets: u64, bytes: u64) {
+        let bucket = match self.bucket_for(timestamp_ms) {
+            Some(b) => b,
+            None => return, // 早于环中最旧的桶，丢弃
+        };
+        bucket.packets += packets;
+        bucket.bytes += bytes;
+        let entry = bucket.protocols.entry(protocol.to_string()).or_default();
+        entry.packets += packets;
+        entry.bytes += bytes;
+    }
+
+    // 将序列推进到指定时间，没有流量的区间补零值桶
+    pub fn advance(&mut self, now_ms: u64) {
+        let _ = self.bucket_for(now_ms);
+    }
+
+    // since_ms 为空时返回全部桶，否则只返回起始时间不早于 since_ms 的桶
+    pub fn snapshot(&self, since_ms: Option<u64>) -> RateSeriesSnapshot {
+        let since = since_ms.unwrap_or(0);
+        RateSeriesSnapshot {
+            interval_ms: self.interval_ms,
+            buckets: self.buckets.iter().filter(|b| b.start_ms >= since).cloned().collect(),
+        }
+    }
+
+    fn bucket_for(&mut self, timestamp_ms: u64) -> Option<&mut RateBucket> {
+        let start = timestamp_ms - timestamp_ms % self.interval_ms;
+
+        let last_start = match self.buckets.back() {
+            Some(last) => last.start_ms,
+            None => {
+                self.push_bucket(start);
+                return self.buckets.back_mut();
+            }
+        };
+
+        if start > last_start {
+            // 间隔过长时只需补齐环能容纳的部分
+            let gap = ((start - last_start) / self.interval_ms) as usize;
+            let first = if gap > self.max_buckets {
+                self.buckets.clear();
+                start - (self.max_buckets as u64 - 1) * self.interval_ms
+            } else {
+                last_start + self.interval_ms
+            };
+            let mut next = first;
+            while next <= start {
+                self.push_bucket(next);
+                next += self.interval_ms;
+            }
+            return self.buckets.back_mut();
+        }
+
+        // 乱序的时间戳落入已有的桶
+        let offset = ((last_start - start) / self.interval_ms) as usize;
+        if offset >= self.buckets.len() {
+            return None;
+        }
+        let index = self.buckets.len() - 1 - offset;
+        self.buckets.get_mut(index)
+    }
+
+    fn push_bucket(&mut self, start_ms: u64) {
+        if self.buckets.len() >= self.max_buckets {
+            self.buckets.pop_front();
+        }
+        self.buckets.push_back(RateBucket {
+            start_ms,
+            packets: 0,
+            bytes: 0,
+            protocols: HashMap::new(),
+        });
+    }
+}
+
+// 同时维护多个粒度的速率序列
+pub struct RateHistory {
+    series: Vec<RateSeries>,
+}
+
+impl RateHistory {
+    pub fn new() -> Self {
+        Self {
+            series: RATE_INTERVALS_MS.iter().map(|&i| RateSeries::new(i, MAX_BUCKETS)).collect(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for s in &mut self.series {
+            s.clear();
+        }
+    }
+
+    pub fn record(&mut self, timestamp_ms: u64, protocol: &str, packets: u64, bytes: u64) {
+        for s in &mut self.series {
+            s.record(timestamp_ms, protocol, packets, bytes);
+        }
+    }
+
+    pub fn advance(&mut self, now_ms: u64) {
+        for s in &mut self.series {
+            s.advance(now_ms);
+        }
+    }
+
+    // 未知的粒度返回 None
+    pub fn snapshot(&self, interval_ms: u64, since_ms: Option<u64>) -> Option<RateSeriesSnapshot> {
+        self.series
+            .iter()
+            .find(|s| s.interval_ms() == interval_ms)
+            .map(|s| s.snapshot(since_ms))
+    }
+}
+
+impl Default for RateHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_into_fixed_buckets_per_protocol() {
+        let mut series = RateSeries::new(100, 10);
+        series.record(1_000, "tcp", 1, 60);
+        series.record(1_050, "udp", 1, 100);
+        series.record(1_120, "tcp", 2, 120);
+
+        let snap = series.snapshot(None);
+        assert_eq!(snap.buckets.len(), 2);
+        assert_eq!(snap.buckets[0].start_ms, 1_000);
+        assert_eq!((snap.buckets[0].packets, snap.buckets[0].bytes), (2, 160));
+        assert_eq!(snap.buckets[0].protocols["udp"].bytes, 100);
+        assert_eq!(snap.buckets[1].protocols["tcp"].packets, 2);
+    }
+
+    #[test]
+    fn gaps_are_filled_and_ring_is_bounded() {
+        let mut series = RateSeries::new(100, 5);
+        series.record(0, "tcp", 1, 10);
+        series.record(300, "tcp", 1, 10);
+        let snap = series.snapshot(None);
+        assert_eq!(snap.buckets.iter().map(|b| b.packets).collect::<Vec<_>>(), vec![1, 0, 0, 1]);
+
+        series.advance(10_000);
+        let snap = series.snapshot(None);
+        assert_eq!(snap.buckets.len(), 5);
+        assert_eq!(snap.buckets.last().unwrap().start_ms, 10_000);
+        assert!(snap.buckets.iter().all(|b| b.packets == 0));
+    }
+
+    #[test]
+    fn late_samples_land_in_existing_bucket_or_are_dropped() {
+        let mut series = RateSeries::new(100, 3);
+        series.record(500, "udp", 1, 10);
+        series.record(700, "udp", 1, 10);
+        series.record(550, "udp", 1, 10);
+        series.record(100, "udp", 1, 10);
+
+        let snap = series.snapshot(Some(500));
+        assert_eq!(snap.buckets.iter().map(|b| b.packets).collect::<Vec<_>>(), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn history_exposes_each_interval() {
+        let mut history = RateHistory::new();
+        history.record(1_250, "icmp", 1, 98);
+        assert_eq!(history.snapshot(100, None).unwrap().buckets[0].start_ms, 1_200);
+        assert_eq!(history.snapshot(1000, None).unwrap().buckets[0].start_ms, 1_000);
+        assert!(history.snapshot(250, None).is_none());
+    }
+}
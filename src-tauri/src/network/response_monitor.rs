@@ -1,21 +1,76 @@
 use serde::{Serialize, Deserialize};
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH, Instant};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::net::Ipv4Addr;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use anyhow::{Result, anyhow};
 use uuid::Uuid;
+use crate::network::rtt_stats::{RttStatistics, RttSummary};
+use crate::network::CapturedPacket;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TestConfig {
     pub test_type: String,
+    // 目标地址，可为单个 IP、CIDR 网段或 host:port，多个以逗号分隔；
+    // tcp_connect 和 udp_echo 需要端口
     pub target_ip: String,
     pub target_mac: Option<String>,
     pub timeout: u64,
     pub interval: u64,
     pub count: u64, // 0 表示无限循环
     pub payload: Option<String>,
+    // 路由追踪参数，其他测试类型忽略；未填写时使用默认值
+    #[serde(default)]
+    pub traceroute: Option<TracerouteConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TracerouteConfig {
+    // 探测协议：icmp、udp 或 tcp
+    #[serde(default = "default_trace_protocol")]
+    pub protocol: String,
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u8,
+    #[serde(default = "default_probes_per_hop")]
+    pub probes_per_hop: u8,
+    // UDP 为起始目的端口（每个探测加一），TCP 为固定的目的端口
+    #[serde(default)]
+    pub dst_port: Option<u16>,
+}
+
+fn default_trace_protocol() -> String {
+    "icmp".to_string()
+}
+
+fn default_max_hops() -> u8 {
+    30
+}
+
+fn default_probes_per_hop() -> u8 {
+    3
+}
+
+impl Default for TracerouteConfig {
+    fn default() -> Self {
+        Self {
+            protocol: default_trace_protocol(),
+            max_hops: default_max_hops(),
+            probes_per_hop: default_probes_per_hop(),
+            dst_port: None,
+        }
+    }
+}
+
+// 路由追踪的一跳：应答地址（负载均衡时可能有多个）和每个探测的往返时间，超时为 None
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TracerouteHop {
+    pub ttl: u8,
+    pub addresses: Vec<String>,
+    pub rtts: Vec<Option<f64>>,
+    // 应答来自目标本身
+    pub reached: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,9 +83,14 @@ pub struct TestResult {
     pub rtt: Option<f64>, // 往返时间(毫秒)
     pub error: Option<String>,
     pub response_data: Option<String>,
+    // 路由追踪探测的 TTL 和实际应答的地址
+    #[serde(default)]
+    pub hop: Option<u8>,
+    #[serde(default)]
+    pub responder: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct MonitoringStatistics {
     pub total_tests: u64,
     pub successful_tests: u64,
@@ -38,6 +98,12 @@ pub struct MonitoringStatistics {
     pub average_rtt: f64,
     pub min_rtt: f64,
     pub max_rtt: f64,
+    // RTT 分布、抖动与丢包，全部目标汇总
+    #[serde(default)]
+    pub rtt: RttSummary,
+    // 按目标地址分别统计
+    #[serde(default)]
+    pub per_target: HashMap<String, RttSummary>,
 }
 
 // 等待响应的测试项
@@ -51,23 +117,285 @@ struct PendingTest {
     expected_response: ExpectedResponse,
 }
 
+// 应答须来自探测的目标，并带有本次探测的标识或端口
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 enum ExpectedResponse {
-    IcmpEchoReply,
+    IcmpEchoReply { target_ip: Ipv4Addr, identifier: u16, sequence: u16 },
     ArpReply { target_ip: String },
-    TcpSynAck { target_port: u16 },
-    UdpData,
+    TcpSynAck { target_ip: Ipv4Addr, target_port: u16, src_port: u16 },
+    UdpData { target_ip: Ipv4Addr, target_port: u16, src_port: u16 },
+    TraceHop(TraceProbe),
+}
+
+impl ExpectedResponse {
+    fn hop(&self) -> Option<u8> {
+        match self {
+            ExpectedResponse::TraceHop(probe) => Some(probe.ttl),
+            _ => None,
+        }
+    }
+
+    fn match_key(&self) -> MatchKey {
+        match self {
+            ExpectedResponse::IcmpEchoReply { target_ip, identifier, sequence } => MatchKey(1, *target_ip, (*identifier, *sequence)),
+            ExpectedResponse::ArpReply { target_ip } => MatchKey(0, target_ip.parse().unwrap_or(Ipv4Addr::UNSPECIFIED), (0, 0)),
+            ExpectedResponse::TcpSynAck { target_ip, target_port, src_port } => MatchKey(6, *target_ip, (*src_port, *target_port)),
+            ExpectedResponse::UdpData { target_ip, target_port, src_port } => MatchKey(17, *target_ip, (*src_port, *target_port)),
+            ExpectedResponse::TraceHop(probe) => MatchKey(probe.protocol, probe.target, probe.key),
+        }
+    }
+}
+
+// 未决测试的索引键：(IP 协议号，ARP 为 0；探测目标；ICMP 标识符和序号，或本地端口和目标端口)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MatchKey(u8, Ipv4Addr, (u16, u16));
+
+// 从收到的报文中提取它可能应答的探测的索引键，
+// ICMP 超时和不可达报文取其引用的原始头部
+fn reply_match_key(packet: &CapturedPacket) -> Option<MatchKey> {
+    if let Some(arp) = packet.arp.as_ref().filter(|arp| arp.is_reply()) {
+        return Some(MatchKey(0, arp.sender_ip.parse().ok()?, (0, 0)));
+    }
+    let (src, protocol, l4) = ipv4_parts(&packet.raw_data)?;
+    match protocol {
+        1 if l4[0] == 11 || l4[0] == 3 => {
+            let inner = &l4[8..];
+            let inner_ihl = ((*inner.first()? & 0x0F) as usize) * 4;
+            let inner_l4 = inner.get(inner_ihl..inner_ihl + 8)?;
+            if inner_ihl < 20 {
+                return None;
+            }
+            let target = Ipv4Addr::new(inner[16], inner[17], inner[18], inner[19]);
+            let key = if inner[9] == 1 { port_pair(&inner_l4[4..]) } else { port_pair(inner_l4) };
+            Some(MatchKey(inner[9], target, key))
+        }
+        1 => Some(MatchKey(1, src, port_pair(&l4[4..]))),
+        6 | 17 => {
+            let (sport, dport) = port_pair(l4);
+            Some(MatchKey(protocol, src, (dport, sport)))
+        }
+        _ => None,
+    }
+}
+
+// 等待应答的测试，按索引键分组，每个报文只需检查键相同的测试
+#[derive(Debug, Default)]
+struct PendingTests {
+    tests: HashMap<String, PendingTest>,
+    index: HashMap<MatchKey, Vec<String>>,
+}
+
+impl PendingTests {
+    fn insert(&mut self, test_id: String, test: PendingTest) {
+        self.remove(&test_id);
+        self.index.entry(test.expected_response.match_key()).or_default().push(test_id.clone());
+        self.tests.insert(test_id, test);
+    }
+
+    fn remove(&mut self, test_id: &str) -> Option<PendingTest> {
+        let test = self.tests.remove(test_id)?;
+        let key = test.expected_response.match_key();
+        if let Some(ids) = self.index.get_mut(&key) {
+            ids.retain(|id| id != test_id);
+            if ids.is_empty() {
+                self.index.remove(&key);
+            }
+        }
+        Some(test)
+    }
+
+    // 与报文索引键相同的测试
+    fn candidates(&self, packet: &CapturedPacket) -> Vec<(&String, &PendingTest)> {
+        reply_match_key(packet)
+            .and_then(|key| self.index.get(&key))
+            .map(|ids| ids.iter().filter_map(|id| self.tests.get_key_value(id)).collect())
+            .unwrap_or_default()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &PendingTest)> {
+        self.tests.iter()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &PendingTest> {
+        self.tests.values()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tests.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.tests.clear();
+        self.index.clear();
+    }
+}
+
+// 路由追踪探测的标识，用于和超时报文中引用的原始头部对应
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TraceProbe {
+    // IP 协议号：1、6 或 17
+    protocol: u8,
+    ttl: u8,
+    target: Ipv4Addr,
+    // ICMP 为标识符和序号，TCP/UDP 为源端口和目的端口
+    key: (u16, u16),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TraceReply {
+    responder: String,
+    // 目标的应答或不可达报文，追踪到此结束
+    final_reply: bool,
+}
+
+// 展开后的单个监控目标，label 即结果中的 target
+#[derive(Debug, Clone)]
+pub struct MonitorTarget {
+    pub label: String,
+    pub ip: Ipv4Addr,
+    pub port: Option<u16>,
+    pub config: TestConfig,
+}
+
+const TEST_TYPES: &[&str] = &["ping", "arp", "tcp_connect", "udp_echo", "traceroute"];
+
+// 将各配置的目标展开为单个地址，网段按主机逐个展开
+pub fn expand_targets(configs: &[TestConfig]) -> Result<Vec<MonitorTarget>> {
+    let mut targets = Vec::new();
+    for config in configs {
+        if !TEST_TYPES.contains(&config.test_type.as_str()) {
+            return Err(anyhow!("不支持的测试类型: {}", config.test_type));
+        }
+        let needs_port = matches!(config.test_type.as_str(), "tcp_connect" | "udp_echo");
+        for item in config.target_ip.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (hosts, port) = match item.rsplit_once(':') {
+                Some((hosts, port)) => {
+                    let port: u16 = port.trim().parse().ok().filter(|p| *p != 0).ok_or_else(|| anyhow!("无效的目标端口: {}", item))?;
+                    (hosts, Some(port))
+                }
+                None => (item, None),
+            };
+            if needs_port && port.is_none() {
+                return Err(anyhow!("{} 测试的目标需要指定端口: {}", config.test_type, item));
+            }
+            for ip in crate::network::arp::parse_cidr_hosts(hosts.trim())? {
+                let label = match port {
+                    Some(port) => format!("{}:{}", ip, port),
+                    None => ip.to_string(),
+                };
+                targets.push(MonitorTarget { label, ip, port, config: config.clone() });
+            }
+            if targets.len() > crate::network::arp::MAX_SCAN_HOSTS {
+                return Err(anyhow!("监控目标超过 {} 个地址", crate::network::arp::MAX_SCAN_HOSTS));
+            }
+        }
+    }
+    if targets.is_empty() {
+        return Err(anyhow!("监控目标为空"));
+    }
+    Ok(targets)
+}
+
+// 共用捕获的协议过滤：所有配置使用同一协议时只捕获该协议，否则捕获全部
+pub fn capture_protocol(configs: &[TestConfig]) -> String {
+    let protocol_of = |config: &TestConfig| match config.test_type.as_str() {
+        "ping" => "icmp",
+        "arp" => "arp",
+        "tcp_connect" => "tcp",
+        "udp_echo" => "udp",
+        // 路由追踪同时需要 ICMP 超时报文和 TCP/UDP 的最终应答
+        _ => "all",
+    };
+    let mut protocols: Vec<&str> = configs.iter().map(protocol_of).collect();
+    protocols.dedup();
+    match protocols.as_slice() {
+        [single] => single.to_string(),
+        _ => "all".to_string(),
+    }
+}
+
+// 以太网帧中 IPv4 报文的源地址、协议号和传输层数据
+fn ipv4_parts(frame: &[u8]) -> Option<(Ipv4Addr, u8, &[u8])> {
+    if frame.get(12..14)? != [0x08, 0x00] {
+        return None;
+    }
+    let ip = frame.get(14..)?;
+    let ihl = ((*ip.first()? & 0x0F) as usize) * 4;
+    let l4 = ip.get(ihl..)?;
+    if ihl < 20 || l4.len() < 8 {
+        return None;
+    }
+    Some((Ipv4Addr::new(ip[12], ip[13], ip[14], ip[15]), ip[9], l4))
+}
+
+fn port_pair(b: &[u8]) -> (u16, u16) {
+    (u16::from_be_bytes([b[0], b[1]]), u16::from_be_bytes([b[2], b[3]]))
+}
+
+fn matches_expected_response(packet: &CapturedPacket, expected: &ExpectedResponse) -> bool {
+    if let ExpectedResponse::ArpReply { target_ip } = expected {
+        return packet.arp.as_ref().is_some_and(|arp| arp.is_reply() && arp.sender_ip == *target_ip);
+    }
+    let Some((src, protocol, l4)) = ipv4_parts(&packet.raw_data) else {
+        return false;
+    };
+    match *expected {
+        ExpectedResponse::IcmpEchoReply { target_ip, identifier, sequence } => {
+            protocol == 1 && src == target_ip && l4[0] == 0 && port_pair(&l4[4..]) == (identifier, sequence)
+        }
+        ExpectedResponse::TcpSynAck { target_ip, target_port, src_port } => {
+            protocol == 6 && src == target_ip && port_pair(l4) == (target_port, src_port) && l4.get(13).is_some_and(|flags| flags & 0x12 == 0x12)
+        }
+        ExpectedResponse::UdpData { target_ip, target_port, src_port } => {
+            protocol == 17 && src == target_ip && port_pair(l4) == (target_port, src_port)
+        }
+        _ => false,
+    }
+}
+
+// 检查收到的帧是否为某个路由追踪探测的应答：
+// 途经路由器的 TTL 超时、不可达报文通过引用的原始 IP 头部和前 8 字节匹配，目标的最终应答通过端口或标识匹配
+fn match_trace_reply(frame: &[u8], probe: &TraceProbe) -> Option<TraceReply> {
+    let (src, protocol, l4) = ipv4_parts(frame)?;
+    let pair = port_pair;
+    let reply = |final_reply| Some(TraceReply { responder: src.to_string(), final_reply });
+
+    match protocol {
+        1 if l4[0] == 11 || l4[0] == 3 => {
+            let inner = &l4[8..];
+            let inner_ihl = ((*inner.first()? & 0x0F) as usize) * 4;
+            let inner_l4 = inner.get(inner_ihl..inner_ihl + 8)?;
+            if inner_ihl < 20 || inner[9] != probe.protocol || inner[16..20] != probe.target.octets() {
+                return None;
+            }
+            let key = if probe.protocol == 1 { pair(&inner_l4[4..]) } else { pair(inner_l4) };
+            (key == probe.key).then(|| reply(l4[0] == 3))?
+        }
+        1 if probe.protocol == 1 && l4[0] == 0 => (src == probe.target && pair(&l4[4..]) == probe.key).then(|| reply(true))?,
+        // TCP 以 SYN-ACK 或 RST 作答，UDP 偶尔有应用层应答
+        6 | 17 if protocol == probe.protocol && src == probe.target => {
+            let (sport, dport) = pair(l4);
+            if (dport, sport) != probe.key {
+                return None;
+            }
+            if probe.protocol == 6 && l4.get(13).is_none_or(|flags| flags & 0x12 != 0x12 && flags & 0x04 == 0) {
+                return None;
+            }
+            reply(true)
+        }
+        _ => None,
+    }
 }
 
 pub struct ResponseMonitor {
     running: Arc<AtomicBool>,
-    test_config: Arc<Mutex<Option<TestConfig>>>,
-    pending_tests: Arc<Mutex<HashMap<String, PendingTest>>>,
+    pending_tests: Arc<Mutex<PendingTests>>,
     test_results: Arc<Mutex<VecDeque<TestResult>>>,
     statistics: Arc<Mutex<MonitoringStatistics>>,
     result_sender: Sender<TestResult>,
     result_receiver: Receiver<TestResult>,
+    // 路由追踪已收到应答的目标
+    trace_reached: Arc<Mutex<HashSet<Ipv4Addr>>>,
 }
 
 impl ResponseMonitor {
@@ -76,67 +404,81 @@ impl ResponseMonitor {
         
         Ok(Self {
             running: Arc::new(AtomicBool::new(false)),
-            test_config: Arc::new(Mutex::new(None)),
-            pending_tests: Arc::new(Mutex::new(HashMap::new())),
+            pending_tests: Arc::new(Mutex::new(PendingTests::default())),
             test_results: Arc::new(Mutex::new(VecDeque::new())),
-            statistics: Arc::new(Mutex::new(MonitoringStatistics {
-                total_tests: 0,
-                successful_tests: 0,
-                failed_tests: 0,
-                average_rtt: 0.0,
-                min_rtt: 0.0,
-                max_rtt: 0.0,
-            })),
+            statistics: Arc::new(Mutex::new(MonitoringStatistics::default())),
             result_sender,
             result_receiver,
+            trace_reached: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
-    pub fn start_monitoring(&mut self, interface_name: String, test_config: TestConfig) -> Result<()> {
+    pub fn start_monitoring(&mut self, interface_name: String, test_config: TestConfig, packets: Receiver<CapturedPacket>) -> Result<()> {
+        let targets = resolve_targets(&interface_name, &[test_config])?;
+        self.start_session(interface_name, targets, packets)
+    }
+
+    // 在一个会话中同时监控多个目标，各目标按自己的测试类型和间隔发送，共用一个捕获；
+    // targets 由 resolve_targets 预先展开并解析下一跳，packets 为捕获流的订阅，应答在后端匹配
+    pub fn start_session(&mut self, interface_name: String, targets: Vec<MonitorTarget>, packets: Receiver<CapturedPacket>) -> Result<()> {
         if self.running.load(Ordering::Relaxed) {
             return Err(anyhow!("响应监控已在运行中"));
         }
-
-        // 保存测试配置
-        {
-            let mut config_guard = self.test_config.lock().unwrap();
-            *config_guard = Some(test_config.clone());
+        if targets.is_empty() {
+            return Err(anyhow!("监控目标为空"));
         }
 
         // 重置统计信息
         {
             let mut stats = self.statistics.lock().unwrap();
-            *stats = MonitoringStatistics {
-                total_tests: 0,
-                successful_tests: 0,
-                failed_tests: 0,
-                average_rtt: 0.0,
-                min_rtt: 0.0,
-                max_rtt: 0.0,
-            };
+            *stats = MonitoringStatistics::default();
         }
 
-        // 清空之前的结果
+        // 清空之前的结果和未决测试
         {
             let mut results = self.test_results.lock().unwrap();
             results.clear();
         }
+        self.pending_tests.lock().unwrap().clear();
 
+        self.trace_reached.lock().unwrap().clear();
         self.running.store(true, Ordering::Relaxed);
 
-        // 启动发送测试包的线程
+        // 路由追踪逐跳等待应答，每个目标一个线程；其余目标由同一个发送线程按各自间隔调度
+        let (traces, probes): (Vec<MonitorTarget>, Vec<MonitorTarget>) =
+            targets.into_iter().partition(|t| t.config.test_type == "traceroute");
+        let active_senders = Arc::new(AtomicUsize::new(traces.len() + usize::from(!probes.is_empty())));
+
+        for target in traces {
+            let running_clone = self.running.clone();
+            let pending_tests_clone = self.pending_tests.clone();
+            let trace_reached_clone = self.trace_reached.clone();
+            let interface_name_clone = interface_name.clone();
+            let active_clone = active_senders.clone();
+            std::thread::spawn(move || {
+                Self::run_traceroute(&running_clone, target, &pending_tests_clone, &trace_reached_clone, &interface_name_clone);
+                Self::finish_sender(&running_clone, &pending_tests_clone, &active_clone);
+            });
+        }
+
+        if !probes.is_empty() {
+            let running_clone = self.running.clone();
+            let pending_tests_clone = self.pending_tests.clone();
+            let interface_name_clone = interface_name.clone();
+            let active_clone = active_senders.clone();
+            std::thread::spawn(move || {
+                Self::run_test_sender(&running_clone, probes, &pending_tests_clone, &interface_name_clone);
+                Self::finish_sender(&running_clone, &pending_tests_clone, &active_clone);
+            });
+        }
+
+        // 启动应答匹配线程
         let running_clone = self.running.clone();
-        let config_clone = self.test_config.clone();
         let pending_tests_clone = self.pending_tests.clone();
-        let interface_name_clone = interface_name.clone();
-        
+        let trace_reached_clone = self.trace_reached.clone();
+        let result_sender_clone = self.result_sender.clone();
         std::thread::spawn(move || {
-            Self::run_test_sender(
-                running_clone,
-                config_clone,
-                pending_tests_clone,
-                interface_name_clone,
-            );
+            Self::run_packet_matcher(running_clone, packets, pending_tests_clone, trace_reached_clone, result_sender_clone);
         });
 
         // 启动超时检查线程
@@ -187,25 +529,81 @@ impl ResponseMonitor {
             .collect()
     }
 
+    // 按 TTL 汇总路由追踪结果；会话中有多个追踪目标时按 target 筛选
+    pub fn get_traceroute_hops(&self, target: Option<&str>) -> Vec<TracerouteHop> {
+        let results = self.test_results.lock().unwrap();
+        let mut hops: BTreeMap<u8, TracerouteHop> = BTreeMap::new();
+        // 结果按时间倒序保存，汇总时按发送顺序
+        for result in results.iter().rev() {
+            let Some(ttl) = result.hop else { continue };
+            if target.is_some_and(|t| t != result.target) {
+                continue;
+            }
+            let hop = hops.entry(ttl).or_insert_with(|| TracerouteHop { ttl, addresses: Vec::new(), rtts: Vec::new(), reached: false });
+            hop.rtts.push(result.rtt);
+            if let Some(ref responder) = result.responder {
+                if !hop.addresses.contains(responder) {
+                    hop.addresses.push(responder.clone());
+                }
+                hop.reached |= *responder == result.target;
+            }
+        }
+        hops.into_values().collect()
+    }
+
     // 处理接收到的数据包，检查是否匹配待处理的测试
-    pub fn process_received_packet(&self, packet: &crate::network::CapturedPacket) {
+    pub fn process_received_packet(&self, packet: &CapturedPacket) {
         if !self.running.load(Ordering::Relaxed) {
             return;
         }
+        Self::match_packet(&self.pending_tests, &self.trace_reached, &self.result_sender, packet);
+    }
+
+    // 订阅捕获流的匹配线程：在后端直接匹配应答，不依赖前端是否轮询数据包
+    fn run_packet_matcher(
+        running: Arc<AtomicBool>,
+        packets: Receiver<CapturedPacket>,
+        pending_tests: Arc<Mutex<PendingTests>>,
+        trace_reached: Arc<Mutex<HashSet<Ipv4Addr>>>,
+        result_sender: Sender<TestResult>,
+    ) {
+        while running.load(Ordering::Relaxed) {
+            if let Ok(packet) = packets.recv_timeout(std::time::Duration::from_millis(100)) {
+                Self::match_packet(&pending_tests, &trace_reached, &result_sender, &packet);
+            }
+        }
+    }
 
-        let mut pending_guard = match self.pending_tests.lock() {
+    fn match_packet(
+        pending_tests: &Mutex<PendingTests>,
+        trace_reached: &Mutex<HashSet<Ipv4Addr>>,
+        result_sender: &Sender<TestResult>,
+        packet: &CapturedPacket,
+    ) {
+        let mut pending_guard = match pending_tests.lock() {
             Ok(guard) => guard,
             Err(_) => return,
         };
 
-        let _pending_count = pending_guard.len();
-        // 调试信息已移除以避免崩溃
-
         let mut matched_tests = Vec::new();
         
-        // 检查是否有匹配的待处理测试
-        for (test_id, pending_test) in pending_guard.iter() {
-            if self.matches_expected_response(packet, &pending_test.expected_response) {
+        // 只检查索引键与报文相同的待处理测试
+        for (test_id, pending_test) in pending_guard.candidates(packet) {
+            let (matched, responder) = match &pending_test.expected_response {
+                ExpectedResponse::TraceHop(probe) => match match_trace_reply(&packet.raw_data, probe) {
+                    Some(reply) => {
+                        if reply.final_reply {
+                            if let Ok(mut reached) = trace_reached.lock() {
+                                reached.insert(probe.target);
+                            }
+                        }
+                        (true, Some(reply.responder))
+                    }
+                    None => (false, None),
+                },
+                expected => (matches_expected_response(packet, expected), None),
+            };
+            if matched {
                 let rtt = pending_test.start_time.elapsed().as_secs_f64() * 1000.0;
                 let result = TestResult {
                     id: pending_test.id.clone(),
@@ -219,6 +617,8 @@ impl ResponseMonitor {
                     rtt: Some(rtt),
                     error: None,
                     response_data: Some(packet.info.clone()),
+                    hop: pending_test.expected_response.hop(),
+                    responder,
                 };
                 
                 matched_tests.push((test_id.clone(), result));
@@ -228,148 +628,165 @@ impl ResponseMonitor {
         // 移除匹配的测试并发送结果
         for (test_id, result) in matched_tests {
             pending_guard.remove(&test_id);
-            let _ = self.result_sender.try_send(result);
-        }
-    }
-
-    fn matches_expected_response(&self, packet: &crate::network::CapturedPacket, expected: &ExpectedResponse) -> bool {
-        let matches = match expected {
-            ExpectedResponse::IcmpEchoReply => {
-                // 对于ICMP Echo Reply，我们检查：
-                // 1. 协议是ICMP
-                // 2. 数据包有源IP（来自我们ping的目标）
-                let is_match = packet.protocol == "icmp" && packet.src_ip.is_some();
-                // 调试信息已移除以避免崩溃
-                is_match
-            },
-            ExpectedResponse::ArpReply { target_ip } => {
-                packet.protocol == "arp" && 
-                packet.info.contains("Reply") &&
-                packet.src_ip.as_ref().map_or(false, |ip| ip == target_ip)
-            },
-            ExpectedResponse::TcpSynAck { target_port } => {
-                packet.protocol == "tcp" &&
-                packet.src_port == Some(*target_port) &&
-                packet.info.contains("SYN-ACK")
-            },
-            ExpectedResponse::UdpData => {
-                packet.protocol == "udp"
-            },
-        };
-        
-        // 调试信息已移除以避免崩溃
-        
-        matches
+            let _ = result_sender.try_send(result);
+        }
     }
 
+    // 按各目标的间隔轮流发送探测，所有目标达到测试次数后结束
     fn run_test_sender(
-        running: Arc<AtomicBool>,
-        test_config: Arc<Mutex<Option<TestConfig>>>,
-        pending_tests: Arc<Mutex<HashMap<String, PendingTest>>>,
-        interface_name: String,
+        running: &AtomicBool,
+        targets: Vec<MonitorTarget>,
+        pending_tests: &Mutex<PendingTests>,
+        interface_name: &str,
     ) {
-        use crate::network::{PacketData, PacketBuilder};
         use crate::network::interface::NetworkSender;
-        
-        let mut sender = match NetworkSender::open(&interface_name) {
+
+        let mut sender = match NetworkSender::open(interface_name) {
             Ok(s) => s,
             Err(_) => return,
         };
 
-        let mut test_count = 0u64;
+        // 发送接口自身的 MAC 和 IPv4 地址，作为探测报文的源地址
+        let (local_mac, local_ip) = crate::network::interface::NetworkInterface::local_addresses(interface_name);
+        let ident = (std::process::id() as u16) ^ (SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos() as u16);
 
-        while running.load(Ordering::Relaxed) {
-            let config = match test_config.lock() {
-                Ok(guard) => match guard.as_ref() {
-                    Some(config) => config.clone(),
-                    None => {
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                        continue;
-                    }
-                },
-                Err(_) => break,
-            };
+        // 每个目标下一次发送的时间和已发送次数
+        let start = Instant::now();
+        let mut schedule: Vec<(Instant, u64)> = vec![(start, 0); targets.len()];
 
-            // 检查是否需要停止（达到指定测试次数）
-            if config.count > 0 && test_count >= config.count {
-                running.store(false, Ordering::Relaxed);
+        while running.load(Ordering::Relaxed) {
+            let due = schedule
+                .iter()
+                .enumerate()
+                .filter(|(i, (_, sent))| targets[*i].config.count == 0 || *sent < targets[*i].config.count)
+                .min_by_key(|(_, (next, _))| *next)
+                .map(|(i, (next, _))| (i, *next));
+            let Some((index, next)) = due else {
                 break;
+            };
+            let now = Instant::now();
+            if next > now {
+                std::thread::sleep((next - now).min(std::time::Duration::from_millis(50)));
+                continue;
             }
 
-            // 生成测试ID
-            let test_id = Uuid::new_v4().to_string();
+            let target = &targets[index];
+            let sequence = schedule[index].1;
+            // 各目标使用不同的 ICMP 标识符和源端口，序号区分同一目标的各次探测
+            let probe_ident = ident.wrapping_add(index as u16);
+            let src_port = 40000 + ((ident as usize + index) % 20000) as u16;
             let start_time = Instant::now();
+            if let Ok((frame, expected_response)) = probe_frame(target, probe_ident, sequence as u16, src_port, local_mac.as_deref(), local_ip.as_deref()) {
+                if sender.send(&frame).is_ok() {
+                    let test_id = Uuid::new_v4().to_string();
+                    if let Ok(mut pending_guard) = pending_tests.lock() {
+                        pending_guard.insert(test_id.clone(), PendingTest {
+                            id: test_id,
+                            test_type: target.config.test_type.clone(),
+                            target: target.label.clone(),
+                            start_time,
+                            timeout: target.config.timeout,
+                            expected_response,
+                        });
+                    }
+                }
+            }
+            schedule[index] = (start_time + std::time::Duration::from_millis(target.config.interval), sequence + 1);
+        }
+    }
 
-            // 根据测试类型创建数据包
-            let (packet_data, expected_response) = match config.test_type.as_str() {
-                "ping" => {
-                    // 调试信息已移除以避免崩溃
-                    
-                    let mut fields = std::collections::HashMap::new();
-                    fields.insert("icmp_type".to_string(), "08".to_string()); // Echo Request
-                    fields.insert("icmp_code".to_string(), "00".to_string());
-                    fields.insert("identifier".to_string(), format!("{:04x}", test_count & 0xFFFF));
-                    fields.insert("sequence".to_string(), format!("{:04x}", test_count & 0xFFFF));
-                    fields.insert("dstIp".to_string(), config.target_ip.clone());
-                    
-                    let packet_data = PacketData {
-                        protocol: "icmp".to_string(),
-                        fields,
-                        payload: config.payload.clone(),
-                    };
-                    
-                    (packet_data, ExpectedResponse::IcmpEchoReply)
-                },
-                "arp" => {
-                    let mut fields = std::collections::HashMap::new();
-                    fields.insert("op".to_string(), "0001".to_string()); // Request
-                    fields.insert("sender_ip".to_string(), "192.168.1.100".to_string()); // 需要动态获取
-                    fields.insert("target_ip".to_string(), config.target_ip.clone());
-                    fields.insert("target_mac".to_string(), "00:00:00:00:00:00".to_string());
-                    
-                    let packet_data = PacketData {
-                        protocol: "arp".to_string(),
-                        fields,
-                        payload: None,
-                    };
-                    
-                    (packet_data, ExpectedResponse::ArpReply { target_ip: config.target_ip.clone() })
-                },
-                _ => {
-                    std::thread::sleep(std::time::Duration::from_millis(config.interval));
-                    continue;
+    // 逐跳递增 TTL 发送探测，每跳等到全部探测应答或超时后再进入下一跳，收到目标应答或达到最大跳数时结束
+    fn run_traceroute(
+        running: &AtomicBool,
+        target: MonitorTarget,
+        pending_tests: &Mutex<PendingTests>,
+        trace_reached: &Mutex<HashSet<Ipv4Addr>>,
+        interface_name: &str,
+    ) {
+        use crate::network::interface::NetworkSender;
+
+        let config = &target.config;
+        let trace = config.traceroute.clone().unwrap_or_default();
+        let protocol = match trace.protocol.as_str() {
+            "icmp" => Some(1u8),
+            "tcp" => Some(6),
+            "udp" => Some(17),
+            _ => None,
+        };
+        let (Some(protocol), Ok(mut sender)) = (protocol, NetworkSender::open(interface_name)) else {
+            return;
+        };
+        let (local_mac, local_ip) = crate::network::interface::NetworkInterface::local_addresses(interface_name);
+        let ident = (std::process::id() as u16)
+            ^ (SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos() as u16)
+            ^ (u32::from(target.ip) as u16);
+        let mut probe_index = 0u16;
+
+        'hops: for ttl in 1..=trace.max_hops.max(1) {
+            for _ in 0..trace.probes_per_hop.max(1) {
+                if !running.load(Ordering::Relaxed) {
+                    break 'hops;
                 }
-            };
+                let key = match protocol {
+                    1 => (ident, probe_index),
+                    6 => (33434u16.wrapping_add(probe_index), target.port.or(trace.dst_port).unwrap_or(80)),
+                    _ => (ident | 0x8000, target.port.or(trace.dst_port).unwrap_or(33434).wrapping_add(probe_index)),
+                };
+                probe_index = probe_index.wrapping_add(1);
+                let probe = TraceProbe { protocol, ttl, target: target.ip, key };
 
-            // 发送测试包
-            if let Ok(packet_bytes) = PacketBuilder::new(packet_data).build() {
-                if sender.send(&packet_bytes).is_ok() {
-                    // 记录待处理的测试
-                    let pending_test = PendingTest {
-                        id: test_id.clone(),
-                        test_type: config.test_type.clone(),
-                        target: config.target_ip.clone(),
-                        start_time,
-                        timeout: config.timeout,
-                        expected_response,
-                    };
-                    
+                let Ok(frame) = trace_probe_frame(&probe, config, local_mac.as_deref(), local_ip.as_deref()) else {
+                    return;
+                };
+                let start_time = Instant::now();
+                if sender.send(&frame).is_ok() {
+                    let test_id = Uuid::new_v4().to_string();
                     if let Ok(mut pending_guard) = pending_tests.lock() {
-                        pending_guard.insert(test_id, pending_test);
+                        pending_guard.insert(test_id.clone(), PendingTest {
+                            id: test_id,
+                            test_type: config.test_type.clone(),
+                            target: target.label.clone(),
+                            start_time,
+                            timeout: config.timeout,
+                            expected_response: ExpectedResponse::TraceHop(probe),
+                        });
                     }
-                    
-                    test_count += 1;
                 }
+                std::thread::sleep(std::time::Duration::from_millis(config.interval));
             }
 
-            // 等待指定间隔
-            std::thread::sleep(std::time::Duration::from_millis(config.interval));
+            // 等待本跳的探测全部应答或被超时检查线程移除
+            while running.load(Ordering::Relaxed) {
+                let waiting = pending_tests.lock().map(|guard| {
+                    guard.values().any(|t| matches!(t.expected_response, ExpectedResponse::TraceHop(p) if p.target == target.ip && p.ttl == ttl))
+                }).unwrap_or(false);
+                if !waiting {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            if trace_reached.lock().map(|reached| reached.contains(&target.ip)).unwrap_or(false) {
+                break;
+            }
+        }
+    }
+
+    // 发送线程结束时调用；最后一个结束的线程等待未决测试应答或超时，再结束会话
+    fn finish_sender(running: &AtomicBool, pending_tests: &Mutex<PendingTests>, active_senders: &AtomicUsize) {
+        if active_senders.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+        while running.load(Ordering::Relaxed) && pending_tests.lock().map(|guard| !guard.is_empty()).unwrap_or(false) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
         }
+        // 留出时间让结果收集线程取走最后的结果
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        running.store(false, Ordering::Relaxed);
     }
 
     fn run_timeout_checker(
         running: Arc<AtomicBool>,
-        pending_tests: Arc<Mutex<HashMap<String, PendingTest>>>,
+        pending_tests: Arc<Mutex<PendingTests>>,
         result_sender: Sender<TestResult>,
         _statistics: Arc<Mutex<MonitoringStatistics>>,
     ) {
@@ -400,6 +817,8 @@ impl ResponseMonitor {
                             rtt: None,
                             error: Some("请求超时".to_string()),
                             response_data: None,
+                            hop: pending_test.expected_response.hop(),
+                            responder: None,
                         };
                         
                         timed_out_tests.push(result);
@@ -429,32 +848,27 @@ impl ResponseMonitor {
         test_results: Arc<Mutex<VecDeque<TestResult>>>,
         statistics: Arc<Mutex<MonitoringStatistics>>,
     ) {
+        // 每次启动监控都使用新的累计器
+        let mut rtt_stats = RttStatistics::new();
+
         while running.load(Ordering::Relaxed) {
             match result_receiver.recv_timeout(std::time::Duration::from_millis(100)) {
                 Ok(result) => {
-                    // 更新统计信息
+                    // 更新统计信息，非 success 的结果均计为丢失
+                    let rtt = if result.status == "success" { Some(result.rtt.unwrap_or(0.0)) } else { None };
+                    rtt_stats.record(&result.target, rtt);
+
                     if let Ok(mut stats) = statistics.lock() {
-                        stats.total_tests += 1;
-                        
-                        match result.status.as_str() {
-                            "success" => {
-                                stats.successful_tests += 1;
-                                if let Some(rtt) = result.rtt {
-                                    if stats.min_rtt == 0.0 || rtt < stats.min_rtt {
-                                        stats.min_rtt = rtt;
-                                    }
-                                    if rtt > stats.max_rtt {
-                                        stats.max_rtt = rtt;
-                                    }
-                                    
-                                    // 计算平均RTT
-                                    let total_successful_rtt = stats.average_rtt * (stats.successful_tests - 1) as f64;
-                                    stats.average_rtt = (total_successful_rtt + rtt) / stats.successful_tests as f64;
-                                }
-                            },
-                            _ => {
-                                stats.failed_tests += 1;
-                            }
+                        let overall = rtt_stats.overall();
+                        stats.total_tests = overall.total;
+                        stats.successful_tests = overall.received;
+                        stats.failed_tests = overall.lost;
+                        stats.average_rtt = overall.average;
+                        stats.min_rtt = overall.min;
+                        stats.max_rtt = overall.max;
+                        stats.rtt = overall;
+                        if let Some(summary) = rtt_stats.target(&result.target) {
+                            stats.per_target.insert(result.target.clone(), summary);
                         }
                     }
                     
@@ -474,6 +888,141 @@ impl ResponseMonitor {
 
 }
 
+// 展开监控目标并解析下一跳 MAC。ARP 解析可能阻塞数秒，须在持有监控器锁和启动捕获之前调用
+pub fn resolve_targets(interface_name: &str, configs: &[TestConfig]) -> Result<Vec<MonitorTarget>> {
+    let mut targets = expand_targets(configs)?;
+    resolve_target_macs(interface_name, &mut targets)?;
+    Ok(targets)
+}
+
+// 为未指定目标 MAC 的探测解析下一跳，网段外的目标经由网关发送；ARP 测试本身使用广播
+fn resolve_target_macs(interface_name: &str, targets: &mut [MonitorTarget]) -> Result<()> {
+    let needs_mac = |t: &MonitorTarget| t.config.test_type != "arp" && t.config.target_mac.is_none();
+    let mut unresolved: Vec<Ipv4Addr> = targets.iter().filter(|t| needs_mac(t)).map(|t| t.ip).collect();
+    unresolved.sort();
+    unresolved.dedup();
+    if unresolved.is_empty() {
+        return Ok(());
+    }
+    let config = crate::network::arp::ArpScanConfig { cidr: String::new(), timeout_ms: 1000, retries: 1, interval_us: 1000 };
+    let resolved = crate::network::arp::resolve_next_hops(interface_name, &unresolved, &config, &AtomicBool::new(true))?;
+    for target in targets.iter_mut().filter(|t| needs_mac(t)) {
+        target.config.target_mac = resolved.get(&target.ip).cloned();
+    }
+    Ok(())
+}
+
+// 构建 ping/arp/tcp_connect/udp_echo 探测帧及对应的预期应答
+fn probe_frame(
+    target: &MonitorTarget,
+    identifier: u16,
+    sequence: u16,
+    src_port: u16,
+    local_mac: Option<&str>,
+    local_ip: Option<&str>,
+) -> Result<(Vec<u8>, ExpectedResponse)> {
+    use crate::network::{PacketData, PacketBuilder};
+
+    let config = &target.config;
+    if config.test_type == "arp" {
+        let packet_data = crate::network::arp::request_packet(
+            local_mac.unwrap_or("00:00:00:00:00:00"),
+            local_ip.unwrap_or("0.0.0.0"),
+            &target.ip.to_string(),
+        );
+        let frame = PacketBuilder::new(packet_data).build()?;
+        return Ok((frame, ExpectedResponse::ArpReply { target_ip: target.ip.to_string() }));
+    }
+
+    let mut fields = HashMap::new();
+    fields.insert("dstIp".to_string(), target.ip.to_string());
+    // 下一跳 MAC 在会话启动时解析；只有本网段内未应答 ARP 的目标才广播
+    fields.insert("dst_mac".to_string(), config.target_mac.clone().unwrap_or_else(|| "ff:ff:ff:ff:ff:ff".to_string()));
+    if let Some(mac) = local_mac {
+        fields.insert("src_mac".to_string(), mac.to_string());
+    }
+    if let Some(ip) = local_ip {
+        fields.insert("srcIp".to_string(), ip.to_string());
+    }
+    let target_port = target.port.unwrap_or(0);
+    let (protocol, expected) = match config.test_type.as_str() {
+        "ping" => {
+            fields.insert("icmp_type".to_string(), "08".to_string()); // Echo Request
+            fields.insert("icmp_code".to_string(), "00".to_string());
+            fields.insert("identifier".to_string(), format!("{:04x}", identifier));
+            fields.insert("sequence".to_string(), format!("{:04x}", sequence));
+            ("icmp", ExpectedResponse::IcmpEchoReply { target_ip: target.ip, identifier, sequence })
+        }
+        "tcp_connect" => {
+            fields.insert("srcPort".to_string(), src_port.to_string());
+            fields.insert("dstPort".to_string(), target_port.to_string());
+            fields.insert("seq".to_string(), sequence.to_string());
+            fields.insert("flag_syn".to_string(), "1".to_string());
+            ("tcp", ExpectedResponse::TcpSynAck { target_ip: target.ip, target_port, src_port })
+        }
+        "udp_echo" => {
+            fields.insert("srcPort".to_string(), src_port.to_string());
+            fields.insert("dstPort".to_string(), target_port.to_string());
+            ("udp", ExpectedResponse::UdpData { target_ip: target.ip, target_port, src_port })
+        }
+        other => return Err(anyhow!("不支持的测试类型: {}", other)),
+    };
+
+    let builder = PacketBuilder::new(PacketData {
+        protocol: protocol.to_string(),
+        fields,
+        payload: config.payload.clone(),
+    });
+    let mut frame = builder.build()?;
+    builder.fixup_ipv4_frame(&mut frame)?;
+    Ok((frame, expected))
+}
+
+// 构建路由追踪探测帧，并补齐传输层校验和，否则目标会丢弃 TCP/UDP 探测而无最终应答
+fn trace_probe_frame(probe: &TraceProbe, config: &TestConfig, local_mac: Option<&str>, local_ip: Option<&str>) -> Result<Vec<u8>> {
+    use crate::network::{PacketData, PacketBuilder};
+
+    let mut fields = HashMap::new();
+    fields.insert("ttl".to_string(), probe.ttl.to_string());
+    fields.insert("dstIp".to_string(), probe.target.to_string());
+    // 网段外的目标在会话启动时已解析为网关 MAC
+    fields.insert("dst_mac".to_string(), config.target_mac.clone().unwrap_or_else(|| "ff:ff:ff:ff:ff:ff".to_string()));
+    if let Some(mac) = local_mac {
+        fields.insert("src_mac".to_string(), mac.to_string());
+    }
+    if let Some(ip) = local_ip {
+        fields.insert("srcIp".to_string(), ip.to_string());
+    }
+    let protocol = match probe.protocol {
+        1 => {
+            fields.insert("icmp_type".to_string(), "08".to_string());
+            fields.insert("identifier".to_string(), format!("{:04x}", probe.key.0));
+            fields.insert("sequence".to_string(), format!("{:04x}", probe.key.1));
+            "icmp"
+        }
+        6 => {
+            fields.insert("srcPort".to_string(), probe.key.0.to_string());
+            fields.insert("dstPort".to_string(), probe.key.1.to_string());
+            fields.insert("flag_syn".to_string(), "1".to_string());
+            "tcp"
+        }
+        _ => {
+            fields.insert("srcPort".to_string(), probe.key.0.to_string());
+            fields.insert("dstPort".to_string(), probe.key.1.to_string());
+            "udp"
+        }
+    };
+
+    let builder = PacketBuilder::new(PacketData {
+        protocol: protocol.to_string(),
+        fields,
+        payload: config.payload.clone(),
+    });
+    let mut frame = builder.build()?;
+    builder.fixup_ipv4_frame(&mut frame)?;
+    Ok(frame)
+}
+
 impl Default for ResponseMonitor {
     fn default() -> Self {
         Self::new().unwrap()
@@ -492,9 +1041,15 @@ impl MonitorManager {
         }
     }
 
-    pub fn start_monitoring(&self, interface_name: String, test_config: TestConfig) -> Result<()> {
+    pub fn start_monitoring(&self, interface_name: String, test_config: TestConfig, packets: Receiver<CapturedPacket>) -> Result<()> {
+        let targets = resolve_targets(&interface_name, &[test_config])?;
+        let mut monitor = self.monitor.lock().unwrap();
+        monitor.start_session(interface_name, targets, packets)
+    }
+
+    pub fn start_session(&self, interface_name: String, targets: Vec<MonitorTarget>, packets: Receiver<CapturedPacket>) -> Result<()> {
         let mut monitor = self.monitor.lock().unwrap();
-        monitor.start_monitoring(interface_name, test_config)
+        monitor.start_session(interface_name, targets, packets)
     }
 
     pub fn stop_monitoring(&self) {
@@ -523,7 +1078,15 @@ impl MonitorManager {
         }
     }
 
-    pub fn process_received_packet(&self, packet: &crate::network::CapturedPacket) {
+    pub fn get_traceroute_hops(&self, target: Option<&str>) -> Vec<TracerouteHop> {
+        if let Ok(monitor) = self.monitor.lock() {
+            monitor.get_traceroute_hops(target)
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn process_received_packet(&self, packet: &CapturedPacket) {
         if let Ok(monitor) = self.monitor.lock() {
             monitor.process_received_packet(packet);
         }
@@ -534,4 +1097,200 @@ impl Default for MonitorManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::packet_builder::test_frame;
+
+    const TARGET: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 9);
+    // 本机地址
+    const LOCAL: &str = "10.0.0.100";
+
+    fn result(hop: u8, responder: Option<&str>, rtt: Option<f64>) -> TestResult {
+        TestResult {
+            id: Uuid::new_v4().to_string(),
+            timestamp: 0,
+            test_type: "traceroute".to_string(),
+            target: TARGET.to_string(),
+            status: if rtt.is_some() { "success" } else { "timeout" }.to_string(),
+            rtt,
+            error: None,
+            response_data: None,
+            hop: Some(hop),
+            responder: responder.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn time_exceeded_matches_quoted_udp_probe() {
+        let probe = TraceProbe { protocol: 17, ttl: 1, target: TARGET, key: (40000, 33434) };
+        let exceeded = test_frame("icmp", "10.0.0.1", LOCAL, vec![
+            ("icmp_type", "0b"), ("orig_dst_ip", "10.0.0.9"), ("orig_src_port", "40000"), ("orig_dst_port", "33434"),
+        ]);
+        assert_eq!(match_trace_reply(&exceeded, &probe), Some(TraceReply { responder: "10.0.0.1".to_string(), final_reply: false }));
+
+        let other_probe = TraceProbe { key: (40000, 33435), ..probe };
+        assert_eq!(match_trace_reply(&exceeded, &other_probe), None);
+
+        // 目标返回端口不可达，追踪结束
+        let unreachable = test_frame("icmp", "10.0.0.9", LOCAL, vec![
+            ("icmp_type", "03"), ("icmp_code", "03"), ("orig_dst_ip", "10.0.0.9"), ("orig_src_port", "40000"), ("orig_dst_port", "33434"),
+        ]);
+        assert!(match_trace_reply(&unreachable, &probe).unwrap().final_reply);
+    }
+
+    #[test]
+    fn final_replies_from_target() {
+        let tcp_probe = TraceProbe { protocol: 6, ttl: 5, target: TARGET, key: (33434, 443) };
+        let syn_ack = test_frame("tcp", "10.0.0.9", LOCAL, vec![("srcPort", "443"), ("dstPort", "33434"), ("flag_ack", "1")]);
+        assert!(match_trace_reply(&syn_ack, &tcp_probe).unwrap().final_reply);
+        let ack_only = test_frame("tcp", "10.0.0.9", LOCAL, vec![("srcPort", "443"), ("dstPort", "33434"), ("flag_syn", "0"), ("flag_ack", "1")]);
+        assert_eq!(match_trace_reply(&ack_only, &tcp_probe), None);
+
+        let icmp_probe = TraceProbe { protocol: 1, ttl: 5, target: TARGET, key: (0x1234, 7) };
+        let echo = test_frame("icmp", "10.0.0.9", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0007")]);
+        assert!(match_trace_reply(&echo, &icmp_probe).unwrap().final_reply);
+
+        // 引用的原始 Echo Request 以标识符和序号匹配
+        let exceeded = test_frame("icmp", "10.0.0.1", LOCAL, vec![
+            ("icmp_type", "0b"), ("icmp_original", "4500001c0000400001010000 0a000064 0a000009 0800f7cb 1234 0007"),
+        ]);
+        assert!(!match_trace_reply(&exceeded, &icmp_probe).unwrap().final_reply);
+    }
+
+    fn config(test_type: &str, target_ip: &str) -> TestConfig {
+        TestConfig {
+            test_type: test_type.to_string(),
+            target_ip: target_ip.to_string(),
+            target_mac: None,
+            timeout: 1000,
+            interval: 1000,
+            count: 0,
+            payload: None,
+            traceroute: None,
+        }
+    }
+
+    #[test]
+    fn targets_expand_cidrs_and_ports() {
+        let targets = expand_targets(&[config("ping", "10.0.0.9, 10.0.1.0/30"), config("tcp_connect", "10.0.0.9:443")]).unwrap();
+        let labels: Vec<&str> = targets.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, vec!["10.0.0.9", "10.0.1.1", "10.0.1.2", "10.0.0.9:443"]);
+        assert_eq!(targets[3].port, Some(443));
+
+        assert!(expand_targets(&[config("udp_echo", "10.0.0.9")]).is_err());
+        assert!(expand_targets(&[config("ping", "10.0.0.9:0")]).is_err());
+        assert!(expand_targets(&[config("smtp", "10.0.0.9")]).is_err());
+        assert!(expand_targets(&[]).is_err());
+
+        assert_eq!(capture_protocol(&[config("ping", "10.0.0.1"), config("ping", "10.0.0.2")]), "icmp");
+        assert_eq!(capture_protocol(&[config("ping", "10.0.0.1"), config("arp", "10.0.0.2")]), "all");
+    }
+
+    #[test]
+    fn replies_match_their_own_target_and_probe() {
+        let targets = expand_targets(&[config("ping", "10.0.0.9"), config("tcp_connect", "10.0.0.9:443")]).unwrap();
+
+        let (_, expected) = probe_frame(&targets[0], 0x1234, 7, 45000, None, Some("10.0.0.100")).unwrap();
+        let echo = test_frame("icmp", "10.0.0.9", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0007")]);
+        assert!(matches_expected_response(&CapturedPacket::from_frame(echo), &expected));
+        let other_host = test_frame("icmp", "10.0.0.8", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0007")]);
+        assert!(!matches_expected_response(&CapturedPacket::from_frame(other_host), &expected));
+        let other_seq = test_frame("icmp", "10.0.0.9", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0008")]);
+        assert!(!matches_expected_response(&CapturedPacket::from_frame(other_seq), &expected));
+
+        let (syn, expected) = probe_frame(&targets[1], 0x1234, 7, 45000, None, Some("10.0.0.100")).unwrap();
+        assert_eq!(syn[47], 0x02);
+        let syn_ack = test_frame("tcp", "10.0.0.9", LOCAL, vec![("srcPort", "443"), ("dstPort", "45000"), ("flag_ack", "1")]);
+        assert!(matches_expected_response(&CapturedPacket::from_frame(syn_ack), &expected));
+        let rst = test_frame("tcp", "10.0.0.9", LOCAL, vec![("srcPort", "443"), ("dstPort", "45000"), ("flag_syn", "0"), ("flag_rst", "1")]);
+        assert!(!matches_expected_response(&CapturedPacket::from_frame(rst), &expected));
+    }
+
+    #[test]
+    fn matcher_consumes_the_capture_stream() {
+        let monitor = ResponseMonitor::new().unwrap();
+        let targets = expand_targets(&[config("ping", "10.0.0.9")]).unwrap();
+        let (_, expected_response) = probe_frame(&targets[0], 0x1234, 1, 45000, None, Some("10.0.0.100")).unwrap();
+        monitor.pending_tests.lock().unwrap().insert("t1".to_string(), PendingTest {
+            id: "t1".to_string(),
+            test_type: "ping".to_string(),
+            target: "10.0.0.9".to_string(),
+            start_time: Instant::now(),
+            timeout: 1000,
+            expected_response,
+        });
+
+        let (packet_tx, packet_rx) = bounded(8);
+        let running = Arc::new(AtomicBool::new(true));
+        let matcher = {
+            let (running, pending, reached, results) =
+                (running.clone(), monitor.pending_tests.clone(), monitor.trace_reached.clone(), monitor.result_sender.clone());
+            std::thread::spawn(move || ResponseMonitor::run_packet_matcher(running, packet_rx, pending, reached, results))
+        };
+        packet_tx.send(CapturedPacket::from_frame(test_frame("icmp", "10.0.0.9", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0001")]))).unwrap();
+
+        let result = monitor.result_receiver.recv_timeout(std::time::Duration::from_secs(2)).unwrap();
+        assert_eq!((result.id.as_str(), result.status.as_str()), ("t1", "success"));
+        assert!(monitor.pending_tests.lock().unwrap().is_empty());
+        running.store(false, Ordering::Relaxed);
+        matcher.join().unwrap();
+    }
+
+    #[test]
+    fn pending_tests_are_indexed_by_reply_key() {
+        let pending = |expected_response| PendingTest {
+            id: String::new(),
+            test_type: String::new(),
+            target: String::new(),
+            start_time: Instant::now(),
+            timeout: 1000,
+            expected_response,
+        };
+        let mut tests = PendingTests::default();
+        tests.insert("echo".to_string(), pending(ExpectedResponse::IcmpEchoReply { target_ip: TARGET, identifier: 0x1234, sequence: 7 }));
+        tests.insert("syn".to_string(), pending(ExpectedResponse::TcpSynAck { target_ip: TARGET, target_port: 443, src_port: 45000 }));
+        tests.insert("hop".to_string(), pending(ExpectedResponse::TraceHop(TraceProbe { protocol: 17, ttl: 1, target: TARGET, key: (40000, 33434) })));
+
+        let ids = |frame: Vec<u8>, tests: &PendingTests| -> Vec<String> {
+            tests.candidates(&CapturedPacket::from_frame(frame)).into_iter().map(|(id, _)| id.clone()).collect()
+        };
+        let echo = test_frame("icmp", "10.0.0.9", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0007")]);
+        assert_eq!(ids(echo.clone(), &tests), vec!["echo"]);
+        let syn_ack = test_frame("tcp", "10.0.0.9", LOCAL, vec![("srcPort", "443"), ("dstPort", "45000"), ("flag_ack", "1")]);
+        assert_eq!(ids(syn_ack, &tests), vec!["syn"]);
+        let exceeded = test_frame("icmp", "10.0.0.1", LOCAL, vec![
+            ("icmp_type", "0b"), ("orig_dst_ip", "10.0.0.9"), ("orig_src_port", "40000"), ("orig_dst_port", "33434"),
+        ]);
+        assert_eq!(ids(exceeded, &tests), vec!["hop"]);
+        let other_host = test_frame("icmp", "10.0.0.8", LOCAL, vec![("icmp_type", "00"), ("identifier", "1234"), ("sequence", "0007")]);
+        assert!(ids(other_host, &tests).is_empty());
+
+        // 移除后索引同步清理
+        tests.remove("echo");
+        assert!(ids(echo, &tests).is_empty());
+        assert_eq!(tests.index.len(), 2);
+    }
+
+    #[test]
+    fn hops_group_results_by_ttl() {
+        let monitor = ResponseMonitor::new().unwrap();
+        {
+            let mut results = monitor.test_results.lock().unwrap();
+            // 按时间倒序保存
+            for r in [
+                result(1, Some("10.0.0.1"), Some(1.0)),
+                result(1, None, None),
+                result(2, Some("10.0.0.9"), Some(3.0)),
+            ] {
+                results.push_front(r);
+            }
+        }
+        let hops = monitor.get_traceroute_hops(None);
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0], TracerouteHop { ttl: 1, addresses: vec!["10.0.0.1".to_string()], rtts: vec![Some(1.0), None], reached: false });
+        assert!(hops[1].reached);
+        assert!(monitor.get_traceroute_hops(Some("10.0.0.10")).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+
+// 百分位数基于最近的样本计算，均值、标准差等基于全部样本
+const MAX_SAMPLES: usize = 100_000;
+// 直方图各桶的上界（毫秒），最后一个桶收集超过最大上界的样本
+pub const HISTOGRAM_BOUNDS_MS: [f64; 12] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HistogramBucket {
+    // 桶的上界（毫秒，含），None 表示无上界
+    pub upper_ms: Option<f64>,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RttSummary {
+    pub total: u64,
+    pub received: u64,
+    pub lost: u64,
+    pub loss_percent: f64,
+    pub min: f64,
+    pub max: f64,
+    pub average: f64,
+    pub std_dev: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub p999: f64,
+    // RFC 3550 抖动：相邻两次 RTT 差值的指数平滑，增益 1/16
+    pub jitter: f64,
+    pub current_loss_streak: u64,
+    pub max_loss_streak: u64,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+// 单个目标的 RTT 累计器
+#[derive(Debug, Clone)]
+pub struct RttAccumulator {
+    window: VecDeque<f64>,
+    // 与 window 内容相同的有序副本，插入和淘汰时用二分查找维护
+    sorted: Vec<f64>,
+    received: u64,
+    lost: u64,
+    min: f64,
+    max: f64,
+    // Welford 算法的均值与偏差平方和
+    mean: f64,
+    m2: f64,
+    jitter: f64,
+    last_rtt: Option<f64>,
+    current_loss_streak: u64,
+    max_loss_streak: u64,
+    histogram: [u64; HISTOGRAM_BOUNDS_MS.len() + 1],
+}
+
+impl Default for RttAccumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RttAccumulator {
+    pub fn new() -> Self {
+        Self {
+            window: VecDeque::new(),
+            sorted: Vec::new(),
+            received: 0,
+            lost: 0,
+            min: 0.0,
+            max: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            jitter: 0.0,
+            last_rtt: None,
+            current_loss_streak: 0,
+            max_loss_streak: 0,
+            histogram: [0; HISTOGRAM_BOUNDS_MS.len() + 1],
+        }
+    }
+
+    // 记录一次测试：Some 为收到应答的 RTT（毫秒），None 为丢失
+    pub fn record(&mut self, rtt: Option<f64>) {
+        let Some(rtt) = rtt else {
+            self.lost += 1;
+            self.current_loss_streak += 1;
+            self.max_loss_streak = self.max_loss_streak.max(self.current_loss_streak);
+            return;
+        };
+
+        self.current_loss_streak = 0;
+        self.received += 1;
+        if self.received == 1 || rtt < self.min {
+            self.min = rtt;
+        }
+        if rtt > self.max {
+            self.max = rtt;
+        }
+        let delta = rtt - self.mean;
+        self.mean += delta / self.received as f64;
+        self.m2 += delta * (rtt - self.mean);
+
+        if let Some(last) = self.last_rtt {
+            self.jitter += ((rtt - last).abs() - self.jitter) / 16.0;
+        }
+        self.last_rtt = Some(rtt);
+
+        let bucket = HISTOGRAM_BOUNDS_MS.iter().position(|upper| rtt <= *upper).unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+        self.histogram[bucket] += 1;
+
+        if self.window.len() == MAX_SAMPLES {
+            if let Some(oldest) = self.window.pop_front() {
+                let index = self.sorted.partition_point(|v| *v < oldest);
+                self.sorted.remove(index);
+            }
+        }
+        self.window.push_back(rtt);
+        let index = self.sorted.partition_point(|v| *v < rtt);
+        self.sorted.insert(index, rtt);
+    }
+
+    // 最近邻秩法百分位数，无样本时为 0
+    pub fn percentile(&self, p: f64) -> f64 {
+        if self.sorted.is_empty() {
+            return 0.0;
+        }
+        let rank = ((p / 100.0) * self.sorted.len() as f64).ceil() as usize;
+        self.sorted[rank.clamp(1, self.sorted.len()) - 1]
+    }
+
+    pub fn summary(&self) -> RttSummary {
+        let total = self.received + self.lost;
+        RttSummary {
+            total,
+            received: self.received,
+            lost: self.lost,
+            loss_percent: if total == 0 { 0.0 } else { self.lost as f64 * 100.0 / total as f64 },
+            min: self.min,
+            max: self.max,
+            average: self.mean,
+            std_dev: if self.received > 1 { (self.m2 / (self.received - 1) as f64).sqrt() } else { 0.0 },
+            p50: self.percentile(50.0),
+            p90: self.percentile(90.0),
+            p99: self.percentile(99.0),
+            p999: self.percentile(99.9),
+            jitter: self.jitter,
+            current_loss_streak: self.current_loss_streak,
+            max_loss_streak: self.max_loss_streak,
+            histogram: self.histogram
+                .iter()
+                .enumerate()
+                .map(|(i, count)| HistogramBucket { upper_ms: HISTOGRAM_BOUNDS_MS.get(i).copied(), count: *count })
+                .collect(),
+        }
+    }
+}
+
+// 全部目标汇总及按目标分别统计
+#[derive(Debug, Clone, Default)]
+pub struct RttStatistics {
+    overall: RttAccumulator,
+    per_target: HashMap<String, RttAccumulator>,
+}
+
+impl RttStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, target: &str, rtt: Option<f64>) {
+        self.overall.record(rtt);
+        self.per_target.entry(target.to_string()).or_default().record(rtt);
+    }
+
+    // 抖动只在同一目标的相邻样本间有意义，汇总的抖动取各目标抖动的平均值（只计至少两个样本的目标）
+    pub fn overall(&self) -> RttSummary {
+        let jitters: Vec<f64> = self.per_target.values().filter(|acc| acc.received > 1).map(|acc| acc.jitter).collect();
+        RttSummary {
+            jitter: if jitters.is_empty() { 0.0 } else { jitters.iter().sum::<f64>() / jitters.len() as f64 },
+            ..self.overall.summary()
+        }
+    }
+
+    pub fn target(&self, target: &str) -> Option<RttSummary> {
+        self.per_target.get(target).map(RttAccumulator::summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_and_spread() {
+        let mut acc = RttAccumulator::new();
+        // 乱序写入 1..=100
+        for i in (1..=100).rev() {
+            acc.record(Some(i as f64));
+        }
+        let summary = acc.summary();
+        assert_eq!((summary.p50, summary.p90, summary.p99, summary.p999), (50.0, 90.0, 99.0, 100.0));
+        assert_eq!((summary.min, summary.max, summary.average), (1.0, 100.0, 50.5));
+        assert!((summary.std_dev - 29.011).abs() < 0.001);
+        assert_eq!(summary.histogram.iter().map(|b| b.count).sum::<u64>(), 100);
+        assert_eq!(summary.histogram[0], HistogramBucket { upper_ms: Some(1.0), count: 1 });
+        assert_eq!(summary.histogram.last().unwrap().upper_ms, None);
+    }
+
+    #[test]
+    fn loss_streaks_and_jitter() {
+        let mut acc = RttAccumulator::new();
+        for rtt in [Some(10.0), None, None, Some(26.0), None] {
+            acc.record(rtt);
+        }
+        let summary = acc.summary();
+        assert_eq!((summary.total, summary.received, summary.lost), (5, 2, 3));
+        assert_eq!(summary.loss_percent, 60.0);
+        assert_eq!((summary.current_loss_streak, summary.max_loss_streak), (1, 2));
+        assert_eq!(summary.jitter, 1.0);
+    }
+
+    #[test]
+    fn per_target_statistics() {
+        let mut stats = RttStatistics::new();
+        stats.record("10.0.0.1", Some(5.0));
+        stats.record("10.0.0.2", None);
+        assert_eq!(stats.overall().total, 2);
+        assert_eq!(stats.target("10.0.0.1").unwrap().received, 1);
+        assert_eq!(stats.target("10.0.0.2").unwrap().loss_percent, 100.0);
+        assert!(stats.target("10.0.0.3").is_none());
+        assert_eq!(RttAccumulator::new().summary().p50, 0.0);
+    }
+
+    #[test]
+    fn overall_jitter_averages_per_target_jitter() {
+        let mut stats = RttStatistics::new();
+        // 两个目标各自稳定，交错写入时相邻样本相差很大
+        for _ in 0..4 {
+            stats.record("10.0.0.1", Some(10.0));
+            stats.record("10.0.0.2", Some(100.0));
+        }
+        stats.record("10.0.0.3", Some(50.0));
+        assert_eq!(stats.target("10.0.0.1").unwrap().jitter, 0.0);
+        assert_eq!(stats.overall().jitter, 0.0);
+
+        stats.record("10.0.0.1", Some(26.0));
+        assert_eq!(stats.overall().jitter, 0.5);
+    }
+}
@@ -0,0 +1,566 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::network::{PacketBuilder, PacketData};
+use crate::network::arp::{self, ArpScanConfig, MAX_SCAN_HOSTS};
+use crate::network::interface::NetworkInterface;
+
+// 本网段内未应答 ARP 的主机仍以广播帧探测；网段外的主机总是经由网关 MAC
+const BROADCAST_MAC: &str = "ff:ff:ff:ff:ff:ff";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanType {
+    IcmpPing,
+    ArpPing,
+    TcpSyn,
+    Udp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanState {
+    // 主机发现的结果
+    Up,
+    Down,
+    // 端口扫描的结果
+    Open,
+    Closed,
+    Filtered,
+    // UDP 无应答时无法区分开放和被过滤
+    OpenFiltered,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanConfig {
+    // 逗号分隔的地址或 CIDR 网段
+    pub targets: String,
+    pub scan_type: ScanType,
+    // 端口列表，如 "22,80,8000-8100"；主机发现时忽略
+    #[serde(default)]
+    pub ports: String,
+    // 每秒发送的探测数，0 表示不限速
+    #[serde(default = "default_rate")]
+    pub rate: u32,
+    // 未应答探测的重发次数
+    #[serde(default)]
+    pub retries: u32,
+    // 每轮发送结束后等待应答的时间
+    #[serde(default = "default_timeout")]
+    pub timeout_ms: u64,
+    // 下一跳 MAC；未填写时先用 ARP 解析目标地址，解析失败的目标使用广播地址。
+    // 目标不在本网段时应填写网关 MAC
+    #[serde(default)]
+    pub next_hop_mac: Option<String>,
+}
+
+fn default_rate() -> u32 {
+    100
+}
+
+fn default_timeout() -> u64 {
+    1000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanResult {
+    pub host: String,
+    pub port: Option<u16>,
+    pub protocol: String,
+    pub state: ScanState,
+    // 最近一次探测到应答的时间（毫秒），无应答时为空
+    pub rtt_ms: Option<f64>,
+    // 发往该主机的下一跳 MAC，网段外的主机为网关 MAC
+    pub mac: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScanTaskStatus {
+    pub task_id: String,
+    pub scan_type: ScanType,
+    pub start_time: u64,
+    pub total_probes: u64,
+    pub sent_probes: u64,
+    pub responses: u64,
+    pub running: bool,
+    pub completed: bool,
+    // 完成时间（Unix 秒），超过保留时间后任务被清理
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+pub struct ScanTaskHandle {
+    pub status: Arc<Mutex<ScanTaskStatus>>,
+    pub results: Arc<Mutex<Vec<ScanResult>>>,
+    pub running: Arc<AtomicBool>,
+}
+
+// 解析端口列表，去重并保持书写顺序
+pub fn parse_ports(spec: &str) -> Result<Vec<u16>> {
+    let mut seen = HashSet::new();
+    let mut ports = Vec::new();
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let invalid = || anyhow!("无效的端口: {}", item);
+        let (start, end) = match item.split_once('-') {
+            Some((a, b)) => (a.trim().parse::<u16>().map_err(|_| invalid())?, b.trim().parse::<u16>().map_err(|_| invalid())?),
+            None => {
+                let port = item.parse::<u16>().map_err(|_| invalid())?;
+                (port, port)
+            }
+        };
+        if start == 0 || start > end {
+            return Err(invalid());
+        }
+        ports.extend((start..=end).filter(|p| seen.insert(*p)));
+    }
+    if ports.is_empty() {
+        return Err(anyhow!("端口列表为空"));
+    }
+    Ok(ports)
+}
+
+// 解析逗号分隔的地址或网段，去重并保持书写顺序
+pub fn parse_targets(spec: &str) -> Result<Vec<Ipv4Addr>> {
+    let mut seen = HashSet::new();
+    let mut targets = Vec::new();
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        targets.extend(arp::parse_cidr_hosts(item)?.into_iter().filter(|ip| seen.insert(*ip)));
+        if targets.len() > MAX_SCAN_HOSTS {
+            return Err(anyhow!("扫描目标超过 {} 个地址", MAX_SCAN_HOSTS));
+        }
+    }
+    if targets.is_empty() {
+        return Err(anyhow!("扫描目标为空"));
+    }
+    Ok(targets)
+}
+
+// 识别本任务探测所需的标识：TCP/UDP 源端口和 ICMP 标识符
+#[derive(Debug, Clone, Copy)]
+struct ProbeContext {
+    scan_type: ScanType,
+    local_ip: Ipv4Addr,
+    src_port: u16,
+    icmp_id: u16,
+}
+
+type ProbeKey = (Ipv4Addr, Option<u16>);
+
+// 将收到的帧归类为某个探测的应答；与本任务无关时返回 None
+fn classify_response(frame: &[u8], ctx: &ProbeContext) -> Option<(ProbeKey, ScanState)> {
+    if frame.len() < 34 || frame[12..14] != [0x08, 0x00] {
+        return None;
+    }
+    let ip = &frame[14..];
+    let ihl = ((ip[0] & 0x0F) as usize) * 4;
+    if ihl < 20 || ip.len() < ihl + 8 || ip[16..20] != ctx.local_ip.octets() {
+        return None;
+    }
+    // 分片的后续部分不含传输层头部
+    if u16::from_be_bytes([ip[6], ip[7]]) & 0x1FFF != 0 {
+        return None;
+    }
+    let src = Ipv4Addr::new(ip[12], ip[13], ip[14], ip[15]);
+    let l4 = &ip[ihl..];
+    let port_pair = |b: &[u8]| (u16::from_be_bytes([b[0], b[1]]), u16::from_be_bytes([b[2], b[3]]));
+
+    match (ip[9], ctx.scan_type) {
+        (6, ScanType::TcpSyn) if l4.len() >= 14 => {
+            let (sport, dport) = port_pair(l4);
+            if dport != ctx.src_port {
+                return None;
+            }
+            let flags = l4[13];
+            let state = if flags & 0x12 == 0x12 {
+                ScanState::Open
+            } else if flags & 0x04 != 0 {
+                ScanState::Closed
+            } else {
+                return None;
+            };
+            Some(((src, Some(sport)), state))
+        }
+        (17, ScanType::Udp) => {
+            let (sport, dport) = port_pair(l4);
+            (dport == ctx.src_port).then_some(((src, Some(sport)), ScanState::Open))
+        }
+        (1, ScanType::IcmpPing) => {
+            let id = u16::from_be_bytes([l4[4], l4[5]]);
+            (l4[0] == 0 && id == ctx.icmp_id).then_some(((src, None), ScanState::Up))
+        }
+        // 目的不可达：从携带的原始数据报中找出对应的探测
+        (1, ScanType::TcpSyn | ScanType::Udp) if l4[0] == 3 => {
+            let code = l4[1];
+            let inner = &l4[8..];
+            let inner_ihl = ((*inner.first()? & 0x0F) as usize) * 4;
+            let inner_l4 = inner.get(inner_ihl..inner_ihl + 4)?;
+            let expected_proto = if ctx.scan_type == ScanType::TcpSyn { 6 } else { 17 };
+            if inner.len() < 20 || inner[9] != expected_proto || inner[12..16] != ctx.local_ip.octets() {
+                return None;
+            }
+            let (sport, dport) = port_pair(inner_l4);
+            if sport != ctx.src_port {
+                return None;
+            }
+            let target = Ipv4Addr::new(inner[16], inner[17], inner[18], inner[19]);
+            let state = match (ctx.scan_type, code) {
+                (ScanType::Udp, 3) => ScanState::Closed,
+                (_, 1 | 2 | 3 | 9 | 10 | 13) => ScanState::Filtered,
+                _ => return None,
+            };
+            Some(((target, Some(dport)), state))
+        }
+        _ => None,
+    }
+}
+
+// 无应答时的结论
+fn silent_state(scan_type: ScanType) -> ScanState {
+    match scan_type {
+        ScanType::IcmpPing | ScanType::ArpPing => ScanState::Down,
+        ScanType::TcpSyn => ScanState::Filtered,
+        ScanType::Udp => ScanState::OpenFiltered,
+    }
+}
+
+fn protocol_name(scan_type: ScanType) -> &'static str {
+    match scan_type {
+        ScanType::IcmpPing => "icmp",
+        ScanType::ArpPing => "arp",
+        ScanType::TcpSyn => "tcp",
+        ScanType::Udp => "udp",
+    }
+}
+
+fn probe_packet(ctx: &ProbeContext, key: ProbeKey, local_mac: &str, dst_mac: &str, seq: u32) -> PacketData {
+    let (host, port) = key;
+    let mut fields: HashMap<String, String> = [
+        ("dst_mac", dst_mac.to_string()),
+        ("src_mac", local_mac.to_string()),
+        ("srcIp", ctx.local_ip.to_string()),
+        ("dstIp", host.to_string()),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+
+    let protocol = match ctx.scan_type {
+        ScanType::IcmpPing | ScanType::ArpPing => {
+            fields.insert("icmp_type".to_string(), "08".to_string());
+            fields.insert("identifier".to_string(), format!("{:04x}", ctx.icmp_id));
+            fields.insert("sequence".to_string(), format!("{:04x}", seq & 0xFFFF));
+            "icmp"
+        }
+        ScanType::TcpSyn => {
+            fields.insert("srcPort".to_string(), ctx.src_port.to_string());
+            fields.insert("dstPort".to_string(), port.unwrap_or(0).to_string());
+            fields.insert("seq".to_string(), seq.to_string());
+            fields.insert("flag_syn".to_string(), "1".to_string());
+            fields.insert("window_size".to_string(), "1024".to_string());
+            fields.insert("tcp_options".to_string(), "mss:1460".to_string());
+            "tcp"
+        }
+        ScanType::Udp => {
+            fields.insert("srcPort".to_string(), ctx.src_port.to_string());
+            fields.insert("dstPort".to_string(), port.unwrap_or(0).to_string());
+            "udp"
+        }
+    };
+    PacketData { protocol: protocol.to_string(), fields, payload: None }
+}
+
+// 构建探测帧并补齐 TCP/UDP/ICMP 校验和，否则目标会直接丢弃
+fn build_probe(packet: PacketData) -> Result<Vec<u8>> {
+    let builder = PacketBuilder::new(packet);
+    let mut frame = builder.build()?;
+    builder.fixup_ipv4_frame(&mut frame)?;
+    Ok(frame)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+pub fn new_status(task_id: &str, scan_type: ScanType) -> ScanTaskStatus {
+    ScanTaskStatus {
+        task_id: task_id.to_string(),
+        scan_type,
+        start_time: now_millis() / 1000,
+        total_probes: 0,
+        sent_probes: 0,
+        responses: 0,
+        running: true,
+        completed: false,
+        completed_at: None,
+        error: None,
+    }
+}
+
+// 阻塞执行扫描，结果边收边写入 results；running 置为 false 时停止发送并结束
+pub fn run_scan(
+    interface_name: &str,
+    config: &ScanConfig,
+    status: &Arc<Mutex<ScanTaskStatus>>,
+    results: &Arc<Mutex<Vec<ScanResult>>>,
+    running: &AtomicBool,
+) -> Result<()> {
+    let targets = parse_targets(&config.targets)?;
+    let ports = match config.scan_type {
+        ScanType::TcpSyn | ScanType::Udp => parse_ports(&config.ports)?.into_iter().map(Some).collect(),
+        ScanType::IcmpPing | ScanType::ArpPing => vec![None],
+    };
+    let total = targets.len() as u64 * ports.len() as u64;
+    if let Ok(mut s) = status.lock() {
+        s.total_probes = total;
+    }
+
+    let interval = if config.rate == 0 { Duration::ZERO } else { Duration::from_micros(1_000_000 / config.rate as u64) };
+    let arp_config = ArpScanConfig {
+        cidr: String::new(),
+        timeout_ms: config.timeout_ms,
+        retries: config.retries,
+        interval_us: interval.as_micros() as u64,
+    };
+
+    // ARP 主机发现直接使用 ARP 解析的结果
+    if config.scan_type == ScanType::ArpPing {
+        let hosts = arp::arp_resolve(interface_name, &targets, &arp_config, running)?;
+        let found: HashMap<String, arp::ArpHost> = hosts.into_iter().map(|h| (h.ip.clone(), h)).collect();
+        let mut out = results.lock().map_err(|_| anyhow!("扫描结果不可用"))?;
+        for target in &targets {
+            let host = found.get(&target.to_string());
+            out.push(ScanResult {
+                host: target.to_string(),
+                port: None,
+                protocol: "arp".to_string(),
+                state: if host.is_some() { ScanState::Up } else { ScanState::Down },
+                rtt_ms: host.map(|h| h.rtt_ms),
+                mac: host.map(|h| h.mac.clone()),
+            });
+        }
+        if let Ok(mut s) = status.lock() {
+            s.sent_probes = total;
+            s.responses = found.len() as u64;
+        }
+        return Ok(());
+    }
+
+    let (local_mac, local_ip) = NetworkInterface::local_addresses(interface_name);
+    let local_mac = local_mac.ok_or_else(|| anyhow!("无法获取接口 {} 的 MAC 地址", interface_name))?;
+    let local_ip: Ipv4Addr = local_ip
+        .and_then(|ip| ip.parse().ok())
+        .ok_or_else(|| anyhow!("接口 {} 没有 IPv4 地址", interface_name))?;
+
+    // 下一跳 MAC：统一指定，或本网段目标逐个 ARP 解析、网段外目标使用网关 MAC
+    let resolved: HashMap<Ipv4Addr, String> = match &config.next_hop_mac {
+        Some(mac) if !mac.trim().is_empty() => targets.iter().map(|t| (*t, mac.trim().to_string())).collect(),
+        _ => arp::resolve_next_hops(interface_name, &targets, &arp_config, running)?,
+    };
+
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    let ctx = ProbeContext {
+        scan_type: config.scan_type,
+        local_ip,
+        src_port: 40000 + (seed % 20000) as u16,
+        icmp_id: (seed >> 8) as u16,
+    };
+    let filter = match config.scan_type {
+        ScanType::TcpSyn => format!("dst host {} and (tcp or icmp)", local_ip),
+        ScanType::Udp => format!("dst host {} and (udp or icmp)", local_ip),
+        _ => format!("dst host {} and icmp", local_ip),
+    };
+    let mut capture = NetworkInterface::open_probe_capture(interface_name, &filter)?;
+
+    let probes: Vec<ProbeKey> = targets.iter().flat_map(|t| ports.iter().map(move |p| (*t, *p))).collect();
+    let mut sent_at: HashMap<ProbeKey, Instant> = HashMap::new();
+    let mut answered: HashMap<ProbeKey, ScanResult> = HashMap::new();
+    let mut seq = 0u32;
+
+    let drain = |capture: &mut pcap::Capture<pcap::Active>, sent_at: &HashMap<ProbeKey, Instant>, answered: &mut HashMap<ProbeKey, ScanResult>| {
+        let mut any = false;
+        while let Ok(packet) = capture.next_packet() {
+            any = true;
+            let Some((key, state)) = classify_response(packet.data, &ctx) else { continue };
+            // 只接受本任务发出过的探测的首个应答
+            if answered.contains_key(&key) || !sent_at.contains_key(&key) {
+                continue;
+            }
+            let result = ScanResult {
+                host: key.0.to_string(),
+                port: key.1,
+                protocol: protocol_name(ctx.scan_type).to_string(),
+                state,
+                rtt_ms: sent_at.get(&key).map(|t| t.elapsed().as_secs_f64() * 1000.0),
+                mac: resolved.get(&key.0).cloned(),
+            };
+            if let Ok(mut out) = results.lock() {
+                out.push(result.clone());
+            }
+            if let Ok(mut s) = status.lock() {
+                s.responses += 1;
+            }
+            answered.insert(key, result);
+        }
+        any
+    };
+
+    'rounds: for _ in 0..=config.retries {
+        for key in &probes {
+            if !running.load(Ordering::Relaxed) {
+                break 'rounds;
+            }
+            if answered.contains_key(key) {
+                continue;
+            }
+            let dst_mac = resolved.get(&key.0).map(String::as_str).unwrap_or(BROADCAST_MAC);
+            let frame = build_probe(probe_packet(&ctx, *key, &local_mac, dst_mac, seq))?;
+            seq = seq.wrapping_add(1);
+            capture.sendpacket(frame).map_err(|e| anyhow!("发送探测报文失败: {}", e))?;
+            sent_at.insert(*key, Instant::now());
+            if let Ok(mut s) = status.lock() {
+                s.sent_probes += 1;
+            }
+            drain(&mut capture, &sent_at, &mut answered);
+            if !interval.is_zero() {
+                std::thread::sleep(interval);
+            }
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(config.timeout_ms);
+        while Instant::now() < deadline && answered.len() < probes.len() && running.load(Ordering::Relaxed) {
+            if !drain(&mut capture, &sent_at, &mut answered) {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+    }
+
+    // 已发送但无应答的探测按扫描类型给出结论；被中途停止而未发送的不计入
+    let mut out = results.lock().map_err(|_| anyhow!("扫描结果不可用"))?;
+    for key in probes.iter().filter(|k| sent_at.contains_key(*k) && !answered.contains_key(*k)) {
+        out.push(ScanResult {
+            host: key.0.to_string(),
+            port: key.1,
+            protocol: protocol_name(ctx.scan_type).to_string(),
+            state: silent_state(ctx.scan_type),
+            rtt_ms: None,
+            mac: resolved.get(&key.0).cloned(),
+        });
+    }
+    out.sort_by_key(|r| (r.host.parse::<Ipv4Addr>().map(u32::from).unwrap_or(0), r.port));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 10);
+
+    fn ctx(scan_type: ScanType) -> ProbeContext {
+        ProbeContext { scan_type, local_ip: LOCAL, src_port: 45000, icmp_id: 0x1234 }
+    }
+
+    // 由目标 192.168.1.20 发回本机的报文
+    fn reply(protocol: &str, fields: Vec<(&str, &str)>) -> Vec<u8> {
+        crate::network::packet_builder::test_frame(protocol, "192.168.1.20", &LOCAL.to_string(), fields)
+    }
+
+    #[test]
+    fn port_and_target_lists() {
+        assert_eq!(parse_ports("22, 80,79-81").unwrap(), vec![22, 80, 79, 81]);
+        assert!(parse_ports("0").is_err());
+        assert!(parse_ports("90-80").is_err());
+        assert!(parse_ports("").is_err());
+        let targets = parse_targets("10.0.0.1, 10.0.0.0/30").unwrap();
+        assert_eq!(targets, vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)]);
+    }
+
+    #[test]
+    fn probes_carry_valid_checksums() {
+        let c = ctx(ScanType::TcpSyn);
+        let frame = build_probe(probe_packet(&c, (Ipv4Addr::new(192, 168, 1, 20), Some(443)), "00:11:22:33:44:55", BROADCAST_MAC, 7)).unwrap();
+        let tcp = &frame[34..];
+        assert_eq!(u16::from_be_bytes([tcp[0], tcp[1]]), 45000);
+        assert_eq!(u16::from_be_bytes([tcp[2], tcp[3]]), 443);
+        assert_eq!(tcp[13], 0x02);
+        assert_ne!(&tcp[16..18], &[0, 0]);
+    }
+
+    #[test]
+    fn classifies_tcp_replies() {
+        let c = ctx(ScanType::TcpSyn);
+        let target = Ipv4Addr::new(192, 168, 1, 20);
+        let syn_ack = reply("tcp", vec![("srcPort", "443"), ("dstPort", "45000"), ("flag_syn", "1"), ("flag_ack", "1")]);
+        assert_eq!(classify_response(&syn_ack, &c), Some(((target, Some(443)), ScanState::Open)));
+
+        let rst = reply("tcp", vec![("srcPort", "22"), ("dstPort", "45000"), ("flag_syn", "0"), ("flag_rst", "1"), ("flag_ack", "1")]);
+        assert_eq!(classify_response(&rst, &c), Some(((target, Some(22)), ScanState::Closed)));
+
+        // 其他连接的报文不属于本任务
+        let other = reply("tcp", vec![("srcPort", "443"), ("dstPort", "50000"), ("flag_ack", "1")]);
+        assert_eq!(classify_response(&other, &c), None);
+    }
+
+    #[test]
+    fn classifies_icmp_unreachable_for_udp() {
+        let c = ctx(ScanType::Udp);
+        // 原始数据报：本机 -> 192.168.1.30:161
+        let unreachable = reply(
+            "icmp",
+            vec![
+                ("icmp_type", "03"),
+                ("icmp_code", "03"),
+                ("orig_protocol", "17"),
+                ("orig_dst_ip", "192.168.1.30"),
+                ("orig_src_port", "45000"),
+                ("orig_dst_port", "161"),
+            ],
+        );
+        assert_eq!(
+            classify_response(&unreachable, &c),
+            Some(((Ipv4Addr::new(192, 168, 1, 30), Some(161)), ScanState::Closed))
+        );
+
+        let udp = reply("udp", vec![("srcPort", "53"), ("dstPort", "45000")]);
+        assert_eq!(classify_response(&udp, &c), Some(((Ipv4Addr::new(192, 168, 1, 20), Some(53)), ScanState::Open)));
+    }
+
+    #[test]
+    fn classifies_echo_reply_by_identifier() {
+        let c = ctx(ScanType::IcmpPing);
+        let echo = reply("icmp", vec![("icmp_type", "00"), ("identifier", "1234")]);
+        assert_eq!(classify_response(&echo, &c), Some(((Ipv4Addr::new(192, 168, 1, 20), None), ScanState::Up)));
+        let foreign = reply("icmp", vec![("icmp_type", "00"), ("identifier", "9999")]);
+        assert_eq!(classify_response(&foreign, &c), None);
+        assert_eq!(silent_state(ScanType::Udp), ScanState::OpenFiltered);
+    }
+
+    #[test]
+    fn finished_tasks_are_pruned_after_retention() {
+        use crate::network::FINISHED_TASK_RETENTION_SECS;
+
+        let handle = |completed_at| {
+            let mut status = new_status("t", ScanType::IcmpPing);
+            status.completed_at = completed_at;
+            ScanTaskHandle {
+                status: Arc::new(Mutex::new(status)),
+                results: Arc::new(Mutex::new(Vec::new())),
+                running: Arc::new(AtomicBool::new(false)),
+            }
+        };
+        let mut tasks = HashMap::new();
+        tasks.insert("running".to_string(), handle(None));
+        tasks.insert("recent".to_string(), handle(Some(1_000)));
+        tasks.insert("expired".to_string(), handle(Some(1_000 - FINISHED_TASK_RETENTION_SECS)));
+        crate::network::prune_finished(&mut tasks, 1_000, |h: &ScanTaskHandle| h.status.lock().unwrap().completed_at);
+
+        let mut left: Vec<&String> = tasks.keys().collect();
+        left.sort();
+        assert_eq!(left, vec!["recent", "running"]);
+    }
+}
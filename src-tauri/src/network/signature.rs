@@ -0,0 +1,302 @@
+use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use crate::network::packet_sniffer::CapturedPacket;
+use crate::network::rtt_stats::{RttAccumulator, RttSummary};
+
+// 签名追加在帧末尾：魔数 + 流 ID + 序号 + 发送时间，均为网络字节序。
+// 共 18 字节，UDP/IPv4 模板加签名恰好放入 64 字节帧
+pub const SIGNATURE_MAGIC: [u8; 2] = *b"BS";
+pub const SIGNATURE_LEN: usize = 18;
+// 发送结束后继续匹配在途报文的时间
+const LINGER: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub stream_id: u32,
+    pub sequence: u64,
+    // Unix 纳秒时间的低 32 位，按回绕差值计算时延，适用于 4.29 秒以内的时延
+    pub timestamp_ns: u32,
+}
+
+impl Signature {
+    pub fn encode(&self) -> [u8; SIGNATURE_LEN] {
+        let mut out = [0u8; SIGNATURE_LEN];
+        out[..2].copy_from_slice(&SIGNATURE_MAGIC);
+        out[2..6].copy_from_slice(&self.stream_id.to_be_bytes());
+        out[6..14].copy_from_slice(&self.sequence.to_be_bytes());
+        out[14..18].copy_from_slice(&self.timestamp_ns.to_be_bytes());
+        out
+    }
+
+    // 从帧尾向前查找指定流的签名，跳过以太网填充等尾部数据
+    pub fn find(data: &[u8], stream_id: u32) -> Option<Signature> {
+        let id = stream_id.to_be_bytes();
+        (0..=data.len().checked_sub(SIGNATURE_LEN)?)
+            .rev()
+            .find(|&i| data[i..i + 2] == SIGNATURE_MAGIC && data[i + 2..i + 6] == id)
+            .map(|i| Signature {
+                stream_id,
+                sequence: u64::from_be_bytes(data[i + 6..i + 14].try_into().unwrap_or_default()),
+                timestamp_ns: u32::from_be_bytes(data[i + 14..i + 18].try_into().unwrap_or_default()),
+            })
+    }
+}
+
+fn now_ns() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u32
+}
+
+// 一个批量发送任务的签名流，序号在所有发送线程间递增
+#[derive(Debug)]
+pub struct SignatureStream {
+    stream_id: u32,
+    next_sequence: AtomicU64,
+    // 已分配序号但本地发送失败的报文数，不计入丢包
+    send_failures: AtomicU64,
+}
+
+impl SignatureStream {
+    pub fn new(stream_id: u32) -> Self {
+        Self { stream_id, next_sequence: AtomicU64::new(0), send_failures: AtomicU64::new(0) }
+    }
+
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    // 带签名成功交给网卡的报文数
+    pub fn sent(&self) -> u64 {
+        self.allocated().saturating_sub(self.send_failures())
+    }
+
+    pub fn send_failures(&self) -> u64 {
+        self.send_failures.load(Ordering::Relaxed)
+    }
+
+    // stamp 分配序号后本地发送失败时调用，该序号不再出现在线路上
+    pub fn send_failed(&self) {
+        self.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn allocated(&self) -> u64 {
+        self.next_sequence.load(Ordering::Relaxed)
+    }
+
+    // 复制帧并在末尾追加签名，IPv4 帧的长度和校验和需由调用方修正
+    pub fn stamp(&self, frame: &[u8]) -> Vec<u8> {
+        let signature = Signature {
+            stream_id: self.stream_id,
+            sequence: self.next_sequence.fetch_add(1, Ordering::Relaxed),
+            timestamp_ns: now_ns(),
+        };
+        let mut out = Vec::with_capacity(frame.len() + SIGNATURE_LEN);
+        out.extend_from_slice(frame);
+        out.extend_from_slice(&signature.encode());
+        out
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SignatureReport {
+    pub stream_id: u32,
+    pub sent: u64,
+    // 本地发送失败的报文数，不计入 sent 和 lost
+    pub send_failures: u64,
+    // 捕获侧订阅通道溢出丢弃的报文数（含非本流报文），不为 0 时 lost 可能偏高
+    pub capture_dropped: u64,
+    // 收到的不同序号数，重复报文不计入
+    pub received: u64,
+    // 发送中途查询时包含仍在途的报文
+    pub lost: u64,
+    pub loss_percent: f64,
+    pub duplicates: u64,
+    // 序号小于此前已收到的最大序号的报文数
+    pub reordered: u64,
+    // 单向时延（毫秒），发送和捕获使用同一主机时钟；其中的丢包字段不使用
+    pub latency: RttSummary,
+}
+
+// 捕获侧的签名匹配状态
+#[derive(Debug)]
+pub struct SignatureTracker {
+    stream: Arc<SignatureStream>,
+    // 捕获流订阅的溢出计数
+    capture_dropped: Arc<AtomicU64>,
+    // 已收到序号的位图
+    seen: Vec<u64>,
+    received: u64,
+    highest: Option<u64>,
+    duplicates: u64,
+    reordered: u64,
+    latency: RttAccumulator,
+}
+
+impl SignatureTracker {
+    pub fn new(stream: Arc<SignatureStream>, capture_dropped: Arc<AtomicU64>) -> Self {
+        Self {
+            stream,
+            capture_dropped,
+            seen: Vec::new(),
+            received: 0,
+            highest: None,
+            duplicates: 0,
+            reordered: 0,
+            latency: RttAccumulator::new(),
+        }
+    }
+
+    pub fn stream(&self) -> Arc<SignatureStream> {
+        self.stream.clone()
+    }
+
+    // 匹配一个捕获到的报文，不属于本流时返回 false
+    pub fn record(&mut self, packet: &CapturedPacket) -> bool {
+        self.record_frame(&packet.raw_data, packet.timestamp_us)
+    }
+
+    // timestamp_us 为捕获时间（Unix 微秒）
+    pub fn record_frame(&mut self, data: &[u8], timestamp_us: u64) -> bool {
+        let Some(signature) = Signature::find(data, self.stream.stream_id) else {
+            return false;
+        };
+        // 未分配过的序号只能是其他来源的数据碰巧匹配
+        if signature.sequence >= self.stream.allocated() {
+            return false;
+        }
+
+        let (word, bit) = ((signature.sequence / 64) as usize, signature.sequence % 64);
+        if self.seen.len() <= word {
+            self.seen.resize(word + 1, 0);
+        }
+        if self.seen[word] & (1 << bit) != 0 {
+            self.duplicates += 1;
+            return true;
+        }
+        self.seen[word] |= 1 << bit;
+        self.received += 1;
+
+        match self.highest {
+            Some(highest) if signature.sequence < highest => self.reordered += 1,
+            _ => self.highest = Some(signature.sequence),
+        }
+        let latency_ns = ((timestamp_us * 1000) as u32).wrapping_sub(signature.timestamp_ns);
+        self.latency.record(Some(latency_ns as f64 / 1_000_000.0));
+        true
+    }
+
+    pub fn report(&self) -> SignatureReport {
+        let sent = self.stream.sent();
+        let lost = sent.saturating_sub(self.received);
+        SignatureReport {
+            stream_id: self.stream.stream_id,
+            sent,
+            send_failures: self.stream.send_failures(),
+            capture_dropped: self.capture_dropped.load(Ordering::Relaxed),
+            received: self.received,
+            lost,
+            loss_percent: if sent == 0 { 0.0 } else { lost as f64 * 100.0 / sent as f64 },
+            duplicates: self.duplicates,
+            reordered: self.reordered,
+            latency: self.latency.summary(),
+        }
+    }
+}
+
+// 从捕获流中匹配签名，发送结束并等待在途报文后退出；捕获会话关闭时也会退出
+pub fn run_signature_matcher(receiver: Receiver<CapturedPacket>, tracker: Arc<Mutex<SignatureTracker>>, sending: Arc<AtomicBool>) {
+    let mut deadline: Option<Instant> = None;
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(packet) => {
+                if let Ok(mut tracker) = tracker.lock() {
+                    tracker.record(&packet);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if sending.load(Ordering::Relaxed) {
+            continue;
+        }
+        match deadline {
+            Some(deadline) if Instant::now() >= deadline => break,
+            Some(_) => {}
+            None => deadline = Some(Instant::now() + LINGER),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captured(raw_data: Vec<u8>, timestamp_us: u64) -> CapturedPacket {
+        CapturedPacket {
+            timestamp: timestamp_us / 1000,
+            protocol: "udp".to_string(),
+            timestamp_us,
+            ..CapturedPacket::from_frame(raw_data)
+        }
+    }
+
+    // 替换签名中的发送时间，便于断言时延
+    fn with_tx_time(mut frame: Vec<u8>, timestamp_ns: u32) -> Vec<u8> {
+        let start = frame.len() - 4;
+        frame[start..].copy_from_slice(&timestamp_ns.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn signature_found_before_padding() {
+        let stream = SignatureStream::new(0xABCD);
+        let mut frame = stream.stamp(&[0x11; 20]);
+        frame.extend_from_slice(&[0; 16]);
+        let signature = Signature::find(&frame, 0xABCD).unwrap();
+        assert_eq!(signature.sequence, 0);
+        assert!(Signature::find(&frame, 0xABCE).is_none());
+        assert!(Signature::find(&frame[..10], 0xABCD).is_none());
+        assert_eq!(stream.sent(), 1);
+
+        // 发送时间戳在 32 位处回绕
+        let stream = Arc::new(SignatureStream::new(1));
+        let frame = with_tx_time(stream.stamp(&[0x11; 20]), 4_294_668_000);
+        let mut tracker = SignatureTracker::new(stream, Arc::new(AtomicU64::new(0)));
+        assert!(tracker.record_frame(&frame, 4_295_468));
+        assert_eq!(tracker.report().latency.max, 0.8);
+    }
+
+    #[test]
+    fn tracks_loss_duplicates_reordering_and_latency() {
+        let stream = Arc::new(SignatureStream::new(7));
+        let frames: Vec<Vec<u8>> = (0..5).map(|_| stream.stamp(&[0x22; 30])).collect();
+        let capture_dropped = Arc::new(AtomicU64::new(0));
+        let mut tracker = SignatureTracker::new(stream.clone(), capture_dropped.clone());
+
+        // 序号 0、2、1、2，序号 3、4 丢失
+        for (index, rx_us) in [(0, 1_500), (2, 3_800), (1, 3_900), (2, 4_000)] {
+            let frame = with_tx_time(frames[index].clone(), 1_000_000 * (index as u32 + 1));
+            assert!(tracker.record(&captured(frame, rx_us)));
+        }
+        assert!(!tracker.record(&captured(vec![0x22; 60], 4_000)));
+        // 超出已发送范围的序号不计入
+        let bogus = Signature { stream_id: 7, sequence: 99, timestamp_ns: 0 }.encode().to_vec();
+        assert!(!tracker.record(&captured(bogus, 4_000)));
+
+        let report = tracker.report();
+        assert_eq!((report.sent, report.received, report.lost), (5, 3, 2));
+        assert_eq!(report.loss_percent, 40.0);
+        assert_eq!((report.duplicates, report.reordered), (1, 1));
+        assert_eq!((report.latency.min, report.latency.max), (0.5, 1.9));
+
+        // 本地发送失败的序号不算作丢包，订阅溢出单独报告
+        stream.stamp(&[0x22; 30]);
+        stream.send_failed();
+        capture_dropped.fetch_add(3, Ordering::Relaxed);
+        let report = tracker.report();
+        assert_eq!((report.sent, report.send_failures, report.lost), (5, 1, 2));
+        assert_eq!(report.capture_dropped, 3);
+    }
+}
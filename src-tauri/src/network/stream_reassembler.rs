@@ -0,0 +1,696 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use crate::network::CapturedPacket;
+
+// 流表容量限制，防止长时间捕获时内存无限增长
+const MAX_FLOWS: usize = 10000;
+// 单方向最多保留的重组数据量
+const MAX_STREAM_BYTES: usize = 16 * 1024 * 1024;
+// 所有会话重组数据和乱序缓存的总量上限，超出后淘汰最久未活动的会话
+const MAX_TOTAL_BYTES: usize = 256 * 1024 * 1024;
+// 单方向最多缓存的乱序分段数，超过后跳过缺口继续重组
+const MAX_PENDING_SEGMENTS: usize = 512;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamConversation {
+    pub flow_id: u64,
+    pub protocol: String, // tcp / udp
+    pub client_ip: String,
+    pub client_port: u16,
+    pub server_ip: String,
+    pub server_port: u16,
+    pub packets: u64,
+    pub client_bytes: u64, // 客户端 -> 服务端的载荷字节数（去除重传）
+    pub server_bytes: u64, // 服务端 -> 客户端的载荷字节数（去除重传）
+    pub retransmissions: u64,
+    pub out_of_order: u64,
+    pub missing_bytes: u64, // 因丢包无法重组而跳过的字节数
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub closed: bool, // 已观察到 FIN 或 RST
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamChunk {
+    pub direction: String, // client / server
+    pub timestamp: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FollowedStream {
+    pub conversation: StreamConversation,
+    pub client_payload: Vec<u8>,
+    pub server_payload: Vec<u8>,
+    // 按到达顺序交错排列的数据块，用于"跟踪流"视图
+    pub chunks: Vec<StreamChunk>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Endpoint {
+    ip: [u8; 4],
+    port: u16,
+}
+
+// 无方向的五元组，两个端点按大小排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FlowKey {
+    protocol: u8,
+    a: Endpoint,
+    b: Endpoint,
+}
+
+impl FlowKey {
+    fn new(protocol: u8, src: Endpoint, dst: Endpoint) -> Self {
+        let (a, b) = if (src.ip, src.port) <= (dst.ip, dst.port) { (src, dst) } else { (dst, src) };
+        Self { protocol, a, b }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Client,
+    Server,
+}
+
+// 传输层分段解析结果
+struct Segment<'a> {
+    protocol: u8,
+    src: Endpoint,
+    dst: Endpoint,
+    seq: u32,
+    syn: bool,
+    ack: bool,
+    fin: bool,
+    rst: bool,
+    payload: &'a [u8],
+}
+
+// 单方向的重组状态
+#[derive(Default)]
+struct HalfStream {
+    isn: Option<u32>,  // 数据起始序列号（SYN 序列号 + 1，或首个分段的序列号）
+    delivered: u64,    // 已按序交付的相对偏移（包含跳过的缺口）
+    data: Vec<u8>,
+    pending: BTreeMap<u64, Vec<u8>>,
+    pending_bytes: usize,
+    total_bytes: u64,
+    truncated: bool,
+}
+
+// 交付顺序记录：(方向, 时间戳, 数据起点, 长度)
+type ChunkRecord = (Direction, u64, usize, usize);
+
+struct FlowState {
+    conversation: StreamConversation,
+    key: FlowKey,
+    client: Endpoint,
+    client_half: HalfStream,
+    server_half: HalfStream,
+    chunks: Vec<ChunkRecord>,
+    // 最近一次活动在 FlowTable::activity 中的序号
+    last_active: u64,
+}
+
+pub struct FlowTable {
+    flows: HashMap<u64, FlowState>,
+    index: HashMap<FlowKey, u64>,
+    next_id: u64,
+    // 按活动先后排列的会话（活动序号 -> 会话 ID），首项为最久未活动的会话
+    activity: BTreeMap<u64, u64>,
+    next_activity: u64,
+    // 所有会话占用的重组数据和乱序缓存字节数
+    stored_bytes: usize,
+    byte_budget: usize,
+}
+
+impl FlowTable {
+    pub fn new() -> Self {
+        Self::with_byte_budget(MAX_TOTAL_BYTES)
+    }
+
+    fn with_byte_budget(byte_budget: usize) -> Self {
+        Self {
+            flows: HashMap::new(),
+            index: HashMap::new(),
+            next_id: 1,
+            activity: BTreeMap::new(),
+            next_activity: 0,
+            stored_bytes: 0,
+            byte_budget,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.flows.clear();
+        self.index.clear();
+        self.next_id = 1;
+        self.activity.clear();
+        self.stored_bytes = 0;
+    }
+
+    pub fn process_packet(&mut self, packet: &CapturedPacket) {
+        self.process_frame(&packet.raw_data, packet.timestamp);
+    }
+
+    pub fn process_frame(&mut self, data: &[u8], timestamp: u64) {
+        let segment = match Self::parse_segment(data) {
+            Some(s) => s,
+            None => return,
+        };
+
+        let key = FlowKey::new(segment.protocol, segment.src, segment.dst);
+
+        // 已关闭的 TCP 连接上出现新的 SYN，视为端口复用，开启新的会话
+        let reuse = segment.protocol == 6 && segment.syn && !segment.ack;
+        let existing = self.index.get(&key).copied().filter(|id| {
+            !(reuse && self.flows.get(id).is_some_and(|f| f.conversation.closed))
+        });
+
+        let flow_id = match existing {
+            Some(id) => id,
+            None => self.create_flow(key, &segment, timestamp),
+        };
+
+        let Some(flow) = self.flows.get_mut(&flow_id) else {
+            return;
+        };
+        let before = flow.stored_bytes();
+        flow.process(&segment, timestamp);
+        self.stored_bytes = self.stored_bytes - before + flow.stored_bytes();
+
+        self.activity.remove(&flow.last_active);
+        flow.last_active = self.next_activity;
+        self.activity.insert(self.next_activity, flow_id);
+        self.next_activity += 1;
+
+        // 当前会话最后活动，排在最后，不会被淘汰
+        while self.stored_bytes > self.byte_budget && self.flows.len() > 1 {
+            self.evict_oldest();
+        }
+    }
+
+    pub fn conversations(&self) -> Vec<StreamConversation> {
+        let mut list: Vec<StreamConversation> = self.flows.values()
+            .map(|f| f.conversation.clone())
+            .collect();
+        list.sort_by_key(|c| c.flow_id);
+        list
+    }
+
+    pub fn follow(&self, flow_id: u64) -> Option<FollowedStream> {
+        let flow = self.flows.get(&flow_id)?;
+
+        let chunks = flow.chunks.iter()
+            .map(|(direction, timestamp, start, len)| {
+                let half = match direction {
+                    Direction::Client => &flow.client_half,
+                    Direction::Server => &flow.server_half,
+                };
+                StreamChunk {
+                    direction: match direction {
+                        Direction::Client => "client".to_string(),
+                        Direction::Server => "server".to_string(),
+                    },
+                    timestamp: *timestamp,
+                    data: half.data[*start..*start + *len].to_vec(),
+                }
+            })
+            .collect();
+
+        Some(FollowedStream {
+            conversation: flow.conversation.clone(),
+            client_payload: flow.client_half.data.clone(),
+            server_payload: flow.server_half.data.clone(),
+            chunks,
+            truncated: flow.client_half.truncated || flow.server_half.truncated,
+        })
+    }
+
+    fn create_flow(&mut self, key: FlowKey, segment: &Segment, timestamp: u64) -> u64 {
+        if self.flows.len() >= MAX_FLOWS {
+            self.evict_oldest();
+        }
+
+        // 发起方判定：SYN-ACK 的接收方是客户端，其余情况以首个报文的发送方为客户端
+        let (client, server) = if segment.protocol == 6 && segment.syn && segment.ack {
+            (segment.dst, segment.src)
+        } else {
+            (segment.src, segment.dst)
+        };
+
+        let flow_id = self.next_id;
+        self.next_id += 1;
+
+        let conversation = StreamConversation {
+            flow_id,
+            protocol: if segment.protocol == 6 { "tcp".to_string() } else { "udp".to_string() },
+            client_ip: Self::format_ip(&client.ip),
+            client_port: client.port,
+            server_ip: Self::format_ip(&server.ip),
+            server_port: server.port,
+            packets: 0,
+            client_bytes: 0,
+            server_bytes: 0,
+            retransmissions: 0,
+            out_of_order: 0,
+            missing_bytes: 0,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            closed: false,
+        };
+
+        self.flows.insert(flow_id, FlowState {
+            conversation,
+            key,
+            client,
+            client_half: HalfStream::default(),
+            server_half: HalfStream::default(),
+            chunks: Vec::new(),
+            last_active: self.next_activity,
+        });
+        self.activity.insert(self.next_activity, flow_id);
+        self.next_activity += 1;
+        self.index.insert(key, flow_id);
+        flow_id
+    }
+
+    fn evict_oldest(&mut self) {
+        let Some((_, id)) = self.activity.pop_first() else {
+            return;
+        };
+        if let Some(flow) = self.flows.remove(&id) {
+            self.stored_bytes -= flow.stored_bytes();
+            if self.index.get(&flow.key) == Some(&id) {
+                self.index.remove(&flow.key);
+            }
+        }
+    }
+
+    fn parse_segment(data: &[u8]) -> Option<Segment<'_>> {
+        if data.len() < 14 + 20 || data[12] != 0x08 || data[13] != 0x00 {
+            return None;
+        }
+
+        let ip = &data[14..];
+        let header_len = ((ip[0] & 0x0f) as usize) * 4;
+        let total_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+        // 分片报文不参与重组
+        if u16::from_be_bytes([ip[6], ip[7]]) & 0x3fff != 0 {
+            return None;
+        }
+        // 以 IPv4 总长度为准，去掉以太网填充
+        let ip_end = total_len.min(ip.len());
+        if header_len < 20 || ip_end < header_len {
+            return None;
+        }
+
+        let protocol = ip[9];
+        let mut src_ip = [0u8; 4];
+        let mut dst_ip = [0u8; 4];
+        src_ip.copy_from_slice(&ip[12..16]);
+        dst_ip.copy_from_slice(&ip[16..20]);
+        let transport = &ip[header_len..ip_end];
+
+        match protocol {
+            6 if transport.len() >= 20 => {
+                let tcp_header_len = ((transport[12] >> 4) as usize) * 4;
+                if tcp_header_len < 20 || transport.len() < tcp_header_len {
+                    return None;
+                }
+                let flags = transport[13];
+                Some(Segment {
+                    protocol,
+                    src: Endpoint { ip: src_ip, port: u16::from_be_bytes([transport[0], transport[1]]) },
+                    dst: Endpoint { ip: dst_ip, port: u16::from_be_bytes([transport[2], transport[3]]) },
+                    seq: u32::from_be_bytes([transport[4], transport[5], transport[6], transport[7]]),
+                    syn: flags & 0x02 != 0,
+                    ack: flags & 0x10 != 0,
+                    fin: flags & 0x01 != 0,
+                    rst: flags & 0x04 != 0,
+                    payload: &transport[tcp_header_len..],
+                })
+            }
+            17 if transport.len() >= 8 => {
+                let udp_len = (u16::from_be_bytes([transport[4], transport[5]]) as usize)
+                    .clamp(8, transport.len());
+                Some(Segment {
+                    protocol,
+                    src: Endpoint { ip: src_ip, port: u16::from_be_bytes([transport[0], transport[1]]) },
+                    dst: Endpoint { ip: dst_ip, port: u16::from_be_bytes([transport[2], transport[3]]) },
+                    seq: 0,
+                    syn: false,
+                    ack: false,
+                    fin: false,
+                    rst: false,
+                    payload: &transport[8..udp_len],
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn format_ip(ip: &[u8; 4]) -> String {
+        format!("{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3])
+    }
+}
+
+impl Default for FlowTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlowState {
+    fn stored_bytes(&self) -> usize {
+        self.client_half.data.len() + self.client_half.pending_bytes
+            + self.server_half.data.len() + self.server_half.pending_bytes
+    }
+
+    fn process(&mut self, segment: &Segment, timestamp: u64) {
+        let direction = if segment.src == self.client { Direction::Client } else { Direction::Server };
+
+        self.conversation.packets += 1;
+        self.conversation.last_seen = timestamp;
+        if segment.fin || segment.rst {
+            self.conversation.closed = true;
+        }
+
+        if segment.protocol == 17 {
+            if !segment.payload.is_empty() {
+                self.deliver(direction, timestamp, segment.payload);
+            }
+            return;
+        }
+
+        self.process_tcp(direction, segment, timestamp);
+    }
+
+    fn process_tcp(&mut self, direction: Direction, segment: &Segment, timestamp: u64) {
+        let half = match direction {
+            Direction::Client => &mut self.client_half,
+            Direction::Server => &mut self.server_half,
+        };
+
+        // SYN 占用一个序列号，数据从 SYN 序列号 + 1 开始
+        let data_seq = if segment.syn { segment.seq.wrapping_add(1) } else { segment.seq };
+        if half.isn.is_none() && (segment.syn || !segment.payload.is_empty()) {
+            half.isn = Some(data_seq);
+        }
+
+        if segment.payload.is_empty() {
+            return;
+        }
+        let isn = match half.isn {
+            Some(isn) => isn,
+            None => return,
+        };
+
+        // 早于起始序列号的分段（回绕差值超过 2^31）视为旧重传
+        let relative = data_seq.wrapping_sub(isn);
+        if relative > i32::MAX as u32 {
+            self.conversation.retransmissions += 1;
+            return;
+        }
+        let offset = relative as u64;
+        let end = offset + segment.payload.len() as u64;
+
+        if end <= half.delivered {
+            self.conversation.retransmissions += 1;
+            return;
+        }
+
+        if offset > half.delivered {
+            // 乱序到达：缓存等待缺口补齐
+            match half.pending.get(&offset) {
+                Some(existing) if existing.len() >= segment.payload.len() => {
+                    self.conversation.retransmissions += 1;
+                }
+                _ => {
+                    half.pending_bytes += segment.payload.len();
+                    if let Some(replaced) = half.pending.insert(offset, segment.payload.to_vec()) {
+                        half.pending_bytes -= replaced.len();
+                    }
+                    self.conversation.out_of_order += 1;
+                }
+            }
+
+            if half.pending.len() > MAX_PENDING_SEGMENTS {
+                // 缺口长时间无法补齐（捕获丢包），跳过缺口继续重组
+                let first = *half.pending.keys().next().unwrap();
+                self.conversation.missing_bytes += first - half.delivered;
+                half.delivered = first;
+                self.drain_pending(direction, timestamp);
+            }
+            return;
+        }
+
+        // 与已交付数据部分重叠：裁掉重复部分
+        if offset < half.delivered {
+            self.conversation.retransmissions += 1;
+        }
+        let skip = (half.delivered - offset) as usize;
+        let payload = segment.payload[skip..].to_vec();
+        self.deliver(direction, timestamp, &payload);
+        self.drain_pending(direction, timestamp);
+    }
+
+    fn drain_pending(&mut self, direction: Direction, timestamp: u64) {
+        loop {
+            let half = self.half_mut(direction);
+            let (offset, data) = match half.pending.first_key_value() {
+                Some((offset, _)) if *offset <= half.delivered => half.pending.pop_first().unwrap(),
+                _ => break,
+            };
+            half.pending_bytes -= data.len();
+
+            let end = offset + data.len() as u64;
+            if end <= half.delivered {
+                continue;
+            }
+            let skip = (half.delivered - offset) as usize;
+            self.deliver(direction, timestamp, &data[skip..]);
+        }
+    }
+
+    fn deliver(&mut self, direction: Direction, timestamp: u64, payload: &[u8]) {
+        let half = self.half_mut(direction);
+        half.delivered += payload.len() as u64;
+        half.total_bytes += payload.len() as u64;
+        let total = half.total_bytes;
+
+        let start = half.data.len();
+        let room = MAX_STREAM_BYTES.saturating_sub(start);
+        let stored = payload.len().min(room);
+        if stored < payload.len() {
+            half.truncated = true;
+        }
+        half.data.extend_from_slice(&payload[..stored]);
+
+        match direction {
+            Direction::Client => self.conversation.client_bytes = total,
+            Direction::Server => self.conversation.server_bytes = total,
+        }
+
+        if stored == 0 {
+            return;
+        }
+
+        // 同方向连续交付的数据合并为一个数据块
+        match self.chunks.last_mut() {
+            Some((last_dir, _, last_start, last_len)) if *last_dir == direction && *last_start + *last_len == start => {
+                *last_len += stored;
+            }
+            _ => self.chunks.push((direction, timestamp, start, stored)),
+        }
+    }
+
+    fn half_mut(&mut self, direction: Direction) -> &mut HalfStream {
+        match direction {
+            Direction::Client => &mut self.client_half,
+            Direction::Server => &mut self.server_half,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    fn tcp_frame(src: [u8; 4], dst: [u8; 4], sport: u16, dport: u16, seq: u32, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let total_len = (20 + 20 + payload.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0x00]);
+        frame.extend_from_slice(&total_len.to_be_bytes());
+        frame.extend_from_slice(&[0x00, 0x00, 0x40, 0x00, 64, 6, 0x00, 0x00]);
+        frame.extend_from_slice(&src);
+        frame.extend_from_slice(&dst);
+        frame.extend_from_slice(&sport.to_be_bytes());
+        frame.extend_from_slice(&dport.to_be_bytes());
+        frame.extend_from_slice(&seq.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0]);
+        frame.extend_from_slice(&[0x50, flags, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn udp_frame(src: [u8; 4], dst: [u8; 4], sport: u16, dport: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let total_len = (20 + 8 + payload.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0x00]);
+        frame.extend_from_slice(&total_len.to_be_bytes());
+        frame.extend_from_slice(&[0x00, 0x00, 0x40, 0x00, 64, 17, 0x00, 0x00]);
+        frame.extend_from_slice(&src);
+        frame.extend_from_slice(&dst);
+        frame.extend_from_slice(&sport.to_be_bytes());
+        frame.extend_from_slice(&dport.to_be_bytes());
+        frame.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        frame.extend_from_slice(&[0x00, 0x00]);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    const SYN: u8 = 0x02;
+    const ACK: u8 = 0x10;
+    const PSH_ACK: u8 = 0x18;
+
+    fn handshake(table: &mut FlowTable) {
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1000, SYN, &[]), 1);
+        table.process_frame(&tcp_frame(SERVER, CLIENT, 80, 40000, 5000, SYN | ACK, &[]), 2);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, ACK, &[]), 3);
+    }
+
+    #[test]
+    fn tcp_in_order_both_directions() {
+        let mut table = FlowTable::new();
+        handshake(&mut table);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, PSH_ACK, b"GET / "), 4);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1007, PSH_ACK, b"HTTP/1.1"), 5);
+        table.process_frame(&tcp_frame(SERVER, CLIENT, 80, 40000, 5001, PSH_ACK, b"200 OK"), 6);
+
+        let conversations = table.conversations();
+        assert_eq!(conversations.len(), 1);
+        let conv = &conversations[0];
+        assert_eq!(conv.client_ip, "10.0.0.1");
+        assert_eq!(conv.server_port, 80);
+        assert_eq!(conv.client_bytes, 14);
+        assert_eq!(conv.server_bytes, 6);
+
+        let stream = table.follow(conv.flow_id).unwrap();
+        assert_eq!(stream.client_payload, b"GET / HTTP/1.1");
+        assert_eq!(stream.server_payload, b"200 OK");
+        assert_eq!(stream.chunks.len(), 2);
+        assert_eq!(stream.chunks[0].direction, "client");
+        assert_eq!(stream.chunks[1].data, b"200 OK");
+    }
+
+    #[test]
+    fn tcp_out_of_order_is_reordered() {
+        let mut table = FlowTable::new();
+        handshake(&mut table);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1004, PSH_ACK, b"def"), 4);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1007, PSH_ACK, b"ghi"), 5);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, PSH_ACK, b"abc"), 6);
+
+        let conv = &table.conversations()[0];
+        assert_eq!(conv.out_of_order, 2);
+        assert_eq!(table.follow(conv.flow_id).unwrap().client_payload, b"abcdefghi");
+    }
+
+    #[test]
+    fn tcp_retransmissions_are_deduplicated() {
+        let mut table = FlowTable::new();
+        handshake(&mut table);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, PSH_ACK, b"abcd"), 4);
+        // 完全重传
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, PSH_ACK, b"abcd"), 5);
+        // 部分重叠的重传，携带新数据
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1003, PSH_ACK, b"cdef"), 6);
+
+        let conv = &table.conversations()[0];
+        assert_eq!(conv.retransmissions, 2);
+        assert_eq!(conv.client_bytes, 6);
+        assert_eq!(table.follow(conv.flow_id).unwrap().client_payload, b"abcdef");
+    }
+
+    #[test]
+    fn tcp_sequence_wraparound() {
+        let mut table = FlowTable::new();
+        let isn = u32::MAX - 1;
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, isn, SYN, &[]), 1);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, u32::MAX, PSH_ACK, b"ab"), 2);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1, PSH_ACK, b"cd"), 3);
+
+        let conv = &table.conversations()[0];
+        assert_eq!(table.follow(conv.flow_id).unwrap().client_payload, b"abcd");
+    }
+
+    #[test]
+    fn tcp_port_reuse_after_close_starts_new_flow() {
+        let mut table = FlowTable::new();
+        handshake(&mut table);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, 0x11, &[]), 4);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 9000, SYN, &[]), 5);
+
+        let conversations = table.conversations();
+        assert_eq!(conversations.len(), 2);
+        assert!(conversations[0].closed);
+        assert!(!conversations[1].closed);
+    }
+
+    #[test]
+    fn udp_conversation_collects_datagrams() {
+        let mut table = FlowTable::new();
+        table.process_frame(&udp_frame(CLIENT, SERVER, 5353, 53, b"query"), 1);
+        table.process_frame(&udp_frame(SERVER, CLIENT, 53, 5353, b"answer"), 2);
+        table.process_frame(&udp_frame(CLIENT, SERVER, 5353, 53, b"again"), 3);
+
+        let conv = &table.conversations()[0];
+        assert_eq!(conv.protocol, "udp");
+        assert_eq!(conv.client_port, 5353);
+        assert_eq!(conv.packets, 3);
+
+        let stream = table.follow(conv.flow_id).unwrap();
+        assert_eq!(stream.client_payload, b"queryagain");
+        assert_eq!(stream.server_payload, b"answer");
+        assert_eq!(stream.chunks.len(), 3);
+    }
+
+    #[test]
+    fn byte_budget_evicts_least_recently_active_flow() {
+        let mut table = FlowTable::with_byte_budget(10);
+        table.process_frame(&udp_frame(CLIENT, SERVER, 1, 53, b"aaaa"), 1);
+        table.process_frame(&udp_frame(CLIENT, SERVER, 2, 53, b"bbbb"), 2);
+        // 较早创建的会话再次活动，淘汰的是端口 2 的会话
+        table.process_frame(&udp_frame(CLIENT, SERVER, 1, 53, b"a"), 3);
+        table.process_frame(&udp_frame(CLIENT, SERVER, 3, 53, b"cccc"), 4);
+
+        let ports: Vec<u16> = table.conversations().iter().map(|c| c.client_port).collect();
+        assert_eq!(ports, vec![1, 3]);
+        assert_eq!(table.stored_bytes, 9);
+
+        // 乱序缓存也计入总量，交付后不重复计算
+        let mut table = FlowTable::new();
+        handshake(&mut table);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1004, PSH_ACK, b"def"), 4);
+        assert_eq!(table.stored_bytes, 3);
+        table.process_frame(&tcp_frame(CLIENT, SERVER, 40000, 80, 1001, PSH_ACK, b"abc"), 5);
+        assert_eq!(table.stored_bytes, 6);
+    }
+
+    #[test]
+    fn ethernet_padding_is_not_payload() {
+        let mut table = FlowTable::new();
+        let mut frame = udp_frame(CLIENT, SERVER, 1000, 2000, b"x");
+        frame.resize(60, 0);
+        table.process_frame(&frame, 1);
+        let conv = &table.conversations()[0];
+        assert_eq!(table.follow(conv.flow_id).unwrap().client_payload, b"x");
+    }
+}
@@ -0,0 +1,244 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::fmt;
+
+// TCP 头部最多 40 字节选项
+pub const MAX_OPTIONS_LEN: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TcpOption {
+    Eol,
+    Nop,
+    Mss { value: u16 },
+    WindowScale { shift: u8 },
+    SackPermitted,
+    Sack { blocks: Vec<(u32, u32)> },
+    Timestamp { value: u32, echo: u32 },
+    // length 为空时按 value 长度 + 2 计算，填写时可构造长度不符的畸形选项
+    Raw { kind: u8, length: Option<u8>, value: Vec<u8> },
+}
+
+impl TcpOption {
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            TcpOption::Eol => out.push(0),
+            TcpOption::Nop => out.push(1),
+            TcpOption::Mss { value } => {
+                out.extend_from_slice(&[2, 4]);
+                out.extend_from_slice(&value.to_be_bytes());
+            }
+            TcpOption::WindowScale { shift } => out.extend_from_slice(&[3, 3, *shift]),
+            TcpOption::SackPermitted => out.extend_from_slice(&[4, 2]),
+            TcpOption::Sack { blocks } => {
+                out.extend_from_slice(&[5, (2 + blocks.len() * 8) as u8]);
+                for (left, right) in blocks {
+                    out.extend_from_slice(&left.to_be_bytes());
+                    out.extend_from_slice(&right.to_be_bytes());
+                }
+            }
+            TcpOption::Timestamp { value, echo } => {
+                out.extend_from_slice(&[8, 10]);
+                out.extend_from_slice(&value.to_be_bytes());
+                out.extend_from_slice(&echo.to_be_bytes());
+            }
+            TcpOption::Raw { kind, length, value } => {
+                out.push(*kind);
+                out.push(length.unwrap_or((value.len() + 2) as u8));
+                out.extend_from_slice(value);
+            }
+        }
+    }
+}
+
+impl fmt::Display for TcpOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TcpOption::Eol => write!(f, "EOL"),
+            TcpOption::Nop => write!(f, "NOP"),
+            TcpOption::Mss { value } => write!(f, "MSS={}", value),
+            TcpOption::WindowScale { shift } => write!(f, "WS={}", 1u32.checked_shl(*shift as u32).unwrap_or(0)),
+            TcpOption::SackPermitted => write!(f, "SACK_PERM"),
+            TcpOption::Sack { blocks } => {
+                let parts: Vec<String> = blocks.iter().map(|(l, r)| format!("SLE={} SRE={}", l, r)).collect();
+                write!(f, "{}", parts.join(" "))
+            }
+            TcpOption::Timestamp { value, echo } => write!(f, "TSval={} TSecr={}", value, echo),
+            TcpOption::Raw { kind, length, value } => {
+                let hex: String = value.iter().map(|b| format!("{:02x}", b)).collect();
+                write!(f, "Kind={} Len={} Data={}", kind, length.unwrap_or((value.len() + 2) as u8), hex)
+            }
+        }
+    }
+}
+
+// 编码选项并以 EOL(0) 补齐到 4 字节；超过 40 字节时报错
+pub fn encode_options(options: &[TcpOption]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for option in options {
+        option.encode_into(&mut out);
+    }
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+    if out.len() > MAX_OPTIONS_LEN {
+        return Err(anyhow!("TCP 选项共 {} 字节，超过 {} 字节上限", out.len(), MAX_OPTIONS_LEN));
+    }
+    Ok(out)
+}
+
+// 解析 TCP 头部中的选项字节；遇到 EOL 停止，长度非法的选项作为 Raw 保留剩余字节后停止
+pub fn decode_options(data: &[u8]) -> Vec<TcpOption> {
+    let mut options = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let kind = data[i];
+        match kind {
+            0 => {
+                options.push(TcpOption::Eol);
+                break;
+            }
+            1 => {
+                options.push(TcpOption::Nop);
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let len = data.get(i + 1).copied().unwrap_or(0) as usize;
+        if len < 2 || i + len > data.len() {
+            options.push(TcpOption::Raw {
+                kind,
+                length: data.get(i + 1).copied(),
+                value: data.get(i + 2..).unwrap_or(&[]).to_vec(),
+            });
+            break;
+        }
+
+        let body = &data[i + 2..i + len];
+        let option = match (kind, len) {
+            (2, 4) => TcpOption::Mss { value: u16::from_be_bytes([body[0], body[1]]) },
+            (3, 3) => TcpOption::WindowScale { shift: body[0] },
+            (4, 2) => TcpOption::SackPermitted,
+            (5, _) if (len - 2).is_multiple_of(8) && len > 2 => TcpOption::Sack {
+                blocks: body
+                    .chunks(8)
+                    .map(|c| (read_u32(&c[0..4]), read_u32(&c[4..8])))
+                    .collect(),
+            },
+            (8, 10) => TcpOption::Timestamp { value: read_u32(&body[0..4]), echo: read_u32(&body[4..8]) },
+            _ => TcpOption::Raw { kind, length: None, value: body.to_vec() },
+        };
+        options.push(option);
+        i += len;
+    }
+    options
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// 解析 tcp_options 字段的文本写法，逗号分隔，按顺序编码：
+// mss:1460, ws:7, sack_perm, sack:100-200;300-400, ts:1000[:0], nop, eol,
+// raw:<kind>[:<十六进制值>] 或 raw:<kind>:<长度>:<十六进制值>
+pub fn parse_options_spec(spec: &str) -> Result<Vec<TcpOption>> {
+    let mut options = Vec::new();
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let mut parts = item.split(':').map(str::trim);
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let args: Vec<&str> = parts.collect();
+        let invalid = || anyhow!("无效的 TCP 选项: {}", item);
+
+        let option = match (name.as_str(), args.as_slice()) {
+            ("eol", []) => TcpOption::Eol,
+            ("nop", []) => TcpOption::Nop,
+            ("mss", [v]) => TcpOption::Mss { value: v.parse().map_err(|_| invalid())? },
+            ("ws" | "wscale", [v]) => TcpOption::WindowScale { shift: v.parse().map_err(|_| invalid())? },
+            ("sack_perm" | "sackok", []) => TcpOption::SackPermitted,
+            ("sack", [blocks]) => {
+                let blocks = blocks
+                    .split(';')
+                    .map(|b| {
+                        let (l, r) = b.split_once('-')?;
+                        Some((l.trim().parse().ok()?, r.trim().parse().ok()?))
+                    })
+                    .collect::<Option<Vec<(u32, u32)>>>()
+                    .filter(|b| !b.is_empty() && b.len() <= 4)
+                    .ok_or_else(invalid)?;
+                TcpOption::Sack { blocks }
+            }
+            ("ts", [v]) => TcpOption::Timestamp { value: v.parse().map_err(|_| invalid())?, echo: 0 },
+            ("ts", [v, e]) => TcpOption::Timestamp {
+                value: v.parse().map_err(|_| invalid())?,
+                echo: e.parse().map_err(|_| invalid())?,
+            },
+            ("raw", [k]) => TcpOption::Raw { kind: k.parse().map_err(|_| invalid())?, length: None, value: Vec::new() },
+            ("raw", [k, hex]) => TcpOption::Raw {
+                kind: k.parse().map_err(|_| invalid())?,
+                length: None,
+                value: crate::network::packet_validation::parse_hex_data(hex).ok_or_else(invalid)?,
+            },
+            ("raw", [k, len, hex]) => TcpOption::Raw {
+                kind: k.parse().map_err(|_| invalid())?,
+                length: Some(len.parse().map_err(|_| invalid())?),
+                value: crate::network::packet_validation::parse_hex_data(hex).ok_or_else(invalid)?,
+            },
+            _ => return Err(invalid()),
+        };
+        options.push(option);
+    }
+    // 提前检查总长度
+    encode_options(&options)?;
+    Ok(options)
+}
+
+pub fn describe_options(options: &[TcpOption]) -> String {
+    options
+        .iter()
+        .filter(|o| !matches!(o, TcpOption::Eol | TcpOption::Nop))
+        .map(|o| o.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typical_syn_options_round_trip() {
+        let options = parse_options_spec("mss:1460, sack_perm, ts:12345:0, nop, ws:7").unwrap();
+        let bytes = encode_options(&options).unwrap();
+        assert_eq!(bytes.len(), 20);
+        assert_eq!(&bytes[..4], &[2, 4, 0x05, 0xB4]);
+        assert_eq!(&bytes[16..], &[1, 3, 3, 7]);
+        let decoded = decode_options(&bytes);
+        assert_eq!(decoded, options);
+        assert_eq!(describe_options(&decoded), "MSS=1460 SACK_PERM TSval=12345 TSecr=0 WS=128");
+    }
+
+    #[test]
+    fn sack_blocks_and_raw() {
+        let options = parse_options_spec("sack:100-200;300-400,raw:30:4:0102").unwrap();
+        let bytes = encode_options(&options).unwrap();
+        assert_eq!(bytes[1], 18);
+        assert_eq!(&bytes[18..22], &[30, 4, 1, 2]);
+        assert_eq!(decode_options(&bytes)[0], TcpOption::Sack { blocks: vec![(100, 200), (300, 400)] });
+    }
+
+    #[test]
+    fn rejects_bad_specs_and_oversize() {
+        assert!(parse_options_spec("mss:70000").is_err());
+        assert!(parse_options_spec("sack:1-2;3-4;5-6;7-8;9-10").is_err());
+        assert!(parse_options_spec("foo").is_err());
+        assert!(parse_options_spec("ts:1,ts:2,ts:3,ts:4,ts:5").is_err());
+    }
+
+    #[test]
+    fn malformed_length_stops_decoding() {
+        let decoded = decode_options(&[1, 2, 9, 0xAA]);
+        assert_eq!(decoded, vec![TcpOption::Nop, TcpOption::Raw { kind: 2, length: Some(9), value: vec![0xAA] }]);
+    }
+}
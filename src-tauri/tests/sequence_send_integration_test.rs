@@ -1,4 +1,4 @@
-use BitSender_lib::network::{PacketSequence, SequencePacket, SequenceTaskStatus};
+use BitSender_lib::network::{PacketBuilder, PacketData, PacketSequence, SequencePacket, SequenceTaskStatus};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
@@ -240,6 +240,27 @@ mod integration_tests {
         assert_eq!(delay_per_loop, 150); // 50 + 100
         assert_eq!(expected_min_time, 3 * 150 + 2 * 50); // 3轮 * 每轮150ms + 2个循环间隔 * 50ms
     }
+
+    #[test]
+    fn test_eth_packets_build_as_raw_frames() {
+        let sequence = create_integration_test_sequence();
+
+        for packet in &sequence.packets {
+            let payload = packet.payload.clone().unwrap();
+            let bytes = PacketBuilder::new(PacketData {
+                protocol: packet.protocol.clone(),
+                fields: packet.fields.clone(),
+                payload: Some(payload.clone()),
+            })
+            .build()
+            .expect("eth 数据包应按原始帧构建成功");
+
+            // 原始帧应与载荷完全一致
+            assert_eq!(bytes.len() * 2, payload.len());
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(hex, payload);
+        }
+    }
 }
 
 /// 验证MAC地址格式的辅助函数